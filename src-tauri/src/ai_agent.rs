@@ -1,6 +1,6 @@
-use rusqlite::{Connection, Result, params};
+use rusqlite::{Result, params};
 use serde::{Deserialize, Serialize};
-use tauri::State;
+use tauri::AppHandle;
 use std::sync::Mutex;
 use chrono::Local;
 use std::error::Error;
@@ -8,6 +8,10 @@ use std::fmt;
 use reqwest::Client;
 use serde_json::json;
 use crate::db;
+use crate::error::AppError;
+use crate::llm;
+use crate::jobs::{self, AiJobRequest, ArticleRequest};
+use crate::secrets;
 
 // Import SqliteState from main.rs
 use crate::SqliteState;
@@ -15,6 +19,9 @@ use crate::SqliteState;
 // Import Project type from db.rs
 use crate::db::Project;
 
+// Gemini API 金鑰在 secrets 表中的名稱
+const GEMINI_API_KEY_SECRET: &str = "gemini_api_key";
+
 // ADDING DRAFT RESPONSE STRUCT
 #[derive(Deserialize, Debug)] // Add Deserialize
 struct DraftGeneratorResponse {
@@ -31,7 +38,11 @@ struct DraftGeneratorResponse {
 pub enum AgentError {
     DatabaseError(rusqlite::Error),
     InvalidAgentType(String),
-    ApiKeyNotSet,
+    ApiKeyNotSet(String),
+    RateLimited { provider: String, retry_after: Option<u64>, quota_exhausted: bool },
+    HttpError { provider: String, status: u16, message: String },
+    ParseError(String),
+    SecretError(String),
     ApiError(String),
 }
 
@@ -40,7 +51,11 @@ impl fmt::Display for AgentError {
         match self {
             AgentError::DatabaseError(e) => write!(f, "Database error: {}", e),
             AgentError::InvalidAgentType(t) => write!(f, "Invalid agent type: {}", t),
-            AgentError::ApiKeyNotSet => write!(f, "Gemini API key is not set"),
+            AgentError::ApiKeyNotSet(provider) => write!(f, "{} API key is not set", provider),
+            AgentError::RateLimited { provider, .. } => write!(f, "{} API rate limit or quota exceeded", provider),
+            AgentError::HttpError { provider, status, message } => write!(f, "{} API returned error {}: {}", provider, status, message),
+            AgentError::ParseError(e) => write!(f, "Parse error: {}", e),
+            AgentError::SecretError(e) => write!(f, "Secret storage error: {}", e),
             AgentError::ApiError(e) => write!(f, "API error: {}", e),
         }
     }
@@ -49,7 +64,7 @@ impl fmt::Display for AgentError {
 impl Error for AgentError {}
 
 // AI 代理類型枚舉
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum AgentType {
     DraftGenerator,
     Planning,
@@ -60,7 +75,7 @@ pub enum AgentType {
 }
 
 impl AgentType {
-    fn to_string(&self) -> String {
+    pub(crate) fn to_string(&self) -> String {
         match self {
             AgentType::DraftGenerator => "draft_generator".to_string(),
             AgentType::Planning => "planning".to_string(),
@@ -71,7 +86,7 @@ impl AgentType {
         }
     }
 
-    fn from_string(s: &str) -> Result<Self, AgentError> {
+    pub(crate) fn from_string(s: &str) -> Result<Self, AgentError> {
         match s {
             "draft_generator" => Ok(AgentType::DraftGenerator),
             "planning" => Ok(AgentType::Planning),
@@ -92,6 +107,8 @@ pub struct AgentReasoning {
     pub agent_type: AgentType,
     pub title: String,
     pub reasoning: String,
+    pub output: Option<String>,
+    pub status: Option<String>,
     pub created_at: Option<String>,
     pub updated_at: Option<String>,
 }
@@ -121,604 +138,372 @@ pub struct RagRetrieval {
     pub updated_at: Option<String>,
 }
 
-// 初始化 AI 代理相關的表
-pub fn init_ai_agent_tables(conn: &Connection) -> Result<(), String> {
-    // 創建 agent_reasoning 表
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS agent_reasoning (
-            id INTEGER PRIMARY KEY,
-            blog_id INTEGER NOT NULL,
-            agent_type TEXT NOT NULL,
-            title TEXT NOT NULL,
-            reasoning TEXT NOT NULL,
-            created_at TEXT NOT NULL,
-            updated_at TEXT NOT NULL,
-            FOREIGN KEY (blog_id) REFERENCES blogs (id) ON DELETE CASCADE
-        )",
-        [],
-    ).map_err(|e| e.to_string())?;
-    
-    // 創建 agent_suggestions 表
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS agent_suggestions (
-            id INTEGER PRIMARY KEY,
-            reasoning_id INTEGER NOT NULL,
-            suggestion TEXT NOT NULL,
-            applied BOOLEAN DEFAULT 0,
-            created_at TEXT NOT NULL,
-            updated_at TEXT NOT NULL,
-            FOREIGN KEY (reasoning_id) REFERENCES agent_reasoning (id) ON DELETE CASCADE
-        )",
-        [],
-    ).map_err(|e| e.to_string())?;
-    
-    // 創建 rag_retrievals 表
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS rag_retrievals (
-            id INTEGER PRIMARY KEY,
-            blog_id INTEGER NOT NULL,
-            agent_type TEXT NOT NULL,
-            source_type TEXT NOT NULL,
-            source_id INTEGER NOT NULL,
-            relevance_score REAL NOT NULL,
-            content TEXT NOT NULL,
-            created_at TEXT NOT NULL,
-            updated_at TEXT NOT NULL,
-            FOREIGN KEY (blog_id) REFERENCES blogs (id) ON DELETE CASCADE
-        )",
-        [],
-    ).map_err(|e| e.to_string())?;
-    
-    Ok(())
-}
-
 // 保存代理推理
 #[tauri::command]
-pub fn save_agent_reasoning(reasoning: AgentReasoning, state: tauri::State<'_, SqliteState>) -> Result<i64, String> {
-    let conn = state.0.lock().unwrap();
-    
-    let now = Local::now().to_rfc3339();
-    
-    let agent_type_str = match reasoning.agent_type {
-        AgentType::DraftGenerator => "draft_generator",
-        AgentType::Planning => "planning",
-        AgentType::Research => "research",
-        AgentType::Editor => "editor",
-        AgentType::Reviewer => "reviewer",
-        AgentType::InlineEditor => "inline_editor",
-    };
-    
-    let id = conn.execute(
-        "INSERT INTO agent_reasoning (blog_id, agent_type, title, reasoning, created_at, updated_at) 
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-        params![
-            reasoning.blog_id,
-            agent_type_str,
-            reasoning.title,
-            reasoning.reasoning,
-            now,
-            now
-        ],
-    ).map_err(|e| e.to_string())?;
+pub async fn save_agent_reasoning(reasoning: AgentReasoning, state: tauri::State<'_, SqliteState>) -> Result<i64, AppError> {
+    state.run(move |conn| {
+        let now = Local::now().to_rfc3339();
+    
+        let agent_type_str = match reasoning.agent_type {
+            AgentType::DraftGenerator => "draft_generator",
+            AgentType::Planning => "planning",
+            AgentType::Research => "research",
+            AgentType::Editor => "editor",
+            AgentType::Reviewer => "reviewer",
+            AgentType::InlineEditor => "inline_editor",
+        };
     
-    Ok(conn.last_insert_rowid())
+        let id = conn.execute(
+            "INSERT INTO agent_reasoning (blog_id, agent_type, title, reasoning, output, status, created_at, updated_at) 
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                reasoning.blog_id,
+                agent_type_str,
+                reasoning.title,
+                reasoning.reasoning,
+                reasoning.output,
+                reasoning.status.unwrap_or_else(|| "completed".to_string()),
+                now,
+                now
+            ],
+        )?;
+    
+        Ok(conn.last_insert_rowid())
+    }).await
 }
 
 // 保存代理建議
 #[tauri::command]
-pub fn save_agent_suggestion(suggestion: AgentSuggestion, state: tauri::State<'_, SqliteState>) -> Result<i64, String> {
-    let conn = state.0.lock().unwrap();
-    
-    let now = Local::now().to_rfc3339();
-    
-    let id = conn.execute(
-        "INSERT INTO agent_suggestions (reasoning_id, suggestion, applied, created_at, updated_at) 
-         VALUES (?1, ?2, ?3, ?4, ?5)",
-        params![
-            suggestion.reasoning_id,
-            suggestion.suggestion,
-            suggestion.applied,
-            now,
-            now
-        ],
-    ).map_err(|e| e.to_string())?;
-    
-    Ok(conn.last_insert_rowid())
+pub async fn save_agent_suggestion(suggestion: AgentSuggestion, state: tauri::State<'_, SqliteState>) -> Result<i64, AppError> {
+    state.run(move |conn| {
+        let now = Local::now().to_rfc3339();
+    
+        let id = conn.execute(
+            "INSERT INTO agent_suggestions (reasoning_id, suggestion, applied, created_at, updated_at) 
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                suggestion.reasoning_id,
+                suggestion.suggestion,
+                suggestion.applied,
+                now,
+                now
+            ],
+        )?;
+    
+        Ok(conn.last_insert_rowid())
+    }).await
 }
 
 // 保存 RAG 檢索結果
 #[tauri::command]
-pub fn save_rag_retrieval(retrieval: RagRetrieval, state: tauri::State<'_, SqliteState>) -> Result<i64, String> {
-    let conn = state.0.lock().unwrap();
-    
-    let now = Local::now().to_rfc3339();
-    
-    let agent_type_str = match retrieval.agent_type {
-        AgentType::DraftGenerator => "draft_generator",
-        AgentType::Planning => "planning",
-        AgentType::Research => "research",
-        AgentType::Editor => "editor",
-        AgentType::Reviewer => "reviewer",
-        AgentType::InlineEditor => "inline_editor",
-    };
-    
-    let id = conn.execute(
-        "INSERT INTO rag_retrievals (blog_id, agent_type, source_type, source_id, relevance_score, content, created_at, updated_at) 
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
-        params![
-            retrieval.blog_id,
-            agent_type_str,
-            retrieval.source_type,
-            retrieval.source_id,
-            retrieval.relevance_score,
-            retrieval.content,
-            now,
-            now
-        ],
-    ).map_err(|e| e.to_string())?;
+pub async fn save_rag_retrieval(retrieval: RagRetrieval, state: tauri::State<'_, SqliteState>) -> Result<i64, AppError> {
+    state.run(move |conn| {
+        let now = Local::now().to_rfc3339();
+    
+        let agent_type_str = match retrieval.agent_type {
+            AgentType::DraftGenerator => "draft_generator",
+            AgentType::Planning => "planning",
+            AgentType::Research => "research",
+            AgentType::Editor => "editor",
+            AgentType::Reviewer => "reviewer",
+            AgentType::InlineEditor => "inline_editor",
+        };
     
-    Ok(conn.last_insert_rowid())
+        let id = conn.execute(
+            "INSERT INTO rag_retrievals (blog_id, agent_type, source_type, source_id, relevance_score, content, created_at, updated_at) 
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                retrieval.blog_id,
+                agent_type_str,
+                retrieval.source_type,
+                retrieval.source_id,
+                retrieval.relevance_score,
+                retrieval.content,
+                now,
+                now
+            ],
+        )?;
+    
+        Ok(conn.last_insert_rowid())
+    }).await
 }
 
 // 獲取博客的所有代理推理
 #[tauri::command]
-pub fn get_agent_reasonings_by_blog(blog_id: i64, state: tauri::State<'_, SqliteState>) -> Result<Vec<AgentReasoning>, String> {
-    let conn = state.0.lock().unwrap();
-    
-    let mut stmt = conn.prepare(
-        "SELECT id, blog_id, agent_type, title, reasoning, created_at, updated_at 
-         FROM agent_reasoning 
-         WHERE blog_id = ? 
-         ORDER BY created_at DESC"
-    ).map_err(|e| e.to_string())?;
-    
-    let reasonings = stmt.query_map([blog_id], |row| {
-        let agent_type_str: String = row.get(2)?;
-        let agent_type = match agent_type_str.as_str() {
-            "draft_generator" => AgentType::DraftGenerator,
-            "planning" => AgentType::Planning,
-            "research" => AgentType::Research,
-            "editor" => AgentType::Editor,
-            "reviewer" => AgentType::Reviewer,
-            "inline_editor" => AgentType::InlineEditor,
-            _ => AgentType::DraftGenerator, // 默認值
-        };
+pub async fn get_agent_reasonings_by_blog(blog_id: i64, state: tauri::State<'_, SqliteState>) -> Result<Vec<AgentReasoning>, AppError> {
+    state.run(move |conn| {
+        let mut stmt = conn.prepare(
+            "SELECT id, blog_id, agent_type, title, reasoning, output, status, created_at, updated_at 
+             FROM agent_reasoning 
+             WHERE blog_id = ? 
+             ORDER BY created_at DESC"
+        )?;
+    
+        let reasonings = stmt.query_map([blog_id], |row| {
+            let agent_type_str: String = row.get(2)?;
+            let agent_type = match agent_type_str.as_str() {
+                "draft_generator" => AgentType::DraftGenerator,
+                "planning" => AgentType::Planning,
+                "research" => AgentType::Research,
+                "editor" => AgentType::Editor,
+                "reviewer" => AgentType::Reviewer,
+                "inline_editor" => AgentType::InlineEditor,
+                _ => AgentType::DraftGenerator, // 默認值
+            };
         
-        Ok(AgentReasoning {
-            id: Some(row.get(0)?),
-            blog_id: row.get(1)?,
-            agent_type,
-            title: row.get(3)?,
-            reasoning: row.get(4)?,
-            created_at: Some(row.get(5)?),
-            updated_at: Some(row.get(6)?),
-        })
-    }).map_err(|e| e.to_string())?
-    .collect::<Result<Vec<_>, _>>()
-    .map_err(|e| e.to_string())?;
-    
-    Ok(reasonings)
+            Ok(AgentReasoning {
+                id: Some(row.get(0)?),
+                blog_id: row.get(1)?,
+                agent_type,
+                title: row.get(3)?,
+                reasoning: row.get(4)?,
+                output: row.get(5)?,
+                status: row.get(6)?,
+                created_at: Some(row.get(7)?),
+                updated_at: Some(row.get(8)?),
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    
+        Ok(reasonings)
+    }).await
 }
 
 // 獲取推理的所有建議
 #[tauri::command]
-pub fn get_agent_suggestions_by_reasoning(reasoning_id: i64, state: tauri::State<'_, SqliteState>) -> Result<Vec<AgentSuggestion>, String> {
-    let conn = state.0.lock().unwrap();
-    
-    let mut stmt = conn.prepare(
-        "SELECT id, reasoning_id, suggestion, applied, created_at, updated_at 
-         FROM agent_suggestions 
-         WHERE reasoning_id = ? 
-         ORDER BY created_at ASC"
-    ).map_err(|e| e.to_string())?;
-    
-    let suggestions = stmt.query_map([reasoning_id], |row| {
-        Ok(AgentSuggestion {
-            id: Some(row.get(0)?),
-            reasoning_id: row.get(1)?,
-            suggestion: row.get(2)?,
-            applied: row.get(3)?,
-            created_at: Some(row.get(4)?),
-            updated_at: Some(row.get(5)?),
-        })
-    }).map_err(|e| e.to_string())?
-    .collect::<Result<Vec<_>, _>>()
-    .map_err(|e| e.to_string())?;
-    
-    Ok(suggestions)
+pub async fn get_agent_suggestions_by_reasoning(reasoning_id: i64, state: tauri::State<'_, SqliteState>) -> Result<Vec<AgentSuggestion>, AppError> {
+    state.run(move |conn| {
+        let mut stmt = conn.prepare(
+            "SELECT id, reasoning_id, suggestion, applied, created_at, updated_at 
+             FROM agent_suggestions 
+             WHERE reasoning_id = ? 
+             ORDER BY created_at ASC"
+        )?;
+    
+        let suggestions = stmt.query_map([reasoning_id], |row| {
+            Ok(AgentSuggestion {
+                id: Some(row.get(0)?),
+                reasoning_id: row.get(1)?,
+                suggestion: row.get(2)?,
+                applied: row.get(3)?,
+                created_at: Some(row.get(4)?),
+                updated_at: Some(row.get(5)?),
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    
+        Ok(suggestions)
+    }).await
 }
 
 // 獲取博客的所有 RAG 檢索結果
 #[tauri::command]
-pub fn get_rag_retrievals_by_blog(blog_id: i64, state: tauri::State<'_, SqliteState>) -> Result<Vec<RagRetrieval>, String> {
-    let conn = state.0.lock().unwrap();
-    
-    let mut stmt = conn.prepare(
-        "SELECT id, blog_id, agent_type, source_type, source_id, relevance_score, content, created_at, updated_at 
-         FROM rag_retrievals 
-         WHERE blog_id = ? 
-         ORDER BY relevance_score DESC"
-    ).map_err(|e| e.to_string())?;
-    
-    let retrievals = stmt.query_map([blog_id], |row| {
-        let agent_type_str: String = row.get(2)?;
-        let agent_type = match agent_type_str.as_str() {
-            "draft_generator" => AgentType::DraftGenerator,
-            "planning" => AgentType::Planning,
-            "research" => AgentType::Research,
-            "editor" => AgentType::Editor,
-            "reviewer" => AgentType::Reviewer,
-            "inline_editor" => AgentType::InlineEditor,
-            _ => AgentType::DraftGenerator, // 默認值
-        };
+pub async fn get_rag_retrievals_by_blog(blog_id: i64, state: tauri::State<'_, SqliteState>) -> Result<Vec<RagRetrieval>, AppError> {
+    state.run(move |conn| {
+        let mut stmt = conn.prepare(
+            "SELECT id, blog_id, agent_type, source_type, source_id, relevance_score, content, created_at, updated_at 
+             FROM rag_retrievals 
+             WHERE blog_id = ? 
+             ORDER BY relevance_score DESC"
+        )?;
+    
+        let retrievals = stmt.query_map([blog_id], |row| {
+            let agent_type_str: String = row.get(2)?;
+            let agent_type = match agent_type_str.as_str() {
+                "draft_generator" => AgentType::DraftGenerator,
+                "planning" => AgentType::Planning,
+                "research" => AgentType::Research,
+                "editor" => AgentType::Editor,
+                "reviewer" => AgentType::Reviewer,
+                "inline_editor" => AgentType::InlineEditor,
+                _ => AgentType::DraftGenerator, // 默認值
+            };
         
-        Ok(RagRetrieval {
-            id: Some(row.get(0)?),
-            blog_id: row.get(1)?,
-            agent_type,
-            source_type: row.get(3)?,
-            source_id: row.get(4)?,
-            relevance_score: row.get(5)?,
-            content: row.get(6)?,
-            created_at: Some(row.get(7)?),
-            updated_at: Some(row.get(8)?),
-        })
-    }).map_err(|e| e.to_string())?
-    .collect::<Result<Vec<_>, _>>()
-    .map_err(|e| e.to_string())?;
-    
-    Ok(retrievals)
+            Ok(RagRetrieval {
+                id: Some(row.get(0)?),
+                blog_id: row.get(1)?,
+                agent_type,
+                source_type: row.get(3)?,
+                source_id: row.get(4)?,
+                relevance_score: row.get(5)?,
+                content: row.get(6)?,
+                created_at: Some(row.get(7)?),
+                updated_at: Some(row.get(8)?),
+            })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+    
+        Ok(retrievals)
+    }).await
 }
 
 // 更新建議的應用狀態
 #[tauri::command]
-pub fn update_suggestion_applied(id: i64, applied: bool, state: tauri::State<'_, SqliteState>) -> Result<bool, String> {
-    let conn = state.0.lock().unwrap();
-    
-    let now = Local::now().to_rfc3339();
-    
-    conn.execute(
-        "UPDATE agent_suggestions 
-         SET applied = ?1, updated_at = ?2 
-         WHERE id = ?3",
-        params![
-            applied,
-            now,
-            id
-        ],
-    ).map_err(|e| e.to_string())?;
-    
-    Ok(true)
+pub async fn update_suggestion_applied(id: i64, applied: bool, state: tauri::State<'_, SqliteState>) -> Result<bool, AppError> {
+    state.run(move |conn| {
+        let now = Local::now().to_rfc3339();
+    
+        // 接受或拒絕都記錄為已審核
+        let status = if applied { "accepted" } else { "rejected" };
+    
+        conn.execute(
+            "UPDATE agent_suggestions 
+             SET applied = ?1, status = ?2, updated_at = ?3 
+             WHERE id = ?4",
+            params![
+                applied,
+                status,
+                now,
+                id
+            ],
+        )?;
+    
+        Ok(true)
+    }).await
 }
 
 // 檢查 Gemini API 金鑰是否已設定
 #[tauri::command]
-pub fn check_gemini_api_key(state: tauri::State<'_, SqliteState>) -> Result<bool, String> {
+pub async fn check_gemini_api_key(state: tauri::State<'_, SqliteState>) -> Result<bool, AppError> {
     println!("Checking if Gemini API key is set");
     
-    let conn = state.0.lock().unwrap();
-    println!("Database path: {:?}", conn.path().unwrap_or("unknown"));
-    
-    // Check if settings table exists using a more reliable method
-    let table_exists: bool = match conn.query_row("SELECT 1 FROM settings LIMIT 1", [], |_| Ok(())) {
-        Ok(_) => true,
-        Err(e) => {
-            // If the error is "no such table", then the table doesn't exist
-            // Otherwise, it's a different error that we should log
-            if e.to_string().contains("no such table") {
-                println!("Settings table does not exist: {}", e);
-                false
-            } else {
-                println!("Error checking settings table: {}", e);
-                false
-            }
-        }
-    };
-    
-    println!("Settings table exists: {}", table_exists);
-    
-    if !table_exists {
-        println!("Settings table does not exist!");
-        return Ok(false);
-    }
-    
-    // Check if gemini_api_key record exists
-    let key_exists: bool = conn.query_row(
-        "SELECT COUNT(*) FROM settings WHERE key = 'gemini_api_key'",
-        [],
-        |row| row.get(0)
-    ).unwrap_or(0) > 0;
-    
-    println!("Gemini API key exists in database: {}", key_exists);
-    
-    if !key_exists {
-        return Ok(false);
-    }
-    
-    let api_key: Result<Option<String>, _> = conn.query_row(
-        "SELECT value FROM settings WHERE key = 'gemini_api_key'",
-        [],
-        |row| row.get(0)
-    );
-    
-    match api_key {
-        Ok(Some(key)) => {
-            let is_valid = !key.is_empty();
-            println!("API key is {}", if is_valid { "valid" } else { "empty" });
-            if is_valid {
-                println!("API key value: {}", key);
-            }
-            Ok(is_valid)
-        },
-        _ => {
-            println!("No API key found or error retrieving it");
-            Ok(false)
-        }
-    }
+    state.run(move |conn| {
+        let key_exists = secrets::has_secret(conn, GEMINI_API_KEY_SECRET)?;
+        println!("Gemini API key exists: {}", key_exists);
+        Ok(key_exists)
+    }).await
 }
 
-// 設定 Gemini API 金鑰
+// 設定 Gemini API 金鑰 (加密保存)
 #[tauri::command]
-pub fn set_gemini_api_key(api_key: String, state: tauri::State<'_, SqliteState>) -> Result<bool, String> {
+pub async fn set_gemini_api_key(api_key: String, state: tauri::State<'_, SqliteState>) -> Result<bool, AppError> {
     println!("Setting Gemini API key");
     
-    let conn = state.0.lock().unwrap();
-    let now = Local::now().to_rfc3339();
+    if api_key.is_empty() {
+        return Err(AppError::validation("Gemini API key cannot be empty"));
+    }
     
-    // Insert or update the API key
-    conn.execute(
-        "INSERT INTO settings (key, value, created_at, updated_at) 
-         VALUES ('gemini_api_key', ?1, ?2, ?2)
-         ON CONFLICT(key) DO UPDATE SET value = ?1, updated_at = ?2",
-        params![api_key, now],
-    ).map_err(|e| {
-        println!("Error saving API key: {}", e);
-        e.to_string()
-    })?;
+    state.run(move |conn| {
+        secrets::set_secret(conn, GEMINI_API_KEY_SECRET, &api_key).map_err(|e| {
+            println!("Error saving API key: {}", e);
+            e
+        })?;
     
-    println!("Gemini API key saved successfully");
-    Ok(true)
+        println!("Gemini API key saved successfully");
+        Ok(true)
+    }).await
 }
 
 // 獲取 Gemini API 金鑰
 #[tauri::command]
-pub fn get_gemini_api_key(state: tauri::State<'_, SqliteState>) -> Result<String, String> {
+pub async fn get_gemini_api_key(state: tauri::State<'_, SqliteState>) -> Result<String, AppError> {
     println!("Attempting to get Gemini API key");
     
-    let conn = state.0.lock().unwrap();
-    println!("Database path: {:?}", conn.path().unwrap_or("unknown"));
-    
-    // Check if settings table exists using a more reliable method
-    let table_exists: bool = match conn.query_row("SELECT 1 FROM settings LIMIT 1", [], |_| Ok(())) {
-        Ok(_) => true,
-        Err(e) => {
-            // If the error is "no such table", then the table doesn't exist
-            // Otherwise, it's a different error that we should log
-            if e.to_string().contains("no such table") {
-                println!("Settings table does not exist: {}", e);
-                false
-            } else {
-                println!("Error checking settings table: {}", e);
-                false
+    state.run(move |conn| {
+        match secrets::get_secret(conn, GEMINI_API_KEY_SECRET)? {
+            Some(key) if !key.is_empty() => {
+                println!("API key found successfully");
+                Ok(key)
+            },
+            _ => {
+                println!("No Gemini API key stored");
+                Err(AppError::ApiKeyMissing { provider: "Gemini".to_string() })
             }
         }
-    };
-    
-    println!("Settings table exists: {}", table_exists);
-    
-    if !table_exists {
-        println!("Settings table does not exist!");
-        return Err("Settings table does not exist".to_string());
-    }
-    
-    // Check if gemini_api_key record exists
-    let key_exists: bool = conn.query_row(
-        "SELECT COUNT(*) FROM settings WHERE key = 'gemini_api_key'",
-        [],
-        |row| row.get(0)
-    ).unwrap_or(0) > 0;
-    
-    println!("Gemini API key exists in database: {}", key_exists);
-    
-    if !key_exists {
-        println!("No Gemini API key found in settings table");
-        return Err("No Gemini API key found".to_string());
+    }).await
+}
+
+// 串流事件名稱
+pub const AI_STREAM_CHUNK_EVENT: &str = "ai-stream-chunk";
+pub const AI_STREAM_DONE_EVENT: &str = "ai-stream-done";
+pub const AI_STREAM_ERROR_EVENT: &str = "ai-stream-error";
+
+// 串流片段事件
+#[derive(Debug, Clone, Serialize)]
+pub struct AiStreamChunk {
+    pub request_id: String,
+    pub chunk: String,
+}
+
+// 串流完成事件，附帶已保存的推理 ID
+#[derive(Debug, Clone, Serialize)]
+pub struct AiStreamDone {
+    pub request_id: String,
+    pub reasoning_id: Option<i64>,
+    pub text: String,
+}
+
+// 串流錯誤事件；code / retryable / details 與指令回傳的 AppError 相同
+#[derive(Debug, Clone, Serialize)]
+pub struct AiStreamError {
+    pub request_id: String,
+    pub error: String,
+    pub code: &'static str,
+    pub retryable: bool,
+    pub details: Option<serde_json::Value>,
+}
+
+impl AiStreamError {
+    pub fn new(request_id: &str, error: &AppError) -> Self {
+        AiStreamError {
+            request_id: request_id.to_string(),
+            error: error.to_string(),
+            code: error.code(),
+            retryable: error.retryable(),
+            details: error.details(),
+        }
     }
-    
-    // Query the API key directly
-    let api_key = conn.query_row(
-        "SELECT value FROM settings WHERE key = 'gemini_api_key'",
-        [],
-        |row| row.get::<_, Option<String>>(0)
-    ).map_err(|e| {
-        println!("Error querying API key: {}", e);
-        e.to_string()
-    })?;
-    
-    match api_key {
-        Some(key) if !key.is_empty() => {
-            println!("API key found successfully");
-            Ok(key)
-        },
-        Some(_) => {
-            println!("API key found but is empty");
-            Err("API key is empty".to_string())
+}
+
+// 為指定代理解析 LLM 供應商
+pub(crate) async fn resolve_provider(agent_type: &AgentType, blog_id: i64, state: &SqliteState) -> Result<Box<dyn llm::LlmProvider>, AppError> {
+    let agent_type = agent_type.clone();
+    let provider = state.run(move |conn| llm::provider_for(conn, &agent_type, blog_id).map_err(AppError::from)).await;
+    match provider {
+        Ok(provider) => {
+            println!("Using {} provider with model {}", provider.kind().label(), provider.model());
+            Ok(provider)
         },
-        None => {
-            println!("No API key found in database");
-            Err("No API key found".to_string())
+        Err(e) => {
+            println!("Failed to configure LLM provider: {}", e);
+            Err(e)
         }
     }
 }
 
-// 使用 Gemini API 生成內容
+// 使用設定的 LLM 供應商生成內容 (預設為 Gemini)；以 AI 任務執行，可以取消與續寫
 #[tauri::command]
-pub async fn generate_with_gemini(prompt: String, agent_type: AgentType, blog_id: i64, project_data: Option<serde_json::Value>, state: tauri::State<'_, SqliteState>) -> Result<String, String> {
+pub async fn generate_with_gemini(prompt: String, agent_type: AgentType, blog_id: i64, project_data: Option<serde_json::Value>, app_handle: AppHandle) -> Result<String, AppError> {
     println!("generate_with_gemini called with agent_type: {:?}, blog_id: {}", agent_type, blog_id);
     
-    // Get API key
-    let api_key = match get_gemini_api_key(state.clone()) {
-        Ok(key) => {
-            println!("Successfully retrieved API key");
-            key
-        },
-        Err(e) => {
-            println!("Failed to get API key: {}", e);
-            return Err("Gemini API key not set. Please set it in Settings.".to_string());
-        }
-    };
-    
-    // 構建請求 URL
-    let url = "https://generativelanguage.googleapis.com/v1beta/models/gemini-2.0-flash:generateContent";
-    println!("Using Gemini API URL: {}", url);
-    
-    // 構建請求體
-    let request_body = serde_json::json!({
-        "contents": [
-            {
-                "parts": [
-                    {
-                        "text": prompt
-                    }
-                ]
-            }
-        ],
-        "generationConfig": {
-            "temperature": 0.7,
-            "topK": 40,
-            "topP": 0.95,
-            "maxOutputTokens": 6000,
-        }
-    });
-    
-    println!("Sending request to Gemini API with prompt: {}", prompt);
-    println!("Request body: {}", serde_json::to_string_pretty(&request_body).unwrap_or_else(|_| "Failed to format JSON".to_string()));
-    
     // 如果有 project_data，將其添加到請求中
-    let mut request_body = request_body;
-    if let Some(project_data) = project_data {
+    if let Some(_project_data) = project_data {
         println!("Including project data in the request");
         // 這裡可以根據需要處理 project_data
         // 例如，將其添加到 prompt 中或作為單獨的參數
     }
     
-    // 發送請求
-    let client = reqwest::Client::new();
-    let response = client.post(format!("{}?key={}", url, api_key))
-        .json(&request_body)
-        .send()
-        .await
-        .map_err(|e| {
-            println!("Request error: {}", e);
-            format!("Failed to send request to Gemini API: {}", e)
-        })?;
-    
-    // 檢查響應狀態
-    let status = response.status();
-    println!("Gemini API response status: {}", status);
-    
-    if !status.is_success() {
-        let error_text = response.text().await.unwrap_or_default();
-        println!("API error response: {}", error_text);
-        return Err(format!("Gemini API returned error: {}", status));
-    }
-    
-    // 解析響應
-    let response_json = response.json::<serde_json::Value>().await.map_err(|e| {
-        println!("JSON parsing error: {}", e);
-        format!("Failed to parse Gemini API response: {}", e)
-    })?;
-    
-    println!("Received response from Gemini API");
-    println!("Full response JSON: {}", serde_json::to_string_pretty(&response_json).unwrap_or_else(|_| "Failed to format JSON".to_string()));
-    
-    // 提取生成的文本
-    let generated_text = match response_json.get("candidates") {
-        Some(candidates) => {
-            println!("Found candidates in response");
-            match candidates.get(0) {
-                Some(candidate) => {
-                    println!("Found first candidate");
-                    match candidate.get("content") {
-                        Some(content) => {
-                            println!("Found content in candidate");
-                            match content.get("parts") {
-                                Some(parts) => {
-                                    println!("Found parts in content");
-                                    match parts.get(0) {
-                                        Some(part) => {
-                                            println!("Found first part");
-                                            match part.get("text") {
-                                                Some(text) => {
-                                                    println!("Found text in part");
-                                                    match text.as_str() {
-                                                        Some(text_str) => {
-                                                            println!("Successfully extracted text: {}", text_str);
-                                                            Ok(text_str.to_string())
-                                                        },
-                                                        None => {
-                                                            println!("Text is not a string: {:?}", text);
-                                                            Err("Text in response is not a string".to_string())
-                                                        }
-                                                    }
-                                                },
-                                                None => {
-                                                    println!("No text field found in part: {:?}", part);
-                                                    Err("No text field found in response part".to_string())
-                                                }
-                                            }
-                                        },
-                                        None => {
-                                            println!("No parts found in content: {:?}", content);
-                                            Err("No parts found in response content".to_string())
-                                        }
-                                    }
-                                },
-                                None => {
-                                    println!("No parts field found in content: {:?}", content);
-                                    Err("No parts field found in response content".to_string())
-                                }
-                            }
-                        },
-                        None => {
-                            println!("No content field found in candidate: {:?}", candidate);
-                            Err("No content field found in response candidate".to_string())
-                        }
-                    }
-                },
-                None => {
-                    println!("No candidates found in response: {:?}", candidates);
-                    Err("No candidates found in response".to_string())
-                }
-            }
-        },
-        None => {
-            println!("No candidates field found in response: {:?}", response_json);
-            Err("No candidates field found in response".to_string())
-        }
-    }?;
+    let generated_text = jobs::run_ai_job(app_handle, AiJobRequest::Generate { prompt, agent_type }, blog_id, None).await?;
     
     println!("Extracted generated text: {}", generated_text);
     
-    // 保存代理推理
-    let reasoning = AgentReasoning {
-        id: None,
-        blog_id,  // 使用從前端傳遞過來的 blog_id
-        agent_type,
-        title: "AI Generation".to_string(),
-        reasoning: prompt,
-        created_at: None,
-        updated_at: None,
-    };
-    
-    match save_agent_reasoning(reasoning, state) {
-        Ok(_) => println!("Saved agent reasoning"),
-        Err(e) => println!("Failed to save agent reasoning: {}", e),
-    }
-    
     Ok(generated_text)
 }
 
+// 串流模式的內容生成，片段以 ai-stream-chunk 事件送出；request_id 同時是任務 ID，可用 cancel_ai_job 取消
+#[tauri::command]
+pub async fn stream_with_gemini(
+    request_id: String,
+    prompt: String,
+    agent_type: AgentType,
+    blog_id: i64,
+    app_handle: AppHandle
+) -> Result<String, AppError> {
+    println!("stream_with_gemini called with request_id: {}, agent_type: {:?}, blog_id: {}", request_id, agent_type, blog_id);
+    
+    jobs::run_ai_job(app_handle, AiJobRequest::Generate { prompt, agent_type }, blog_id, Some(request_id)).await
+}
+
 // 使用 Gemini API 生成文章草稿
 #[tauri::command]
 pub async fn generate_article_draft(
@@ -728,37 +513,62 @@ pub async fn generate_article_draft(
     project_data: Option<serde_json::Value>,
     current_title: String,
     current_content: String,
-    state: tauri::State<'_, SqliteState>
-) -> Result<String, String> {
+    app_handle: AppHandle
+) -> Result<String, AppError> {
     println!("generate_article_draft called with:");
     println!("prompt: {}", prompt);
     println!("selected_text: {}", selected_text);
     println!("blog_id: {}", blog_id);
     println!("current_title: {}", current_title);
     
+    let request = ArticleRequest { prompt, selected_text, project_data, current_title, current_content };
+    jobs::run_ai_job(app_handle, AiJobRequest::ArticleDraft(request), blog_id, None).await
+}
+
+// 串流模式的文章草稿生成，完成事件帶有處理後的草稿 JSON
+#[tauri::command]
+pub async fn stream_article_draft(
+    request_id: String,
+    blog_id: i64,
+    request: ArticleRequest,
+    app_handle: AppHandle
+) -> Result<String, AppError> {
+    println!("stream_article_draft called with request_id: {}, blog_id: {}", request_id, blog_id);
+    
+    jobs::run_ai_job(app_handle, AiJobRequest::ArticleDraft(request), blog_id, Some(request_id)).await
+}
+
+// 構建文章草稿的 AI 提示
+pub(crate) fn build_draft_prompt(
+    prompt: &str,
+    selected_text: &str,
+    project_data: &Option<serde_json::Value>,
+    current_title: &str,
+    current_content: &str,
+) -> String {
     // 從 project_data 中提取信息，如果有的話
-    let project_goal = match &project_data {
+    let project_goal = match project_data {
         Some(data) => data.get("goal").and_then(|v| v.as_str()).unwrap_or(""),
         None => ""
     };
     
-    let project_description = match &project_data {
+    let project_description = match project_data {
         Some(data) => data.get("description").and_then(|v| v.as_str()).unwrap_or(""),
         None => ""
     };
     
-    let target_audience = match &project_data {
+    let target_audience = match project_data {
         Some(data) => data.get("target_audience").and_then(|v| v.as_str()).unwrap_or(""),
         None => ""
     };
     
-    let keywords = match &project_data {
+    let keywords = match project_data {
         Some(data) => data.get("keywords").and_then(|v| v.as_str()).unwrap_or(""),
         None => ""
     };
     
     // 構建 AI 提示
-    let ai_prompt = format!(
+    format!(
         "You are an AI writing assistant for a content creator. I need your help drafting an article with the following details:
 
 Project Goal: {}
@@ -804,10 +614,11 @@ IMPORTANT: Your response MUST be formatted as a JSON object wrapped in a markdow
 Do not include any text outside of this JSON code block. Keep your response concise and focused on the task.",
         project_goal, project_description, target_audience, keywords, 
         prompt, selected_text, current_title, current_content
-    );
-    
-    let response = generate_with_gemini(ai_prompt, AgentType::DraftGenerator, blog_id, project_data, state).await?;
-    
+    )
+}
+
+// 處理草稿回應：擷取、清理並修復 AI 回傳的 JSON
+pub(crate) fn process_draft_response(response: String, current_title: &str, current_content: &str) -> Result<String, AppError> {
     // --- REVISED LOGIC --- 
     // 1. Extract JSON string FIRST from the raw response
     let json_str_extracted: &str = if response.starts_with("```json") && response.ends_with("```") {
@@ -947,23 +758,115 @@ pub async fn plan_article_content(
     project_data: Option<serde_json::Value>,
     current_title: String,
     current_content: String,
-    state: tauri::State<'_, SqliteState>
-) -> Result<String, String> {
+    app_handle: AppHandle
+) -> Result<String, AppError> {
     println!("plan_article_content called with prompt: {}", prompt);
     
+    let request = ArticleRequest { prompt, selected_text, project_data, current_title, current_content };
+    jobs::run_ai_job(app_handle, AiJobRequest::PlanArticle(request), blog_id, None).await
+}
+
+// 使用 Gemini API 分析文章內容
+#[tauri::command]
+pub async fn analyze_article_content(
+    prompt: String,
+    selected_text: String,
+    blog_id: i64,
+    project_data: Option<serde_json::Value>,
+    current_title: String,
+    current_content: String,
+    app_handle: AppHandle
+) -> Result<String, AppError> {
+    println!("analyze_article_content called with prompt: {}", prompt);
+    
+    let request = ArticleRequest { prompt, selected_text, project_data, current_title, current_content };
+    jobs::run_ai_job(app_handle, AiJobRequest::AnalyzeArticle(request), blog_id, None).await
+}
+
+// 使用 Gemini API 調整文章風格
+#[tauri::command]
+pub async fn adjust_article_style(
+    prompt: String,
+    selected_text: String,
+    blog_id: i64,
+    project_data: Option<serde_json::Value>,
+    current_title: String,
+    current_content: String,
+    app_handle: AppHandle
+) -> Result<String, AppError> {
+    println!("adjust_article_style called with prompt: {}", prompt);
+    
+    let request = ArticleRequest { prompt, selected_text, project_data, current_title, current_content };
+    jobs::run_ai_job(app_handle, AiJobRequest::AdjustStyle(request), blog_id, None).await
+}
+
+// 使用 Gemini API 進行最終審查
+#[tauri::command]
+pub async fn review_article_final(
+    prompt: String,
+    selected_text: String,
+    blog_id: i64,
+    project_data: Option<serde_json::Value>,
+    current_title: String, 
+    current_content: String,
+    app_handle: AppHandle
+) -> Result<String, AppError> {
+    println!("review_article_final called with prompt: {}", prompt);
+    
+    let request = ArticleRequest { prompt, selected_text, project_data, current_title, current_content };
+    jobs::run_ai_job(app_handle, AiJobRequest::ReviewFinal(request), blog_id, None).await
+}
+
+// 使用 Gemini API 進行內聯編輯
+#[tauri::command]
+pub async fn inline_edit_text(
+    selected_text: String, 
+    article_title: String, 
+    article_content: String, 
+    user_prompt: String,
+    blog_id: i64,
+    app_handle: AppHandle
+) -> Result<String, AppError> {
+    println!("inline_edit_text called with prompt: {}", user_prompt);
+    println!("Selected text: {}", selected_text);
+    println!("Blog ID: {}", blog_id);
+    
+    let request = AiJobRequest::InlineEdit { selected_text, article_title, user_prompt };
+    
+    println!("Sending prompt to Gemini API");
+    match jobs::run_ai_job(app_handle, request, blog_id, None).await {
+        Ok(response) => {
+            println!("Received response from Gemini API: {}", response);
+            Ok(response)
+        },
+        Err(e) => {
+            println!("Error from Gemini API: {}", e);
+            Err(e)
+        }
+    }
+}
+
+// 構建文章規劃的 AI 提示
+pub(crate) fn build_plan_prompt(
+    prompt: &str,
+    selected_text: &str,
+    project_data: &Option<serde_json::Value>,
+    current_title: &str,
+    current_content: &str,
+) -> String {
     // 從 project_data 中提取信息，如果有的話
-    let project_title = match &project_data {
+    let project_title = match project_data {
         Some(data) => data.get("title").and_then(|v| v.as_str()).unwrap_or(""),
         None => ""
     };
     
-    let keywords = match &project_data {
+    let keywords = match project_data {
         Some(data) => data.get("keywords").and_then(|v| v.as_str()).unwrap_or(""),
         None => ""
     };
     
     // 構建 AI 提示
-    let ai_prompt = format!(
+    format!(
         "Create a detailed content plan for a blog article with the following details:
         
 Current Article Title: {}
@@ -981,26 +884,18 @@ Current Article Content (if any):
 Include a suggested outline with main sections and subsections, key points to cover, and any relevant research topics.
 Provide a clear structure that addresses the user's request and incorporates the keywords naturally.",
         current_title, keywords, prompt, selected_text, current_content
-    );
-    
-    generate_with_gemini(ai_prompt, AgentType::Planning, blog_id, project_data, state).await
+    )
 }
 
-// 使用 Gemini API 分析文章內容
-#[tauri::command]
-pub async fn analyze_article_content(
-    prompt: String,
-    selected_text: String,
-    blog_id: i64,
-    project_data: Option<serde_json::Value>,
-    current_title: String,
-    current_content: String,
-    state: tauri::State<'_, SqliteState>
-) -> Result<String, String> {
-    println!("analyze_article_content called with prompt: {}", prompt);
-    
+// 構建文章分析的 AI 提示
+pub(crate) fn build_analysis_prompt(
+    prompt: &str,
+    selected_text: &str,
+    current_title: &str,
+    current_content: &str,
+) -> String {
     // 構建 AI 提示
-    let ai_prompt = format!(
+    format!(
         "Analyze the following blog article content and provide feedback based on the user's request:
 
 Article Title: {}
@@ -1017,26 +912,18 @@ Article Content:
 Provide detailed analysis on clarity, coherence, argument strength, and areas for improvement.
 Offer specific suggestions that address the user's request.",
         current_title, prompt, selected_text, current_content
-    );
-    
-    generate_with_gemini(ai_prompt, AgentType::Research, blog_id, project_data, state).await
+    )
 }
 
-// 使用 Gemini API 調整文章風格
-#[tauri::command]
-pub async fn adjust_article_style(
-    prompt: String,
-    selected_text: String,
-    blog_id: i64,
-    project_data: Option<serde_json::Value>,
-    current_title: String,
-    current_content: String,
-    state: tauri::State<'_, SqliteState>
-) -> Result<String, String> {
-    println!("adjust_article_style called with prompt: {}", prompt);
-    
+// 構建風格調整的 AI 提示
+pub(crate) fn build_style_prompt(
+    prompt: &str,
+    selected_text: &str,
+    current_title: &str,
+    current_content: &str,
+) -> String {
     // 構建 AI 提示
-    let ai_prompt = format!(
+    format!(
         "Review the following blog article and suggest improvements to style, tone, and readability based on the user's request:
 
 Article Title: {}
@@ -1053,26 +940,18 @@ Article Content:
 Provide specific suggestions for enhancing the writing style while preserving the original meaning and intent.
 Focus on making the content more engaging, clear, and effective for the target audience.",
         current_title, prompt, selected_text, current_content
-    );
-    
-    generate_with_gemini(ai_prompt, AgentType::Editor, blog_id, project_data, state).await
+    )
 }
 
-// 使用 Gemini API 進行最終審查
-#[tauri::command]
-pub async fn review_article_final(
-    prompt: String,
-    selected_text: String,
-    blog_id: i64,
-    project_data: Option<serde_json::Value>,
-    current_title: String, 
-    current_content: String,
-    state: tauri::State<'_, SqliteState>
-) -> Result<String, String> {
-    println!("review_article_final called with prompt: {}", prompt);
-    
+// 構建最終審查的 AI 提示
+pub(crate) fn build_review_prompt(
+    prompt: &str,
+    selected_text: &str,
+    current_title: &str,
+    current_content: &str,
+) -> String {
     // 構建 AI 提示
-    let ai_prompt = format!(
+    format!(
         "Perform a comprehensive final review of the following blog article, addressing the user's specific request:
 
 Article Title: {}
@@ -1090,26 +969,12 @@ Check for grammar, spelling, style consistency, and overall quality.
 Provide a thorough evaluation and specific recommendations based on the user's request.
 Include both strengths and areas for improvement in your assessment.",
         current_title, prompt, selected_text, current_content
-    );
-    
-    generate_with_gemini(ai_prompt, AgentType::Reviewer, blog_id, project_data, state).await
+    )
 }
 
-// 使用 Gemini API 進行內聯編輯
-#[tauri::command]
-pub async fn inline_edit_text(
-    selected_text: String, 
-    article_title: String, 
-    article_content: String, 
-    user_prompt: String,
-    blog_id: i64,
-    state: tauri::State<'_, SqliteState>
-) -> Result<String, String> {
-    println!("inline_edit_text called with prompt: {}", user_prompt);
-    println!("Selected text: {}", selected_text);
-    println!("Blog ID: {}", blog_id);
-    
-    let prompt = format!(
+// 構建內聯編輯的 AI 提示
+pub(crate) fn build_inline_edit_prompt(selected_text: &str, article_title: &str, user_prompt: &str) -> String {
+    format!(
         "You are an inline editor AI assistant. I need you to improve the following selected text from a blog article.
         
         Article Title: {}
@@ -1133,19 +998,7 @@ pub async fn inline_edit_text(
         If the selected text is a title, focus on making it more engaging and SEO-friendly.
         If the selected text is content, ensure it flows well with the surrounding text.",
         article_title, selected_text, user_prompt
-    );
-    
-    println!("Sending prompt to Gemini API");
-    match generate_with_gemini(prompt, AgentType::InlineEditor, blog_id, None, state).await {
-        Ok(response) => {
-            println!("Received response from Gemini API: {}", response);
-            Ok(response)
-        },
-        Err(e) => {
-            println!("Error from Gemini API: {}", e);
-            Err(e)
-        }
-    }
+    )
 }
 
 #[derive(Debug, Serialize)]
@@ -1159,18 +1012,18 @@ pub struct ArticleData {
 }
 
 #[tauri::command]
-pub async fn get_article_data(blog_id: i64) -> Result<ArticleData, String> {
-    let conn = db::init_db().map_err(|e| e.to_string())?;
-    
-    // First get the project_id from the blog
-    let project_id: i64 = conn.query_row(
-        "SELECT project_id FROM blogs WHERE id = ?",
-        [blog_id],
-        |row| row.get(0)
-    ).map_err(|e| format!("Failed to get project_id for blog {}: {}", blog_id, e))?;
-    
-    // Then get the project data
-    let project = db::get_project(project_id)?;
+pub async fn get_article_data(blog_id: i64, state: tauri::State<'_, SqliteState>) -> Result<ArticleData, AppError> {
+    let project = state.run(move |conn| {
+        // First get the project_id from the blog
+        let project_id: i64 = conn.query_row(
+            "SELECT project_id FROM blogs WHERE id = ?",
+            [blog_id],
+            |row| row.get(0)
+        ).map_err(|e| AppError::from(e).context(format!("Failed to get project_id for blog {}", blog_id)))?;
+        
+        // Then get the project data
+        db::load_project(conn, project_id)
+    }).await?;
     
     // Create and return the ArticleData struct
     Ok(ArticleData {
@@ -0,0 +1,510 @@
+use chrono::{Local, NaiveDateTime};
+use rusqlite::{Connection, OptionalExtension};
+use rusqlite::backup::Backup;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::SqliteState;
+use crate::db;
+use crate::embeddings;
+use crate::encryption;
+use crate::error::AppError;
+use crate::migrations::{self, MigrationState, MigrationStatus};
+use crate::rag;
+
+// 線上備份每次複製的頁數，以及兩次之間讓出給其他連線的時間
+const BACKUP_PAGES_PER_STEP: std::os::raw::c_int = 256;
+const BACKUP_STEP_PAUSE: Duration = Duration::from_millis(10);
+// integrity_check 失敗時最多列出的訊息數
+const MAX_INTEGRITY_MESSAGES: usize = 5;
+
+// 自動備份設定存在 settings 表
+const BACKUP_SCHEDULE_KEY: &str = "backup_schedule";
+const SNAPSHOT_DIR: &str = "snapshots";
+const SNAPSHOT_PREFIX: &str = "snapshot_";
+// Millisecond precision so snapshots taken in the same second keep separate files
+const SNAPSHOT_TIME_FORMAT: &str = "%Y%m%d_%H%M%S%.3f";
+// %.f also accepts names without a fraction, written before millisecond names were used
+const SNAPSHOT_PARSE_FORMAT: &str = "%Y%m%d_%H%M%S%.f";
+// 排程執行緒檢查是否需要備份的間隔，以及啟動後第一次檢查前的等待
+const SCHEDULER_TICK: Duration = Duration::from_secs(10 * 60);
+const SCHEDULER_START_DELAY: Duration = Duration::from_secs(60);
+
+// Scheduled and on-exit snapshots must not write the same file at once
+static SNAPSHOT_LOCK: Mutex<()> = Mutex::new(());
+
+// 自動備份與保留設定
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupSchedule {
+    pub enabled: bool,
+    pub interval_hours: u32,
+    pub snapshot_on_exit: bool,
+    // 保留每天、每週、每月最新的一份
+    pub keep_daily: usize,
+    pub keep_weekly: usize,
+    pub keep_monthly: usize,
+    // exports 目錄中保留的匯出檔數量；None 表示全部保留
+    pub max_exports: Option<usize>,
+}
+
+impl Default for BackupSchedule {
+    fn default() -> Self {
+        BackupSchedule {
+            enabled: true,
+            interval_hours: 24,
+            snapshot_on_exit: true,
+            keep_daily: 7,
+            keep_weekly: 4,
+            keep_monthly: 6,
+            max_exports: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SnapshotInfo {
+    pub file_name: String,
+    pub path: String,
+    pub created_at: String,
+    pub size_bytes: u64,
+    pub encrypted: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PruneResult {
+    pub kept: usize,
+    pub removed: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RestoreResult {
+    pub restored_from: String,
+    pub safety_copy: String,
+    pub migration: MigrationStatus,
+}
+
+// 資料庫所在目錄下的 backups 目錄
+pub fn backup_dir(db_path: &Path) -> Result<PathBuf, AppError> {
+    let dir = db_path.parent()
+        .ok_or_else(|| AppError::Internal("Invalid database path".to_string()))?
+        .join("backups");
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+// 在 dir 下以目前時間 (毫秒) 命名新的備份檔，同名檔案已存在時往後找下一個可用的名稱
+// Callers that may run at the same time must hold a lock around picking the name and writing the file
+pub fn unique_backup_path(dir: &Path, prefix: &str) -> PathBuf {
+    let mut time = Local::now().naive_local();
+    loop {
+        let path = dir.join(format!("{}{}.db", prefix, time.format(SNAPSHOT_TIME_FORMAT)));
+        if !path.exists() {
+            return path;
+        }
+        time += chrono::Duration::milliseconds(1);
+    }
+}
+
+// 以 SQLite 線上備份 API 複製資料庫，複製期間其他連線仍可讀寫
+// The copy uses the given passphrase, so an encrypted database stays encrypted
+pub fn backup_to(conn: &Connection, dest: &Path, passphrase: Option<&str>) -> Result<(), AppError> {
+    if dest.exists() {
+        fs::remove_file(dest)?;
+    }
+    let result = encryption::open_database(dest, passphrase)
+        .and_then(|mut dest_conn| {
+            let backup = Backup::new(conn, &mut dest_conn)?;
+            backup.run_to_completion(BACKUP_PAGES_PER_STEP, BACKUP_STEP_PAUSE, None)
+        });
+    if let Err(e) = result {
+        let _ = fs::remove_file(dest);
+        return Err(AppError::from(e).context("Backup failed"));
+    }
+    println!("Backed up database to {:?}", dest);
+    Ok(())
+}
+
+// 還原前檢查備份檔：完整性、資料表結構與版本
+pub fn check_backup_file(path: &Path, passphrase: Option<&str>) -> Result<i32, AppError> {
+    let conn = encryption::open_database(path, passphrase)
+        .map_err(|e| encryption::passphrase_error(e).context("Cannot open backup"))?;
+
+    let messages: Vec<String> = conn.prepare("PRAGMA integrity_check")?
+        .query_map([], |row| row.get(0))?
+        .collect::<Result<_, _>>()?;
+    if messages.len() != 1 || messages[0] != "ok" {
+        let total = messages.len();
+        let shown: Vec<String> = messages.into_iter().take(MAX_INTEGRITY_MESSAGES).collect();
+        return Err(AppError::validation(format!(
+            "Backup failed the integrity check ({} problems): {}", total, shown.join("; ")
+        )));
+    }
+
+    db::validate_db_schema(&conn)
+        .map_err(|e| AppError::validation(format!("Backup is not a valid database for this app: {}", e)))?;
+
+    let version: i32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if version > migrations::latest_version() {
+        return Err(AppError::validation(format!(
+            "Backup was made by a newer version of the app (schema version {}, this app supports up to {})",
+            version, migrations::latest_version()
+        )));
+    }
+    Ok(version)
+}
+
+// 以備份檔取代目前的資料庫；呼叫前必須關閉所有連線 (DbPool::exclusive)
+// Returns the path of the safety copy of the database that was replaced.
+pub fn restore_from(db_path: &Path, candidate: &Path, candidate_passphrase: Option<&str>, passphrase: Option<&str>) -> Result<PathBuf, AppError> {
+    // A unique name so a second restore never replaces the copy of the original database
+    let safety_copy = unique_backup_path(&backup_dir(db_path)?, "pre_restore_");
+    let live = encryption::open_database(db_path, passphrase)?;
+    backup_to(&live, &safety_copy, passphrase)?;
+
+    // Rewrite the backup with the current database's key so an unlocked session can keep using it
+    let restoring = encryption::sibling_path(db_path, ".restoring");
+    {
+        let source = encryption::open_database(candidate, candidate_passphrase)?;
+        encryption::export_database_copy(&source, &restoring, passphrase)?;
+    }
+    if let Err(e) = encryption::verify_database(&restoring, passphrase) {
+        let _ = fs::remove_file(&restoring);
+        return Err(e.context("Restore was not applied"));
+    }
+
+    live.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+    drop(live);
+    encryption::replace_database_file(db_path, &restoring)?;
+    println!("Restored database from {:?}; previous database saved to {:?}", candidate, safety_copy);
+    Ok(safety_copy)
+}
+
+// 從 .db 備份還原
+// passphrase is only needed when the backup is encrypted with a different passphrase
+#[tauri::command]
+pub async fn restore_database(
+    path: String,
+    passphrase: Option<String>,
+    app_handle: AppHandle,
+    state: tauri::State<'_, SqliteState>,
+    migration_state: tauri::State<'_, MigrationState>,
+) -> Result<RestoreResult, AppError> {
+    if state.is_locked() {
+        return Err(AppError::Locked("Unlock the database before restoring a backup".to_string()));
+    }
+
+    let candidate = PathBuf::from(&path);
+    if !candidate.exists() {
+        return Err(AppError::NotFound(format!("Backup file {} does not exist", path)));
+    }
+    if candidate.canonicalize()? == state.path().canonicalize()? {
+        return Err(AppError::validation("Choose a backup file, not the live database"));
+    }
+
+    let pool = state.inner().clone();
+    let (safety_copy, migration) = tauri::async_runtime::spawn_blocking(move || {
+        let candidate_key = if encryption::is_encrypted(&candidate) {
+            Some(passphrase.or_else(|| pool.passphrase())
+                .ok_or_else(|| AppError::validation("This backup is encrypted; enter its passphrase"))?)
+        } else {
+            None
+        };
+        let version = check_backup_file(&candidate, candidate_key.as_deref())?;
+        println!("Backup {:?} passed checks (schema version {})", candidate, version);
+
+        pool.exclusive(|db_path, current| {
+            let safety_copy = restore_from(db_path, &candidate, candidate_key.as_deref(), current.as_deref())?;
+            // Older backups are brought up to the current schema
+            let migration = migrations::run_migrations(db_path, current.as_deref());
+            Ok((safety_copy, migration))
+        })
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Restore task failed: {}", e)))??;
+
+    *migration_state.0.lock().unwrap() = migration.clone();
+    if migration.error.is_none() {
+        let conn = state.get()?;
+        if let Err(e) = rag::index_missing_sources(&conn) {
+            println!("Failed to build retrieval index: {}", e);
+        }
+        embeddings::schedule_embedding(&app_handle);
+    }
+
+    Ok(RestoreResult {
+        restored_from: path,
+        safety_copy: safety_copy.to_string_lossy().to_string(),
+        migration,
+    })
+}
+
+fn read_schedule(conn: &Connection) -> Result<BackupSchedule, AppError> {
+    let value: Option<String> = conn.query_row(
+        "SELECT value FROM settings WHERE key = ?1",
+        [BACKUP_SCHEDULE_KEY],
+        |row| row.get(0)
+    ).optional()?;
+    match value {
+        Some(value) => serde_json::from_str(&value)
+            .map_err(|e| AppError::Parse(format!("Invalid backup schedule: {}", e))),
+        None => Ok(BackupSchedule::default()),
+    }
+}
+
+fn snapshot_dir(db_path: &Path) -> Result<PathBuf, AppError> {
+    let dir = backup_dir(db_path)?.join(SNAPSHOT_DIR);
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+// 從檔名取得備份時間；不是快照的檔案回傳 None
+fn snapshot_time(file_name: &str) -> Option<NaiveDateTime> {
+    let stamp = file_name.strip_prefix(SNAPSHOT_PREFIX)?.strip_suffix(".db")?;
+    NaiveDateTime::parse_from_str(stamp, SNAPSHOT_PARSE_FORMAT).ok()
+}
+
+// 所有快照，最新的在前
+fn list_snapshot_files(db_path: &Path) -> Result<Vec<(NaiveDateTime, PathBuf)>, AppError> {
+    let mut snapshots = Vec::new();
+    for entry in fs::read_dir(snapshot_dir(db_path)?)? {
+        let path = entry?.path();
+        let time = path.file_name().and_then(|n| n.to_str()).and_then(snapshot_time);
+        if let Some(time) = time {
+            snapshots.push((time, path));
+        }
+    }
+    snapshots.sort_by_key(|(time, _)| std::cmp::Reverse(*time));
+    Ok(snapshots)
+}
+
+// 保留規則：每天、每週、每月各保留最新的一份，最新的快照一定保留
+fn snapshots_to_keep(snapshots: &[(NaiveDateTime, PathBuf)], schedule: &BackupSchedule) -> HashSet<usize> {
+    let mut keep = HashSet::new();
+    if !snapshots.is_empty() {
+        keep.insert(0);
+    }
+    let periods: [(&str, usize); 3] = [
+        ("%Y-%m-%d", schedule.keep_daily),
+        ("%G-W%V", schedule.keep_weekly),
+        ("%Y-%m", schedule.keep_monthly),
+    ];
+    for (format, count) in periods {
+        let mut seen = HashSet::new();
+        for (index, (time, _)) in snapshots.iter().enumerate() {
+            let period = time.format(format).to_string();
+            if seen.contains(&period) {
+                continue;
+            }
+            if seen.len() >= count {
+                break;
+            }
+            seen.insert(period);
+            keep.insert(index);
+        }
+    }
+    keep
+}
+
+// 套用保留規則，刪除多餘的快照與匯出檔
+fn prune(db_path: &Path, schedule: &BackupSchedule) -> Result<PruneResult, AppError> {
+    let snapshots = list_snapshot_files(db_path)?;
+    let keep = snapshots_to_keep(&snapshots, schedule);
+    let mut removed = Vec::new();
+    for (index, (_, path)) in snapshots.iter().enumerate() {
+        if !keep.contains(&index) {
+            fs::remove_file(path)?;
+            removed.push(path.to_string_lossy().to_string());
+        }
+    }
+
+    if let Some(max_exports) = schedule.max_exports {
+        let export_dir = db_path.parent()
+            .ok_or_else(|| AppError::Internal("Invalid database path".to_string()))?
+            .join("exports");
+        if export_dir.exists() {
+            // Export names embed a sortable timestamp, so newest sort last
+            let mut exports: Vec<PathBuf> = fs::read_dir(&export_dir)?
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.starts_with("stingtao_export_")))
+                .collect();
+            exports.sort();
+            let excess = exports.len().saturating_sub(max_exports);
+            for path in exports.into_iter().take(excess) {
+                fs::remove_file(&path)?;
+                removed.push(path.to_string_lossy().to_string());
+            }
+        }
+    }
+
+    println!("Pruned {} backups, kept {} snapshots", removed.len(), keep.len());
+    Ok(PruneResult { kept: keep.len(), removed })
+}
+
+// 建立一份快照並套用保留規則
+fn take_snapshot(state: &SqliteState) -> Result<PathBuf, AppError> {
+    let _guard = SNAPSHOT_LOCK.lock().unwrap();
+    let db_path = state.path().to_path_buf();
+    let conn = state.get()?;
+    let schedule = read_schedule(&conn)?;
+
+    // SNAPSHOT_LOCK keeps picking the name race-free
+    let path = unique_backup_path(&snapshot_dir(&db_path)?, SNAPSHOT_PREFIX);
+    backup_to(&conn, &path, state.passphrase().as_deref())?;
+    drop(conn);
+
+    prune(&db_path, &schedule)?;
+    Ok(path)
+}
+
+// 距離上一份快照超過設定的間隔時建立新的快照
+fn snapshot_if_due(state: &SqliteState) -> Result<Option<PathBuf>, AppError> {
+    if state.is_locked() {
+        return Ok(None);
+    }
+    let schedule = read_schedule(&*state.get()?)?;
+    if !schedule.enabled {
+        return Ok(None);
+    }
+
+    let latest = list_snapshot_files(state.path())?.first().map(|(time, _)| *time);
+    let due = match latest {
+        Some(time) => Local::now().naive_local() - time >= chrono::Duration::hours(schedule.interval_hours as i64),
+        None => true,
+    };
+    if !due {
+        return Ok(None);
+    }
+    take_snapshot(state).map(Some)
+}
+
+// 啟動背景排程執行緒
+pub fn start_scheduler(app: AppHandle) {
+    let spawned = std::thread::Builder::new()
+        .name("backup-scheduler".to_string())
+        .spawn(move || {
+            std::thread::sleep(SCHEDULER_START_DELAY);
+            loop {
+                let state = app.state::<SqliteState>();
+                match snapshot_if_due(&state) {
+                    Ok(Some(path)) => println!("Scheduled backup written to {:?}", path),
+                    Ok(None) => {},
+                    Err(e) => println!("Scheduled backup failed: {}", e),
+                }
+                std::thread::sleep(SCHEDULER_TICK);
+            }
+        });
+    if let Err(e) = spawned {
+        println!("Failed to start backup scheduler: {}", e);
+    }
+}
+
+// 關閉程式時的快照
+pub fn snapshot_on_exit(app: &AppHandle) {
+    let state = app.state::<SqliteState>();
+    if state.is_locked() {
+        return;
+    }
+    let enabled = state.get()
+        .and_then(|conn| read_schedule(&conn))
+        .map(|schedule| schedule.enabled && schedule.snapshot_on_exit);
+    match enabled {
+        Ok(true) => match take_snapshot(&state) {
+            Ok(path) => println!("Exit backup written to {:?}", path),
+            Err(e) => println!("Exit backup failed: {}", e),
+        },
+        Ok(false) => {},
+        Err(e) => println!("Failed to read backup schedule: {}", e),
+    }
+}
+
+// 獲取自動備份設定
+#[tauri::command]
+pub async fn get_backup_schedule(state: tauri::State<'_, SqliteState>) -> Result<BackupSchedule, AppError> {
+    state.run(|conn| read_schedule(conn)).await
+}
+
+// 保存自動備份設定
+#[tauri::command]
+pub async fn set_backup_schedule(schedule: BackupSchedule, state: tauri::State<'_, SqliteState>) -> Result<BackupSchedule, AppError> {
+    if schedule.interval_hours == 0 {
+        return Err(AppError::validation("Backup interval must be at least one hour"));
+    }
+    if schedule.keep_daily + schedule.keep_weekly + schedule.keep_monthly == 0 {
+        return Err(AppError::validation("Keep at least one daily, weekly or monthly backup"));
+    }
+
+    state.run(move |conn| {
+        let value = serde_json::to_string(&schedule)?;
+        conn.execute(
+            "INSERT INTO settings (key, value, created_at, updated_at) VALUES (?1, ?2, ?3, ?3)
+             ON CONFLICT(key) DO UPDATE SET value = ?2, updated_at = ?3",
+            rusqlite::params![BACKUP_SCHEDULE_KEY, value, Local::now().to_rfc3339()],
+        )?;
+        println!("Saved backup schedule: {:?}", schedule);
+        Ok(schedule)
+    }).await
+}
+
+// 立即建立快照
+#[tauri::command]
+pub async fn create_snapshot(state: tauri::State<'_, SqliteState>) -> Result<String, AppError> {
+    let pool = state.inner().clone();
+    let path = tauri::async_runtime::spawn_blocking(move || take_snapshot(&pool))
+        .await
+        .map_err(|e| AppError::Internal(format!("Backup task failed: {}", e)))??;
+    Ok(path.to_string_lossy().to_string())
+}
+
+// 列出自動備份快照，最新的在前
+#[tauri::command]
+pub async fn list_snapshots(state: tauri::State<'_, SqliteState>) -> Result<Vec<SnapshotInfo>, AppError> {
+    // 掃描目錄與讀取檔頭都是阻塞操作
+    let db_path = state.path().to_path_buf();
+    tauri::async_runtime::spawn_blocking(move || {
+        let snapshots = list_snapshot_files(&db_path)?;
+        snapshots.into_iter().map(|(time, path)| {
+            Ok(SnapshotInfo {
+                file_name: path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+                path: path.to_string_lossy().to_string(),
+                created_at: time.format("%Y-%m-%dT%H:%M:%S").to_string(),
+                size_bytes: fs::metadata(&path)?.len(),
+                encrypted: encryption::is_encrypted(&path),
+            })
+        }).collect()
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Snapshot listing failed: {}", e)))?
+}
+
+// 依保留規則刪除多餘的快照
+#[tauri::command]
+pub async fn prune_snapshots(state: tauri::State<'_, SqliteState>) -> Result<PruneResult, AppError> {
+    let db_path = state.path().to_path_buf();
+    state.run(move |conn| {
+        let schedule = read_schedule(conn)?;
+        let _guard = SNAPSHOT_LOCK.lock().unwrap();
+        prune(&db_path, &schedule)
+    }).await
+}
+
+// 從快照還原
+#[tauri::command]
+pub async fn restore_snapshot(
+    file_name: String,
+    passphrase: Option<String>,
+    app_handle: AppHandle,
+    state: tauri::State<'_, SqliteState>,
+    migration_state: tauri::State<'_, MigrationState>,
+) -> Result<RestoreResult, AppError> {
+    // Only plain snapshot names are accepted, never paths
+    if snapshot_time(&file_name).is_none() {
+        return Err(AppError::validation(format!("{} is not a snapshot", file_name)));
+    }
+    let path = snapshot_dir(state.path())?.join(&file_name);
+    restore_database(path.to_string_lossy().to_string(), passphrase, app_handle, state, migration_state).await
+}
@@ -0,0 +1,183 @@
+use rusqlite::Connection;
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+use crate::encryption;
+use crate::error::AppError;
+
+// 連線池大小：WAL 模式下多個讀取可以與一個寫入同時進行
+pub const DEFAULT_POOL_SIZE: usize = 4;
+// 其他連線持有寫入鎖時等待的時間
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+struct PoolState {
+    idle: Vec<Connection>,
+    open: usize,
+    // 加密資料庫的密碼；解鎖前為 None
+    passphrase: Option<String>,
+    locked: bool,
+    // 更換資料庫檔案時暫停借出連線
+    exclusive: bool,
+}
+
+struct PoolInner {
+    path: PathBuf,
+    max_size: usize,
+    state: Mutex<PoolState>,
+    available: Condvar,
+}
+
+// 共用的資料庫連線池，所有指令透過它存取資料庫
+#[derive(Clone)]
+pub struct DbPool {
+    inner: Arc<PoolInner>,
+}
+
+// 從連線池借出的連線，drop 時歸還
+pub struct PooledConnection {
+    conn: Option<Connection>,
+    inner: Arc<PoolInner>,
+}
+
+impl Deref for PooledConnection {
+    type Target = Connection;
+    fn deref(&self) -> &Connection {
+        self.conn.as_ref().unwrap()
+    }
+}
+
+impl DerefMut for PooledConnection {
+    fn deref_mut(&mut self) -> &mut Connection {
+        self.conn.as_mut().unwrap()
+    }
+}
+
+impl Drop for PooledConnection {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            let mut state = self.inner.state.lock().unwrap();
+            state.idle.push(conn);
+            // Wake everyone: an exclusive caller may be waiting for the last connection
+            self.inner.available.notify_all();
+        }
+    }
+}
+
+fn open_connection(path: &Path, passphrase: Option<&str>) -> Result<Connection, rusqlite::Error> {
+    let conn = encryption::open_database(path, passphrase)?;
+    conn.busy_timeout(BUSY_TIMEOUT)?;
+    // journal_mode returns the resulting mode as a row
+    conn.query_row("PRAGMA journal_mode = WAL", [], |_| Ok(()))?;
+    // Overwrite deleted content so removed secrets do not stay readable in free pages
+    conn.pragma_update(None, "secure_delete", true)?;
+    Ok(conn)
+}
+
+impl DbPool {
+    // locked: 資料庫已加密，需先呼叫 unlock 才能借出連線
+    pub fn new(path: PathBuf, max_size: usize, locked: bool) -> Self {
+        println!("Creating database pool for {:?} with {} connections (locked: {})", path, max_size, locked);
+        DbPool {
+            inner: Arc::new(PoolInner {
+                path,
+                max_size: max_size.max(1),
+                state: Mutex::new(PoolState { idle: Vec::new(), open: 0, passphrase: None, locked, exclusive: false }),
+                available: Condvar::new(),
+            }),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.inner.path
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.inner.state.lock().unwrap().locked
+    }
+
+    // 目前使用的密碼，供需要另外開啟資料庫檔案的功能使用 (例如匯出)
+    pub fn passphrase(&self) -> Option<String> {
+        self.inner.state.lock().unwrap().passphrase.clone()
+    }
+
+    // 設定密碼後開始借出連線；呼叫前應先確認密碼正確
+    pub fn unlock(&self, passphrase: String) {
+        let mut state = self.inner.state.lock().unwrap();
+        state.passphrase = Some(passphrase);
+        state.locked = false;
+        self.inner.available.notify_all();
+    }
+
+    // 等所有連線歸還並關閉後執行 f，期間不借出連線
+    // f may replace the database file and update the passphrase; new connections pick up both
+    pub fn exclusive<T>(&self, f: impl FnOnce(&Path, &mut Option<String>) -> Result<T, AppError>) -> Result<T, AppError> {
+        let mut state = self.inner.state.lock().unwrap();
+        while state.exclusive {
+            state = self.inner.available.wait(state).unwrap();
+        }
+        state.exclusive = true;
+        while state.idle.len() < state.open {
+            state = self.inner.available.wait(state).unwrap();
+        }
+        // Close every connection so none keeps the old file or key open
+        state.idle.clear();
+        state.open = 0;
+
+        let result = f(&self.inner.path, &mut state.passphrase);
+
+        state.exclusive = false;
+        self.inner.available.notify_all();
+        result
+    }
+
+    // 借出一個連線；連線都在使用中時等待歸還
+    // Blocks the calling thread, so async code should go through run() instead
+    pub fn get(&self) -> Result<PooledConnection, AppError> {
+        let mut state = self.inner.state.lock().unwrap();
+        loop {
+            if state.locked {
+                return Err(AppError::Locked("The database is encrypted. Unlock it with your passphrase first.".to_string()));
+            }
+            if state.exclusive {
+                state = self.inner.available.wait(state).unwrap();
+                continue;
+            }
+            if let Some(conn) = state.idle.pop() {
+                return Ok(PooledConnection { conn: Some(conn), inner: self.inner.clone() });
+            }
+            if state.open < self.inner.max_size {
+                state.open += 1;
+                let passphrase = state.passphrase.clone();
+                drop(state);
+                return match open_connection(&self.inner.path, passphrase.as_deref()) {
+                    Ok(conn) => Ok(PooledConnection { conn: Some(conn), inner: self.inner.clone() }),
+                    Err(e) => {
+                        self.inner.state.lock().unwrap().open -= 1;
+                        self.inner.available.notify_all();
+                        Err(AppError::Database { message: format!("Failed to open database: {}", e), busy: false })
+                    }
+                };
+            }
+            state = self.inner.available.wait(state).unwrap();
+        }
+    }
+
+    // 在阻塞執行緒上使用連線，避免佔用非同步執行環境
+    // Pool errors are converted with From<AppError> into the closure's error type
+    pub async fn run<T, E, F>(&self, f: F) -> Result<T, E>
+    where
+        F: FnOnce(&mut Connection) -> Result<T, E> + Send + 'static,
+        T: Send + 'static,
+        E: From<AppError> + Send + 'static,
+    {
+        let pool = self.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            let mut conn = pool.get()?;
+            f(&mut conn)
+        })
+        .await
+        .map_err(|e| E::from(AppError::Internal(format!("Database task failed: {}", e))))?
+    }
+}
@@ -0,0 +1,437 @@
+use serde::{Deserialize, Serialize};
+
+use crate::export_common::is_cjk;
+
+// 差異操作
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffOp {
+    Equal,
+    Insert,
+    Delete,
+}
+
+// 一段連續的相同操作
+#[derive(Debug, Clone, Serialize)]
+pub struct DiffSegment {
+    pub op: DiffOp,
+    pub text: String,
+}
+
+// Myers 差異演算法，回傳逐項的操作序列
+// Runs in O((N+M)·D) time and O(N+M) memory (linear-space variant), so full rewrites of long texts stay cheap
+pub fn diff_sequences<T: PartialEq>(a: &[T], b: &[T]) -> Vec<(DiffOp, usize)> {
+    let max = a.len() + b.len();
+    let mut ops = Vec::with_capacity(max);
+    // Diagonal buffers shared by every level of the recursion
+    let mut forward = vec![0isize; 2 * max + 3];
+    let mut backward = vec![0isize; 2 * max + 3];
+    diff_range(a, b, 0, 0, &mut forward, &mut backward, &mut ops);
+    ops
+}
+
+// 回傳的 index：Equal 與 Delete 指向 a，Insert 指向 b (a_start / b_start 為此段在原序列中的位置)
+fn diff_range<T: PartialEq>(
+    a: &[T],
+    b: &[T],
+    a_start: usize,
+    b_start: usize,
+    forward: &mut [isize],
+    backward: &mut [isize],
+    ops: &mut Vec<(DiffOp, usize)>,
+) {
+    // Trim the common prefix and suffix first; the search only has to cover the changed middle
+    let prefix = a.iter().zip(b.iter()).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..].iter().rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    ops.extend((0..prefix).map(|i| (DiffOp::Equal, a_start + i)));
+    let a_mid = &a[prefix..a.len() - suffix];
+    let b_mid = &b[prefix..b.len() - suffix];
+    let (a_base, b_base) = (a_start + prefix, b_start + prefix);
+
+    if a_mid.is_empty() {
+        ops.extend((0..b_mid.len()).map(|i| (DiffOp::Insert, b_base + i)));
+    } else if b_mid.is_empty() {
+        ops.extend((0..a_mid.len()).map(|i| (DiffOp::Delete, a_base + i)));
+    } else {
+        // 以中間蛇分成前後兩半，各自遞迴
+        let (x, y, u, v) = middle_snake(a_mid, b_mid, forward, backward);
+        diff_range(&a_mid[..x], &b_mid[..y], a_base, b_base, forward, backward, ops);
+        ops.extend((x..u).map(|i| (DiffOp::Equal, a_base + i)));
+        diff_range(&a_mid[u..], &b_mid[v..], a_base + u, b_base + v, forward, backward, ops);
+    }
+
+    ops.extend((a.len() - suffix..a.len()).map(|i| (DiffOp::Equal, a_start + i)));
+}
+
+// 找出最短編輯路徑中間的一段相同區段，回傳其起點 (x, y) 與終點 (u, v)
+// Both inputs are non-empty and differ at their first and last items
+fn middle_snake<T: PartialEq>(a: &[T], b: &[T], forward: &mut [isize], backward: &mut [isize]) -> (usize, usize, usize, usize) {
+    let n = a.len() as isize;
+    let m = b.len() as isize;
+    let delta = n - m;
+    let odd = delta % 2 != 0;
+    let offset = (forward.len() / 2) as isize;
+    let at = |k: isize| (k + offset) as usize;
+    forward[at(1)] = 0;
+    backward[at(1)] = 0;
+
+    for d in 0..=(n + m + 1) / 2 {
+        // 從起點往後延伸
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && forward[at(k - 1)] < forward[at(k + 1)]) {
+                forward[at(k + 1)]
+            } else {
+                forward[at(k - 1)] + 1
+            };
+            let mut y = x - k;
+            let (start_x, start_y) = (x, y);
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            forward[at(k)] = x;
+            // The reverse search has finished round d - 1; its diagonal delta - k must be in range
+            if odd && (delta - k).abs() < d && x + backward[at(delta - k)] >= n {
+                return (start_x as usize, start_y as usize, x as usize, y as usize);
+            }
+        }
+
+        // 從終點往前延伸 (x 為距離終點的長度)
+        for k in (-d..=d).step_by(2) {
+            let mut x = if k == -d || (k != d && backward[at(k - 1)] < backward[at(k + 1)]) {
+                backward[at(k + 1)]
+            } else {
+                backward[at(k - 1)] + 1
+            };
+            let mut y = x - k;
+            let (start_x, start_y) = (x, y);
+            while x < n && y < m && a[(n - 1 - x) as usize] == b[(m - 1 - y) as usize] {
+                x += 1;
+                y += 1;
+            }
+            backward[at(k)] = x;
+            if !odd && (delta - k).abs() <= d && x + forward[at(delta - k)] >= n {
+                return ((n - x) as usize, (m - y) as usize, (n - start_x) as usize, (m - start_y) as usize);
+            }
+        }
+    }
+
+    unreachable!("the forward and reverse searches always meet")
+}
+
+// 合併相鄰的相同操作為片段
+fn collect_segments(a: &[&str], b: &[&str], ops: &[(DiffOp, usize)], separator: &str) -> Vec<DiffSegment> {
+    let mut segments: Vec<DiffSegment> = Vec::new();
+    for (op, index) in ops {
+        let piece = match op {
+            DiffOp::Insert => b[*index],
+            _ => a[*index],
+        };
+        match segments.last_mut() {
+            Some(last) if last.op == *op => {
+                last.text.push_str(separator);
+                last.text.push_str(piece);
+            },
+            _ => segments.push(DiffSegment { op: *op, text: piece.to_string() }),
+        }
+    }
+    segments
+}
+
+// 以行為單位比較兩段文字
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffSegment> {
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+    let ops = diff_sequences(&a, &b);
+    collect_segments(&a, &b, &ops, "\n")
+}
+
+// 比較粒度
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Granularity {
+    #[default]
+    Word,
+    Sentence,
+}
+
+// 建議中的一處修改，start/end 為原文中的位元組位置
+#[derive(Debug, Clone, Serialize)]
+pub struct DiffHunk {
+    pub index: usize,
+    pub start: usize,
+    pub end: usize,
+    pub original: String,
+    pub replacement: String,
+    pub context_before: String,
+    pub context_after: String,
+}
+
+const HUNK_CONTEXT_CHARS: usize = 40;
+
+#[derive(PartialEq)]
+enum TokenClass {
+    Word,
+    Space,
+    Single,
+}
+
+fn token_class(c: char) -> TokenClass {
+    if c.is_whitespace() {
+        TokenClass::Space
+    } else if c.is_alphanumeric() && !is_cjk(c) {
+        TokenClass::Word
+    } else {
+        TokenClass::Single
+    }
+}
+
+// 切分為單字、空白與標點；CJK 每個字元為一個單位
+// Tokens are slices of the input, so joining them reproduces the text exactly
+pub fn tokenize_words(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut current: Option<TokenClass> = None;
+    for (i, c) in text.char_indices() {
+        let class = token_class(c);
+        let continues = class != TokenClass::Single && current.as_ref() == Some(&class);
+        if !continues && i > start {
+            tokens.push(&text[start..i]);
+            start = i;
+        }
+        current = Some(class);
+    }
+    if start < text.len() {
+        tokens.push(&text[start..]);
+    }
+    tokens
+}
+
+// 切分為句子，句尾標點與其後的空白屬於該句
+pub fn tokenize_sentences(text: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut ended = false;
+    for (i, c) in text.char_indices() {
+        if ended && !c.is_whitespace() {
+            tokens.push(&text[start..i]);
+            start = i;
+            ended = false;
+        }
+        if matches!(c, '.' | '!' | '?' | '。' | '！' | '？' | '\n') {
+            ended = true;
+        }
+    }
+    if start < text.len() {
+        tokens.push(&text[start..]);
+    }
+    tokens
+}
+
+fn tokenize(text: &str, granularity: Granularity) -> Vec<&str> {
+    match granularity {
+        Granularity::Word => tokenize_words(text),
+        Granularity::Sentence => tokenize_sentences(text),
+    }
+}
+
+// 以單字或句子為單位比較，回傳可直接呈現的片段
+pub fn diff_text(old: &str, new: &str, granularity: Granularity) -> Vec<DiffSegment> {
+    let a = tokenize(old, granularity);
+    let b = tokenize(new, granularity);
+    let ops = diff_sequences(&a, &b);
+    collect_segments(&a, &b, &ops, "")
+}
+
+fn context_before(text: &str, end: usize) -> String {
+    let chars: Vec<char> = text[..end].chars().rev().take(HUNK_CONTEXT_CHARS).collect();
+    chars.into_iter().rev().collect()
+}
+
+fn context_after(text: &str, start: usize) -> String {
+    text[start..].chars().take(HUNK_CONTEXT_CHARS).collect()
+}
+
+// 計算修改區塊；只以空白分隔的相鄰修改會合併為一個區塊
+pub fn compute_hunks(old: &str, new: &str, granularity: Granularity) -> Vec<DiffHunk> {
+    let a = tokenize(old, granularity);
+    let b = tokenize(new, granularity);
+    let ops = diff_sequences(&a, &b);
+
+    // 每個 token 在原文中的起始位置
+    let mut a_offsets = Vec::with_capacity(a.len() + 1);
+    let mut offset = 0;
+    for token in &a {
+        a_offsets.push(offset);
+        offset += token.len();
+    }
+    a_offsets.push(offset);
+
+    let mut raw: Vec<(usize, usize, String)> = Vec::new();
+    let mut position = 0;
+    let mut open: Option<(usize, usize, String)> = None;
+    for (op, index) in &ops {
+        match op {
+            DiffOp::Equal => {
+                if let Some(hunk) = open.take() {
+                    raw.push(hunk);
+                }
+                position = a_offsets[*index + 1];
+            },
+            DiffOp::Delete => {
+                let hunk = open.get_or_insert((position, position, String::new()));
+                hunk.1 = a_offsets[*index + 1];
+                position = hunk.1;
+            },
+            DiffOp::Insert => {
+                let hunk = open.get_or_insert((position, position, String::new()));
+                hunk.2.push_str(b[*index]);
+            },
+        }
+    }
+    if let Some(hunk) = open.take() {
+        raw.push(hunk);
+    }
+
+    let mut merged: Vec<(usize, usize, String)> = Vec::new();
+    for (start, end, replacement) in raw {
+        match merged.last_mut() {
+            Some(last) if old[last.1..start].trim().is_empty() => {
+                let gap = &old[last.1..start];
+                last.2.push_str(gap);
+                last.2.push_str(&replacement);
+                last.1 = end;
+            },
+            _ => merged.push((start, end, replacement)),
+        }
+    }
+
+    merged.into_iter()
+        .enumerate()
+        .map(|(index, (start, end, replacement))| DiffHunk {
+            index,
+            start,
+            end,
+            original: old[start..end].to_string(),
+            replacement,
+            context_before: context_before(old, start),
+            context_after: context_after(old, end),
+        })
+        .collect()
+}
+
+// 將接受的修改套用到原文；修改必須依位置排序且不重疊
+pub fn apply_hunks(old: &str, hunks: &[(usize, usize, String)]) -> Result<String, String> {
+    let mut result = String::with_capacity(old.len());
+    let mut position = 0;
+    for (start, end, replacement) in hunks {
+        if *start < position || *end < *start || *end > old.len()
+            || !old.is_char_boundary(*start) || !old.is_char_boundary(*end) {
+            return Err(format!("Suggestion range {}..{} does not fit the original text", start, end));
+        }
+        result.push_str(&old[position..*start]);
+        result.push_str(replacement);
+        position = *end;
+    }
+    result.push_str(&old[position..]);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 由操作序列重建兩邊的序列，並回傳編輯次數
+    fn replay(a: &[u8], b: &[u8], ops: &[(DiffOp, usize)]) -> usize {
+        let mut old = Vec::new();
+        let mut new = Vec::new();
+        let mut edits = 0;
+        for (op, index) in ops {
+            match op {
+                DiffOp::Equal => {
+                    old.push(a[*index]);
+                    new.push(a[*index]);
+                },
+                DiffOp::Delete => {
+                    old.push(a[*index]);
+                    edits += 1;
+                },
+                DiffOp::Insert => {
+                    new.push(b[*index]);
+                    edits += 1;
+                },
+            }
+        }
+        assert_eq!(old, a);
+        assert_eq!(new, b);
+        edits
+    }
+
+    // 以最長共同子序列計算最少編輯次數
+    fn edit_distance(a: &[u8], b: &[u8]) -> usize {
+        let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+        for i in 0..a.len() {
+            for j in 0..b.len() {
+                lcs[i + 1][j + 1] = if a[i] == b[j] { lcs[i][j] + 1 } else { lcs[i][j + 1].max(lcs[i + 1][j]) };
+            }
+        }
+        a.len() + b.len() - 2 * lcs[a.len()][b.len()]
+    }
+
+    #[test]
+    fn diff_sequences_is_minimal() {
+        // Small alphabets produce many partial matches
+        let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = move |limit: u64| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed % limit
+        };
+        for _ in 0..500 {
+            let a: Vec<u8> = (0..next(20)).map(|_| b'a' + next(4) as u8).collect();
+            let b: Vec<u8> = (0..next(20)).map(|_| b'a' + next(4) as u8).collect();
+            let ops = diff_sequences(&a, &b);
+            assert_eq!(replay(&a, &b, &ops), edit_distance(&a, &b), "{:?} -> {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn diff_sequences_handles_edges() {
+        assert!(diff_sequences::<u8>(&[], &[]).is_empty());
+        assert_eq!(diff_sequences(b"", b"ab"), vec![(DiffOp::Insert, 0), (DiffOp::Insert, 1)]);
+        assert_eq!(diff_sequences(b"ab", b""), vec![(DiffOp::Delete, 0), (DiffOp::Delete, 1)]);
+        assert_eq!(diff_sequences(b"abc", b"abc").len(), 3);
+        assert_eq!(replay(b"abcabba", b"cbabac", &diff_sequences(b"abcabba", b"cbabac")), 5);
+    }
+
+    #[test]
+    fn full_rewrite_of_a_long_text() {
+        let old: String = (0..3000).map(|i| format!("alpha{} ", i)).collect();
+        let new: String = (0..3000).map(|i| format!("omega{} ", i)).collect();
+
+        let segments = diff_text(&old, &new, Granularity::Word);
+        let rebuilt_old: String = segments.iter().filter(|s| s.op != DiffOp::Insert).map(|s| s.text.as_str()).collect();
+        let rebuilt_new: String = segments.iter().filter(|s| s.op != DiffOp::Delete).map(|s| s.text.as_str()).collect();
+        assert_eq!(rebuilt_old, old);
+        assert_eq!(rebuilt_new, new);
+
+        // Whitespace-only gaps merge every change into a single hunk
+        let hunks = compute_hunks(&old, &new, Granularity::Word);
+        assert_eq!(hunks.len(), 1);
+        let accepted: Vec<(usize, usize, String)> = hunks.iter().map(|h| (h.start, h.end, h.replacement.clone())).collect();
+        assert_eq!(apply_hunks(&old, &accepted).unwrap(), new);
+    }
+
+    #[test]
+    fn hunks_cover_separate_changes() {
+        let old = "The quick brown fox jumps over the lazy dog.";
+        let new = "The quick red fox jumps over the sleepy dog.";
+        let hunks = compute_hunks(old, new, Granularity::Word);
+        let changes: Vec<(&str, &str)> = hunks.iter().map(|h| (h.original.as_str(), h.replacement.as_str())).collect();
+        assert_eq!(changes, vec![("brown", "red"), ("lazy", "sleepy")]);
+    }
+}
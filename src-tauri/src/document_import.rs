@@ -0,0 +1,892 @@
+use chrono::Local;
+use quick_xml::events::{BytesStart, Event as XmlEvent};
+use quick_xml::Reader;
+use regex::{Regex, RegexBuilder};
+use rusqlite::{params, Transaction};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use tauri::AppHandle;
+use zip::ZipArchive;
+
+use crate::SqliteState;
+use crate::db;
+use crate::embeddings;
+use crate::error::AppError;
+use crate::import::{self, item, ImportMode, ImportReport, IMPORT_AUTHOR};
+use crate::rag;
+use crate::revisions;
+
+// 第一個分章點之前的內容沒有標題時使用
+const FRONT_MATTER_TITLE: &str = "Front Matter";
+
+#[derive(Debug, Default, Deserialize)]
+pub struct DocumentImportOptions {
+    // 分章用的正規表示式 (不分大小寫，比對整段文字)，例如 "^(chapter|第)\s*\d+"；未指定時以標題 1 分章
+    pub split_pattern: Option<String>,
+    // 第一章之前的內容 (書名頁、獻詞等) 是否也建立為章節
+    #[serde(default)]
+    pub include_front_matter: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Format {
+    bold: bool,
+    italic: bool,
+    strike: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Run {
+    text: String,
+    format: Format,
+    link: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TextKind {
+    Paragraph,
+    Title,
+    Heading(usize),
+    Quote,
+    ListItem { ordered: bool, depth: usize },
+}
+
+#[derive(Debug)]
+enum Block {
+    Text(TextKind, Vec<Run>),
+    Row { cells: Vec<Vec<Run>>, header: bool },
+}
+
+fn plain_text(runs: &[Run]) -> String {
+    runs.iter().map(|r| r.text.as_str()).collect::<String>().trim().to_string()
+}
+
+// DOCX 與 ODT 共用：收集段落、文字格式與表格
+#[derive(Default)]
+struct BlockBuilder {
+    blocks: Vec<Block>,
+    runs: Vec<Run>,
+    link: Option<String>,
+    table_depth: usize,
+    row: Vec<Vec<Run>>,
+    cell: Vec<Run>,
+    rows_in_table: usize,
+}
+
+impl BlockBuilder {
+    fn text(&mut self, text: &str, format: Format) {
+        if text.is_empty() {
+            return;
+        }
+        match self.runs.last_mut() {
+            Some(last) if last.format == format && last.link == self.link => last.text.push_str(text),
+            _ => self.runs.push(Run { text: text.to_string(), format, link: self.link.clone() }),
+        }
+    }
+
+    fn end_paragraph(&mut self, kind: TextKind) {
+        let runs = std::mem::take(&mut self.runs);
+        if self.table_depth > 0 {
+            // 儲存格內的多個段落以空格相連
+            if !self.cell.is_empty() && !runs.is_empty() {
+                self.cell.push(Run { text: " ".to_string(), format: Format::default(), link: None });
+            }
+            self.cell.extend(runs);
+        } else if !plain_text(&runs).is_empty() {
+            self.blocks.push(Block::Text(kind, runs));
+        }
+    }
+
+    fn start_table(&mut self) {
+        self.table_depth += 1;
+        if self.table_depth == 1 {
+            self.rows_in_table = 0;
+        }
+    }
+
+    fn end_table(&mut self) {
+        self.table_depth = self.table_depth.saturating_sub(1);
+    }
+
+    fn end_cell(&mut self) {
+        if self.table_depth == 1 {
+            let cell = std::mem::take(&mut self.cell);
+            self.row.push(cell);
+        }
+    }
+
+    fn end_row(&mut self) {
+        if self.table_depth == 1 {
+            let cells = std::mem::take(&mut self.row);
+            if cells.iter().any(|c| !plain_text(c).is_empty()) {
+                self.blocks.push(Block::Row { cells, header: self.rows_in_table == 0 });
+                self.rows_in_table += 1;
+            }
+        }
+    }
+}
+
+fn attr(e: &BytesStart, name: &[u8]) -> Option<String> {
+    e.attributes().flatten()
+        .find(|a| a.key.as_ref() == name)
+        .and_then(|a| a.unescape_value().ok())
+        .map(|v| v.to_string())
+}
+
+// w:b、w:i 等屬性沒有 w:val 或 w:val 不是 0/false 時為開啟
+fn toggle(e: &BytesStart) -> bool {
+    !matches!(attr(e, b"w:val").as_deref(), Some("0") | Some("false") | Some("none"))
+}
+
+fn xml_error(part: &str, e: quick_xml::Error) -> AppError {
+    AppError::validation(format!("Cannot read {}: {}", part, e))
+}
+
+fn read_part(archive: &mut ZipArchive<File>, name: &str) -> Result<Option<Vec<u8>>, AppError> {
+    let mut file = match archive.by_name(name) {
+        Ok(file) => file,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let mut data = Vec::new();
+    file.read_to_end(&mut data)?;
+    Ok(Some(data))
+}
+
+// 逐一處理 XML 事件
+fn walk_xml(xml: &[u8], part: &str, mut on_event: impl FnMut(XmlEvent) -> Result<(), AppError>) -> Result<(), AppError> {
+    let mut reader = Reader::from_reader(xml);
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf).map_err(|e| xml_error(part, e))? {
+            XmlEvent::Eof => return Ok(()),
+            event => on_event(event)?,
+        }
+        buf.clear();
+    }
+}
+
+// ---------- DOCX ----------
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DocxStyle {
+    Title,
+    // 目錄標題與目錄項目，匯入時略過
+    Toc,
+    Heading(usize),
+    Quote,
+    Bold,
+    Italic,
+}
+
+#[derive(Default)]
+struct DocxStyleInfo {
+    name: String,
+    based_on: Option<String>,
+    outline_level: Option<usize>,
+    num_id: Option<String>,
+}
+
+struct DocxStyles {
+    kinds: HashMap<String, DocxStyle>,
+    num_ids: HashMap<String, String>,
+}
+
+fn docx_style_kind(name: &str, outline_level: Option<usize>) -> Option<DocxStyle> {
+    let name = name.to_lowercase();
+    if name == "title" {
+        Some(DocxStyle::Title)
+    } else if name == "toc heading" || name.starts_with("toc ") {
+        Some(DocxStyle::Toc)
+    } else if let Some(level) = name.strip_prefix("heading ").and_then(|l| l.trim().parse::<usize>().ok()) {
+        Some(DocxStyle::Heading(level))
+    } else if let Some(level) = outline_level.filter(|l| *l < 9) {
+        Some(DocxStyle::Heading(level + 1))
+    } else if name.contains("quote") {
+        Some(DocxStyle::Quote)
+    } else if name == "strong" {
+        Some(DocxStyle::Bold)
+    } else if name == "emphasis" {
+        Some(DocxStyle::Italic)
+    } else {
+        None
+    }
+}
+
+// 樣式 ID 在非英文版 Word 中不固定，所以依樣式名稱與大綱階層判斷
+fn parse_docx_styles(xml: Option<&[u8]>) -> Result<DocxStyles, AppError> {
+    let mut infos: HashMap<String, DocxStyleInfo> = HashMap::new();
+    if let Some(xml) = xml {
+        let mut current: Option<(String, DocxStyleInfo)> = None;
+        walk_xml(xml, "styles.xml", |event| {
+            match &event {
+                XmlEvent::Start(e) | XmlEvent::Empty(e) => match e.name().as_ref() {
+                    b"w:style" => current = attr(e, b"w:styleId").map(|id| (id, DocxStyleInfo::default())),
+                    b"w:name" => if let Some((_, info)) = current.as_mut() { info.name = attr(e, b"w:val").unwrap_or_default() },
+                    b"w:basedOn" => if let Some((_, info)) = current.as_mut() { info.based_on = attr(e, b"w:val") },
+                    b"w:outlineLvl" => if let Some((_, info)) = current.as_mut() { info.outline_level = attr(e, b"w:val").and_then(|v| v.parse().ok()) },
+                    b"w:numId" => if let Some((_, info)) = current.as_mut() { info.num_id = attr(e, b"w:val") },
+                    _ => {},
+                },
+                XmlEvent::End(e) if e.name().as_ref() == b"w:style" => {
+                    if let Some((id, info)) = current.take() {
+                        infos.insert(id, info);
+                    }
+                },
+                _ => {},
+            }
+            Ok(())
+        })?;
+    }
+
+    let mut kinds = HashMap::new();
+    let mut num_ids = HashMap::new();
+    for id in infos.keys() {
+        // 沿著 basedOn 找到第一個可辨識的樣式
+        let mut style_id = Some(id.clone());
+        for _ in 0..10 {
+            let Some(info) = style_id.as_ref().and_then(|s| infos.get(s)) else { break };
+            if let Some(kind) = docx_style_kind(&info.name, info.outline_level) {
+                kinds.insert(id.clone(), kind);
+                break;
+            }
+            style_id = info.based_on.clone();
+        }
+        if let Some(num_id) = infos[id].num_id.clone() {
+            num_ids.insert(id.clone(), num_id);
+        }
+    }
+    if infos.is_empty() {
+        // 沒有 styles.xml 時使用英文版 Word 的樣式 ID
+        kinds.insert("Title".to_string(), DocxStyle::Title);
+        kinds.insert("Quote".to_string(), DocxStyle::Quote);
+        for level in 1..=6 {
+            kinds.insert(format!("Heading{}", level), DocxStyle::Heading(level));
+        }
+    }
+    Ok(DocxStyles { kinds, num_ids })
+}
+
+// (numId, 階層) 是否為編號清單
+fn parse_docx_numbering(xml: Option<&[u8]>) -> Result<HashMap<(String, usize), bool>, AppError> {
+    let Some(xml) = xml else { return Ok(HashMap::new()) };
+    let mut abstract_levels: HashMap<String, HashMap<usize, bool>> = HashMap::new();
+    let mut num_to_abstract: HashMap<String, String> = HashMap::new();
+    let mut abstract_id: Option<String> = None;
+    let mut num_id: Option<String> = None;
+    let mut level: usize = 0;
+    walk_xml(xml, "numbering.xml", |event| {
+        match &event {
+            XmlEvent::Start(e) | XmlEvent::Empty(e) => match e.name().as_ref() {
+                b"w:abstractNum" => abstract_id = attr(e, b"w:abstractNumId"),
+                b"w:lvl" => level = attr(e, b"w:ilvl").and_then(|v| v.parse().ok()).unwrap_or(0),
+                b"w:numFmt" => if let Some(id) = &abstract_id {
+                    let ordered = !matches!(attr(e, b"w:val").as_deref(), Some("bullet") | Some("none"));
+                    abstract_levels.entry(id.clone()).or_default().insert(level, ordered);
+                },
+                b"w:num" => num_id = attr(e, b"w:numId"),
+                b"w:abstractNumId" => if let (Some(num), Some(target)) = (&num_id, attr(e, b"w:val")) {
+                    num_to_abstract.insert(num.clone(), target);
+                },
+                _ => {},
+            },
+            XmlEvent::End(e) => match e.name().as_ref() {
+                b"w:abstractNum" => abstract_id = None,
+                b"w:num" => num_id = None,
+                _ => {},
+            },
+            _ => {},
+        }
+        Ok(())
+    })?;
+
+    let mut lists = HashMap::new();
+    for (num, abstract_num) in num_to_abstract {
+        for (level, ordered) in abstract_levels.get(&abstract_num).cloned().unwrap_or_default() {
+            lists.insert((num.clone(), level), ordered);
+        }
+    }
+    Ok(lists)
+}
+
+// 超連結的 r:id 對應的網址
+fn parse_docx_relationships(xml: Option<&[u8]>) -> Result<HashMap<String, String>, AppError> {
+    let mut links = HashMap::new();
+    if let Some(xml) = xml {
+        walk_xml(xml, "document.xml.rels", |event| {
+            if let XmlEvent::Start(e) | XmlEvent::Empty(e) = &event {
+                if e.name().as_ref() == b"Relationship" && attr(e, b"TargetMode").as_deref() == Some("External") {
+                    if let (Some(id), Some(target)) = (attr(e, b"Id"), attr(e, b"Target")) {
+                        links.insert(id, target);
+                    }
+                }
+            }
+            Ok(())
+        })?;
+    }
+    Ok(links)
+}
+
+#[derive(Default)]
+struct DocxParagraph {
+    style: Option<String>,
+    num_id: Option<String>,
+    level: usize,
+    outline_level: Option<usize>,
+}
+
+fn parse_docx(archive: &mut ZipArchive<File>) -> Result<Vec<Block>, AppError> {
+    let document = read_part(archive, "word/document.xml")?
+        .ok_or_else(|| AppError::validation("Not a Word document: word/document.xml is missing"))?;
+    let styles = parse_docx_styles(read_part(archive, "word/styles.xml")?.as_deref())?;
+    let numbering = parse_docx_numbering(read_part(archive, "word/numbering.xml")?.as_deref())?;
+    let links = parse_docx_relationships(read_part(archive, "word/_rels/document.xml.rels")?.as_deref())?;
+
+    let mut builder = BlockBuilder::default();
+    let mut paragraph = DocxParagraph::default();
+    let mut format = Format::default();
+    let mut in_paragraph_props = false;
+    let mut in_run_props = false;
+    let mut in_text = false;
+    // 文字方塊、替代內容等不屬於正文
+    let mut skip_depth = 0usize;
+
+    walk_xml(&document, "document.xml", |event| {
+        match &event {
+            XmlEvent::Start(_) if skip_depth > 0 => skip_depth += 1,
+            XmlEvent::End(_) if skip_depth > 0 => skip_depth -= 1,
+            XmlEvent::Start(e) | XmlEvent::Empty(e) if skip_depth == 0 => {
+                let empty = matches!(event, XmlEvent::Empty(_));
+                match e.name().as_ref() {
+                    b"w:txbxContent" | b"mc:Fallback" | b"w:footnoteReference" | b"w:commentReference" if !empty => skip_depth = 1,
+                    b"w:p" => {
+                        paragraph = DocxParagraph::default();
+                        if empty {
+                            builder.end_paragraph(TextKind::Paragraph);
+                        }
+                    },
+                    b"w:pPr" if !empty => in_paragraph_props = true,
+                    b"w:pStyle" if in_paragraph_props => paragraph.style = attr(e, b"w:val"),
+                    b"w:numId" if in_paragraph_props => paragraph.num_id = attr(e, b"w:val"),
+                    b"w:ilvl" if in_paragraph_props => paragraph.level = attr(e, b"w:val").and_then(|v| v.parse().ok()).unwrap_or(0),
+                    b"w:outlineLvl" if in_paragraph_props => paragraph.outline_level = attr(e, b"w:val").and_then(|v| v.parse().ok()),
+                    b"w:r" => format = Format::default(),
+                    b"w:rPr" if !in_paragraph_props && !empty => in_run_props = true,
+                    b"w:b" if in_run_props => format.bold = toggle(e),
+                    b"w:i" if in_run_props => format.italic = toggle(e),
+                    b"w:strike" | b"w:dstrike" if in_run_props => format.strike = toggle(e),
+                    b"w:rStyle" if in_run_props => match attr(e, b"w:val").and_then(|s| styles.kinds.get(&s).copied()) {
+                        Some(DocxStyle::Bold) => format.bold = true,
+                        Some(DocxStyle::Italic) => format.italic = true,
+                        _ => {},
+                    },
+                    b"w:t" if !empty => in_text = true,
+                    b"w:tab" if !in_paragraph_props => builder.text(" ", format),
+                    b"w:br" | b"w:cr" if attr(e, b"w:type").as_deref() != Some("page") => builder.text("\n", format),
+                    b"w:noBreakHyphen" => builder.text("-", format),
+                    b"w:hyperlink" if !empty => builder.link = attr(e, b"r:id").and_then(|id| links.get(&id).cloned()),
+                    b"w:tbl" if !empty => builder.start_table(),
+                    _ => {},
+                }
+            },
+            XmlEvent::Text(t) if in_text && skip_depth == 0 => {
+                let text = t.unescape().map_err(|e| xml_error("document.xml", e))?;
+                builder.text(&text, format);
+            },
+            XmlEvent::End(e) => match e.name().as_ref() {
+                b"w:pPr" => in_paragraph_props = false,
+                b"w:rPr" => in_run_props = false,
+                b"w:t" => in_text = false,
+                b"w:hyperlink" => builder.link = None,
+                b"w:tc" => builder.end_cell(),
+                b"w:tr" => builder.end_row(),
+                b"w:tbl" => builder.end_table(),
+                b"w:p" => {
+                    let style = paragraph.style.as_ref().and_then(|s| styles.kinds.get(s).copied());
+                    let num_id = paragraph.num_id.clone()
+                        .or_else(|| paragraph.style.as_ref().and_then(|s| styles.num_ids.get(s).cloned()))
+                        .filter(|id| id != "0");
+                    if style == Some(DocxStyle::Toc) {
+                        builder.runs.clear();
+                        return Ok(());
+                    }
+                    let kind = match (style, num_id, paragraph.outline_level.filter(|l| *l < 9)) {
+                        (Some(DocxStyle::Title), _, _) => TextKind::Title,
+                        (Some(DocxStyle::Heading(level)), _, _) => TextKind::Heading(level),
+                        (_, _, Some(level)) => TextKind::Heading(level + 1),
+                        (_, Some(num_id), _) => TextKind::ListItem {
+                            ordered: numbering.get(&(num_id, paragraph.level)).copied().unwrap_or(false),
+                            depth: paragraph.level,
+                        },
+                        (Some(DocxStyle::Quote), _, _) => TextKind::Quote,
+                        _ => TextKind::Paragraph,
+                    };
+                    builder.end_paragraph(kind);
+                },
+                _ => {},
+            },
+            _ => {},
+        }
+        Ok(())
+    })?;
+    Ok(builder.blocks)
+}
+
+// ---------- ODT ----------
+
+#[derive(Default)]
+struct OdtStyles {
+    // 文字樣式 (含自動樣式) 的格式
+    text: HashMap<String, Format>,
+    // 段落樣式的上層樣式，用來辨識 Title 與 Quotations
+    parents: HashMap<String, String>,
+    // (清單樣式, 階層) 是否為編號清單
+    lists: HashMap<(String, usize), bool>,
+}
+
+impl OdtStyles {
+    fn paragraph_kind(&self, style: &str) -> TextKind {
+        let mut name = style.to_string();
+        for _ in 0..10 {
+            match name.as_str() {
+                "Title" => return TextKind::Title,
+                "Quotations" | "Quote" => return TextKind::Quote,
+                _ => {},
+            }
+            match self.parents.get(&name) {
+                Some(parent) => name = parent.clone(),
+                None => break,
+            }
+        }
+        TextKind::Paragraph
+    }
+}
+
+fn parse_odt_styles(xml: &[u8], part: &str, styles: &mut OdtStyles) -> Result<(), AppError> {
+    let mut style: Option<String> = None;
+    let mut list_style: Option<String> = None;
+    walk_xml(xml, part, |event| {
+        match &event {
+            XmlEvent::Start(e) | XmlEvent::Empty(e) => match e.name().as_ref() {
+                b"style:style" => {
+                    style = attr(e, b"style:name");
+                    if let (Some(name), Some(parent)) = (&style, attr(e, b"style:parent-style-name")) {
+                        styles.parents.insert(name.clone(), parent);
+                    }
+                },
+                b"style:text-properties" => if let Some(name) = &style {
+                    let format = Format {
+                        bold: attr(e, b"fo:font-weight").as_deref() == Some("bold"),
+                        italic: attr(e, b"fo:font-style").as_deref() == Some("italic"),
+                        strike: attr(e, b"style:text-line-through-style").map(|s| s != "none").unwrap_or(false),
+                    };
+                    styles.text.insert(name.clone(), format);
+                },
+                b"text:list-style" => list_style = attr(e, b"style:name"),
+                b"text:list-level-style-number" | b"text:list-level-style-bullet" => if let Some(name) = &list_style {
+                    let level = attr(e, b"text:level").and_then(|l| l.parse::<usize>().ok()).unwrap_or(1);
+                    let ordered = e.name().as_ref() == b"text:list-level-style-number";
+                    styles.lists.insert((name.clone(), level.saturating_sub(1)), ordered);
+                },
+                _ => {},
+            },
+            XmlEvent::End(e) => match e.name().as_ref() {
+                b"style:style" => style = None,
+                b"text:list-style" => list_style = None,
+                _ => {},
+            },
+            _ => {},
+        }
+        Ok(())
+    })
+}
+
+fn parse_odt(archive: &mut ZipArchive<File>) -> Result<Vec<Block>, AppError> {
+    let content = read_part(archive, "content.xml")?
+        .ok_or_else(|| AppError::validation("Not an OpenDocument text: content.xml is missing"))?;
+    let mut styles = OdtStyles::default();
+    if let Some(xml) = read_part(archive, "styles.xml")? {
+        parse_odt_styles(&xml, "styles.xml", &mut styles)?;
+    }
+    parse_odt_styles(&content, "content.xml", &mut styles)?;
+
+    let mut builder = BlockBuilder::default();
+    let mut in_body = false;
+    let mut kinds: Vec<TextKind> = Vec::new();
+    // 巢狀清單的樣式 (內層未指定時沿用外層)
+    let mut lists: Vec<Option<String>> = Vec::new();
+    let mut formats: Vec<Format> = vec![Format::default()];
+    // 註腳、批註、目錄與追蹤修訂不屬於正文
+    let mut skip_depth = 0usize;
+
+    walk_xml(&content, "content.xml", |event| {
+        match &event {
+            XmlEvent::Start(_) if skip_depth > 0 => skip_depth += 1,
+            XmlEvent::End(_) if skip_depth > 0 => skip_depth -= 1,
+            XmlEvent::Start(e) | XmlEvent::Empty(e) if skip_depth == 0 => {
+                let empty = matches!(event, XmlEvent::Empty(_));
+                let format = *formats.last().unwrap_or(&Format::default());
+                match e.name().as_ref() {
+                    b"office:text" => in_body = !empty,
+                    _ if !in_body => {},
+                    b"text:note" | b"office:annotation" | b"text:tracked-changes" | b"text:table-of-content"
+                        | b"text:alphabetical-index" | b"draw:frame" | b"text:sequence-decls" if !empty => skip_depth = 1,
+                    b"text:list" if !empty => {
+                        let style = attr(e, b"text:style-name").or_else(|| lists.last().cloned().flatten());
+                        lists.push(style);
+                    },
+                    b"text:h" | b"text:p" => {
+                        let kind = if e.name().as_ref() == b"text:h" {
+                            TextKind::Heading(attr(e, b"text:outline-level").and_then(|l| l.parse().ok()).unwrap_or(1))
+                        } else if !lists.is_empty() {
+                            let depth = lists.len() - 1;
+                            let ordered = lists.last().cloned().flatten()
+                                .and_then(|s| styles.lists.get(&(s, depth)).copied())
+                                .unwrap_or(false);
+                            TextKind::ListItem { ordered, depth }
+                        } else {
+                            attr(e, b"text:style-name").map(|s| styles.paragraph_kind(&s)).unwrap_or(TextKind::Paragraph)
+                        };
+                        if empty {
+                            builder.end_paragraph(kind);
+                        } else {
+                            kinds.push(kind);
+                        }
+                    },
+                    b"text:span" if !empty => {
+                        let span = attr(e, b"text:style-name").and_then(|s| styles.text.get(&s).copied()).unwrap_or_default();
+                        formats.push(Format {
+                            bold: format.bold || span.bold,
+                            italic: format.italic || span.italic,
+                            strike: format.strike || span.strike,
+                        });
+                    },
+                    b"text:a" if !empty => builder.link = attr(e, b"xlink:href"),
+                    b"text:s" => {
+                        let count = attr(e, b"text:c").and_then(|c| c.parse::<usize>().ok()).unwrap_or(1);
+                        builder.text(&" ".repeat(count.min(100)), format);
+                    },
+                    b"text:tab" => builder.text(" ", format),
+                    b"text:line-break" => builder.text("\n", format),
+                    b"table:table" if !empty => builder.start_table(),
+                    _ => {},
+                }
+            },
+            XmlEvent::Text(t) if in_body && skip_depth == 0 && !kinds.is_empty() => {
+                let text = t.unescape().map_err(|e| xml_error("content.xml", e))?;
+                // ODF 的連續空白只算一個 (多個空白以 text:s 表示)
+                let mut collapsed = String::with_capacity(text.len());
+                for c in text.chars() {
+                    let c = if matches!(c, '\n' | '\r' | '\t') { ' ' } else { c };
+                    if !(c == ' ' && collapsed.ends_with(' ')) {
+                        collapsed.push(c);
+                    }
+                }
+                let text = collapsed;
+                builder.text(&text, *formats.last().unwrap_or(&Format::default()));
+            },
+            XmlEvent::End(e) if in_body => match e.name().as_ref() {
+                b"office:text" => in_body = false,
+                b"text:list" => { lists.pop(); },
+                b"text:h" | b"text:p" => {
+                    let kind = kinds.pop().unwrap_or(TextKind::Paragraph);
+                    builder.end_paragraph(kind);
+                },
+                b"text:span" if formats.len() > 1 => { formats.pop(); },
+                b"text:a" => builder.link = None,
+                b"table:table-cell" => builder.end_cell(),
+                b"table:table-row" => builder.end_row(),
+                b"table:table" => builder.end_table(),
+                _ => {},
+            },
+            _ => {},
+        }
+        Ok(())
+    })?;
+    Ok(builder.blocks)
+}
+
+// ---------- Markdown ----------
+
+fn escape_markdown(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '*' | '_' | '`' | '[' | ']' | '<' | '|') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+// 行首的 #、>、-、+ 與 "1." 會被當成 Markdown 語法
+fn escape_line_start(line: &str) -> String {
+    let digits = line.chars().take_while(|c| c.is_ascii_digit()).count();
+    let numbered = digits > 0 && matches!(line[digits..].chars().next(), Some('.') | Some(')'));
+    if numbered {
+        format!("{}\\{}", &line[..digits], &line[digits..])
+    } else if line.starts_with(['#', '>', '-', '+', '=']) {
+        format!("\\{}", line)
+    } else {
+        line.to_string()
+    }
+}
+
+fn render_span(text: &str, format: Format, link: &Option<String>) -> String {
+    let escaped = escape_markdown(text);
+    let inner = escaped.trim();
+    if inner.is_empty() {
+        return escaped;
+    }
+    // 格式符號內側不能有空白
+    let lead = &escaped[..escaped.len() - escaped.trim_start().len()];
+    let trail = &escaped[escaped.trim_end().len()..];
+    let mut inner = inner.to_string();
+    if format.strike {
+        inner = format!("~~{}~~", inner);
+    }
+    if format.italic {
+        inner = format!("*{}*", inner);
+    }
+    if format.bold {
+        inner = format!("**{}**", inner);
+    }
+    if let Some(url) = link {
+        inner = format!("[{}]({})", inner, url.replace(' ', "%20").replace(')', "%29"));
+    }
+    format!("{}{}{}", lead, inner, trail)
+}
+
+fn render_runs(runs: &[Run]) -> String {
+    let text: String = runs.iter().map(|run| render_span(&run.text, run.format, &run.link)).collect();
+    text.trim()
+        .lines()
+        .map(|line| escape_line_start(line.trim()))
+        .collect::<Vec<_>>()
+        .join("  \n")
+}
+
+// 只有 * # ~ 等符號的短段落視為場景分隔
+fn is_scene_break(runs: &[Run]) -> bool {
+    let text = plain_text(runs);
+    !text.is_empty() && text.chars().count() <= 10 && text.chars().all(|c| matches!(c, '*' | '#' | '~' | '-' | '•' | '·' | '⁂') || c.is_whitespace())
+}
+
+fn render_blocks(blocks: &[&Block]) -> String {
+    let mut out = String::new();
+    let mut previous: Option<&Block> = None;
+    // 最外層清單是否為編號清單
+    let mut list_ordered: Option<bool> = None;
+    for block in blocks {
+        // 同一個清單或表格的項目之間不空行；最外層的清單類型改變時另起一個清單
+        let tight = match (previous, block) {
+            (Some(Block::Text(TextKind::ListItem { .. }, _)), Block::Text(TextKind::ListItem { ordered, depth }, _)) => {
+                *depth > 0 || list_ordered == Some(*ordered)
+            },
+            (Some(Block::Row { .. }), Block::Row { header: false, .. }) => true,
+            _ => false,
+        };
+        if let Block::Text(TextKind::ListItem { ordered, depth: 0 }, _) = block {
+            list_ordered = Some(*ordered);
+        }
+        if previous.is_some() {
+            out.push_str(if tight { "\n" } else { "\n\n" });
+        }
+        match block {
+            Block::Text(_, runs) if is_scene_break(runs) => out.push_str("* * *"),
+            Block::Text(kind, runs) => {
+                let text = render_runs(runs);
+                match kind {
+                    TextKind::Paragraph => out.push_str(&text),
+                    TextKind::Title => out.push_str(&format!("# {}", text)),
+                    TextKind::Heading(level) => out.push_str(&format!("{} {}", "#".repeat((*level).clamp(1, 6)), text)),
+                    TextKind::Quote => out.push_str(&format!("> {}", text.replace('\n', "\n> "))),
+                    TextKind::ListItem { ordered, depth } => {
+                        let marker = if *ordered { "1." } else { "-" };
+                        let indent = "    ".repeat(*depth);
+                        out.push_str(&format!("{}{} {}", indent, marker, text.replace('\n', &format!("\n{}  ", indent))));
+                    },
+                }
+            },
+            Block::Row { cells, header } => {
+                let cells: Vec<String> = cells.iter().map(|c| render_runs(c).replace("  \n", " ")).collect();
+                out.push_str(&format!("| {} |", cells.join(" | ")));
+                if *header {
+                    out.push_str(&format!("\n|{}", "---|".repeat(cells.len())));
+                }
+            },
+        }
+        previous = Some(block);
+    }
+    out
+}
+
+// ---------- 分章 ----------
+
+struct ImportedChapter {
+    title: String,
+    content: String,
+}
+
+fn is_chapter_start(block: &Block, pattern: &Option<Regex>) -> bool {
+    match (block, pattern) {
+        (Block::Text(TextKind::ListItem { .. }, _), _) => false,
+        (Block::Text(_, runs), Some(pattern)) => pattern.is_match(&plain_text(runs)),
+        (Block::Text(TextKind::Heading(1), _), None) => true,
+        _ => false,
+    }
+}
+
+// 回傳 (章節, 第一章之前的內容)
+fn split_chapters(blocks: &[Block], pattern: &Option<Regex>) -> (Vec<ImportedChapter>, Option<ImportedChapter>) {
+    let mut chapters = Vec::new();
+    let mut current: Option<(String, Vec<&Block>)> = None;
+    let mut preamble: Vec<&Block> = Vec::new();
+
+    for block in blocks {
+        if is_chapter_start(block, pattern) {
+            if let Some((title, body)) = current.take() {
+                chapters.push(ImportedChapter { title, content: render_blocks(&body) });
+            }
+            let title = match block {
+                Block::Text(_, runs) => plain_text(runs).replace('\n', " "),
+                Block::Row { .. } => String::new(),
+            };
+            current = Some((title, Vec::new()));
+        } else {
+            match current.as_mut() {
+                Some((_, body)) => body.push(block),
+                None => preamble.push(block),
+            }
+        }
+    }
+    if let Some((title, body)) = current {
+        chapters.push(ImportedChapter { title, content: render_blocks(&body) });
+    }
+
+    let front_matter = if preamble.is_empty() {
+        None
+    } else {
+        // 書名頁的標題作為這一章的名稱
+        let title = preamble.iter().find_map(|block| match block {
+            Block::Text(TextKind::Title, runs) => Some(plain_text(runs)),
+            _ => None,
+        });
+        let body: Vec<&Block> = preamble.into_iter().filter(|b| !matches!(b, Block::Text(TextKind::Title, _))).collect();
+        Some(ImportedChapter {
+            title: title.unwrap_or_else(|| FRONT_MATTER_TITLE.to_string()),
+            content: render_blocks(&body),
+        })
+    };
+    (chapters, front_matter)
+}
+
+fn read_document(path: &Path) -> Result<Vec<Block>, AppError> {
+    let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+    if !matches!(extension.as_str(), "docx" | "odt") {
+        return Err(AppError::validation("Only .docx and .odt files can be imported"));
+    }
+    let file = File::open(path)
+        .map_err(|e| AppError::validation(format!("Cannot open {}: {}", path.display(), e)))?;
+    let mut archive = ZipArchive::new(file)
+        .map_err(|e| AppError::validation(format!("{} is not a valid {} file: {}", path.display(), extension.to_uppercase(), e)))?;
+    if extension == "docx" { parse_docx(&mut archive) } else { parse_odt(&mut archive) }
+}
+
+fn insert_chapters(tx: &Transaction, project_id: i64, chapters: &[ImportedChapter], report: &mut ImportReport) -> Result<Vec<i64>, AppError> {
+    let mut chapter_number: i32 = tx.query_row(
+        "SELECT COALESCE(MAX(chapter_number), 0) FROM chapters WHERE project_id = ?1",
+        [project_id],
+        |row| row.get(0),
+    )?;
+    let now = Local::now().to_rfc3339();
+    let mut created = Vec::new();
+    for chapter in chapters {
+        if let Some(existing) = import::find_existing(tx, "chapters", project_id, &chapter.title)? {
+            let (conflict, reason) = import::classify_duplicate(&existing, &chapter.content, &None);
+            let entry = item("chapter", None, Some(existing.0), &chapter.title, Some(reason));
+            if conflict { report.conflicts.push(entry) } else { report.skipped.push(entry) }
+            continue;
+        }
+
+        chapter_number += 1;
+        tx.execute(
+            "INSERT INTO chapters (project_id, title, content, chapter_number, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?5)",
+            params![project_id, chapter.title, chapter.content, chapter_number, now],
+        )?;
+        let id = tx.last_insert_rowid();
+        created.push(id);
+        report.created.push(item("chapter", None, Some(id), &chapter.title, None));
+    }
+    Ok(created)
+}
+
+// 匯入 Word (.docx) 或 OpenDocument (.odt) 稿件，依標題 1 或自訂樣式分章
+#[tauri::command]
+pub async fn import_document_chapters(path: String, project_id: i64, options: Option<DocumentImportOptions>, app_handle: AppHandle, state: tauri::State<'_, SqliteState>) -> Result<ImportReport, AppError> {
+    let options = options.unwrap_or_default();
+    let pattern = match options.split_pattern.as_deref().map(str::trim) {
+        Some(pattern) if !pattern.is_empty() => Some(
+            RegexBuilder::new(pattern).case_insensitive(true).build()
+                .map_err(|e| AppError::validation(format!("Invalid chapter pattern: {}", e)))?
+        ),
+        _ => None,
+    };
+
+    println!("Importing manuscript {} into project {}", path, project_id);
+    // Unzipping and parsing the XML is CPU and disk work; keep it off the async runtime
+    let (chapters, mut report) = tauri::async_runtime::spawn_blocking(move || {
+        let blocks = read_document(Path::new(&path))?;
+        let (mut chapters, front_matter) = split_chapters(&blocks, &pattern);
+
+        let mut report = ImportReport::new(ImportMode::Merge);
+        if chapters.is_empty() {
+            // 找不到分章點時整份文件成為一章
+            let mut whole = front_matter.ok_or_else(|| AppError::validation("The document has no text to import"))?;
+            if whole.title == FRONT_MATTER_TITLE {
+                whole.title = Path::new(&path).file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+            }
+            println!("No chapter breaks found in {}; importing it as one chapter", path);
+            chapters.push(whole);
+        } else if let Some(front_matter) = front_matter {
+            if options.include_front_matter {
+                chapters.insert(0, front_matter);
+            } else {
+                report.skipped.push(item("chapter", None, None, &front_matter.title,
+                    Some("content before the first chapter was not imported".to_string())));
+            }
+        }
+        println!("Found {} chapters in {}", chapters.len(), path);
+        Ok::<_, AppError>((chapters, report))
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Document import task failed: {}", e)))??;
+
+    let report = state.run(move |conn| {
+        let project = db::load_project(conn, project_id)?;
+        if project.type_ != "book" {
+            return Err(AppError::validation("Manuscripts can only be imported into book projects"));
+        }
+
+        let tx = conn.transaction()?;
+        let created = insert_chapters(&tx, project_id, &chapters, &mut report)?;
+        for id in &created {
+            revisions::record_revision_logged(&tx, rag::SOURCE_CHAPTER, *id, IMPORT_AUTHOR);
+            db::reindex_source(&tx, rag::SOURCE_CHAPTER, *id);
+        }
+        tx.commit()?;
+
+        println!(
+            "Manuscript import finished: {} created, {} skipped, {} conflicts",
+            report.created.len(), report.skipped.len(), report.conflicts.len()
+        );
+        Ok::<_, AppError>(report)
+    }).await?;
+
+    embeddings::schedule_embedding(&app_handle);
+    Ok(report)
+}
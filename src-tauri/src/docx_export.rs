@@ -0,0 +1,690 @@
+use chrono::Utc;
+use pulldown_cmark::{Event, HeadingLevel, Options, Parser, Tag};
+use serde::Deserialize;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::SqliteState;
+use crate::db::{self, Chapter, Project};
+use crate::epub_export::{check_well_formed, xml_escape};
+use crate::error::AppError;
+use crate::export_common::{chapter_title, default_export_path};
+use crate::markdown_export::{split_keywords, strip_tags, strip_title_heading};
+
+// Word (DOCX) 匯出：單篇文章、單一章節或整本書
+const W_NS: &str = "xmlns:w=\"http://schemas.openxmlformats.org/wordprocessingml/2006/main\" \
+                    xmlns:r=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships\"";
+const REL_HYPERLINK: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships/hyperlink";
+// Relationship ids below this are fixed parts (styles, numbering, settings)
+const FIRST_LINK_REL: usize = 10;
+// 項目符號清單共用同一個編號定義
+const BULLET_NUM_ID: usize = 1;
+const LIST_LEVELS: usize = 9;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct DocxExportOptions {
+    pub author: Option<String>,
+    // 未指定時寫到 exports 目錄
+    pub output_path: Option<String>,
+}
+
+// 文件屬性 (docProps/core.xml)
+struct DocProperties {
+    title: String,
+    author: Option<String>,
+    subject: Option<String>,
+    description: Option<String>,
+    keywords: Vec<String>,
+}
+
+#[derive(Default)]
+struct DocxBuilder {
+    body: String,
+    // External hyperlink targets, in relationship id order
+    links: Vec<String>,
+    // (numId, level, start) for each numbered list; every list restarts its own count
+    ordered_lists: Vec<(usize, usize, u64)>,
+    bookmarks: usize,
+}
+
+// 行內格式狀態
+#[derive(Default)]
+struct Inline {
+    bold: usize,
+    italic: usize,
+    strike: usize,
+    link: usize,
+}
+
+fn heading_level(level: HeadingLevel) -> usize {
+    match level {
+        HeadingLevel::H1 => 1,
+        HeadingLevel::H2 => 2,
+        HeadingLevel::H3 => 3,
+        HeadingLevel::H4 => 4,
+        HeadingLevel::H5 => 5,
+        HeadingLevel::H6 => 6,
+    }
+}
+
+fn paragraph_style(style: &str) -> String {
+    format!("<w:pPr><w:pStyle w:val=\"{}\"/></w:pPr>", style)
+}
+
+fn text_run(text: &str, properties: &str) -> String {
+    let properties = if properties.is_empty() { String::new() } else { format!("<w:rPr>{}</w:rPr>", properties) };
+    format!("<w:r>{}<w:t xml:space=\"preserve\">{}</w:t></w:r>", properties, xml_escape(text))
+}
+
+impl DocxBuilder {
+    fn paragraph(&mut self, properties: &str, runs: &str) {
+        self.body.push_str(&format!("<w:p>{}{}</w:p>", properties, runs));
+    }
+
+    fn page_break(&mut self) {
+        self.body.push_str("<w:p><w:r><w:br w:type=\"page\"/></w:r></w:p>");
+    }
+
+    fn link_rel(&mut self, target: &str) -> String {
+        self.links.push(target.to_string());
+        format!("rId{}", FIRST_LINK_REL + self.links.len() - 1)
+    }
+
+    fn ordered_list(&mut self, level: usize, start: u64) -> usize {
+        // numId 1 is the bullet list, so numbered lists start at 2
+        let num_id = self.ordered_lists.len() + 2;
+        self.ordered_lists.push((num_id, level, start));
+        num_id
+    }
+
+    // 章節標題 (Heading 1)，加上目錄用的書籤
+    fn chapter_heading(&mut self, title: &str, page_break: bool) {
+        self.bookmarks += 1;
+        let name = format!("_TocChapter{}", self.bookmarks);
+        let properties = if page_break {
+            "<w:pPr><w:pStyle w:val=\"Heading1\"/><w:pageBreakBefore/></w:pPr>"
+        } else {
+            "<w:pPr><w:pStyle w:val=\"Heading1\"/></w:pPr>"
+        };
+        let runs = format!(
+            "<w:bookmarkStart w:id=\"{id}\" w:name=\"{name}\"/>{run}<w:bookmarkEnd w:id=\"{id}\"/>",
+            id = self.bookmarks,
+            name = name,
+            run = text_run(title, ""),
+        );
+        self.paragraph(properties, &runs);
+    }
+
+    // 目錄欄位；先填入章節清單，Word 開啟時會更新頁碼
+    fn table_of_contents(&mut self, entries: &[(String, String)]) {
+        self.paragraph(&paragraph_style("TOCHeading"), &text_run("Contents", ""));
+        let field_start = "<w:r><w:fldChar w:fldCharType=\"begin\" w:dirty=\"true\"/></w:r>\
+                           <w:r><w:instrText xml:space=\"preserve\"> TOC \\o \"1-2\" \\h \\z \\u </w:instrText></w:r>\
+                           <w:r><w:fldChar w:fldCharType=\"separate\"/></w:r>";
+        for (index, (title, anchor)) in entries.iter().enumerate() {
+            let link = format!("<w:hyperlink w:anchor=\"{}\" w:history=\"1\">{}</w:hyperlink>", anchor, text_run(title, ""));
+            let runs = if index == 0 { format!("{}{}", field_start, link) } else { link };
+            self.paragraph(&paragraph_style("TOC1"), &runs);
+        }
+        if entries.is_empty() {
+            self.paragraph(&paragraph_style("TOC1"), field_start);
+        }
+        self.paragraph("", "<w:r><w:fldChar w:fldCharType=\"end\"/></w:r>");
+    }
+
+    // Markdown 轉成 WordprocessingML 段落
+    fn push_markdown(&mut self, markdown: &str, heading_offset: usize) {
+        let mut options = Options::empty();
+        options.insert(Options::ENABLE_TABLES);
+        options.insert(Options::ENABLE_STRIKETHROUGH);
+        options.insert(Options::ENABLE_TASKLISTS);
+
+        let mut inline = Inline::default();
+        // (properties, runs) of the paragraph being built
+        let mut current: Option<(String, String)> = None;
+        // numId of each open list, innermost last
+        let mut lists: Vec<usize> = Vec::new();
+        let mut item_fresh = false;
+        let mut quote_depth = 0usize;
+        let mut code: Option<String> = None;
+        let mut table_head = false;
+
+        macro_rules! close {
+            () => {
+                if let Some((properties, runs)) = current.take() {
+                    self.paragraph(&properties, &runs);
+                }
+            };
+        }
+        // Text outside a paragraph (tight list items, table cells) still needs one
+        macro_rules! open {
+            () => {
+                if current.is_none() {
+                    let properties = if !lists.is_empty() {
+                        format!("<w:pPr><w:pStyle w:val=\"ListParagraph\"/><w:ind w:left=\"{}\"/></w:pPr>", 720 * lists.len())
+                    } else if quote_depth > 0 {
+                        paragraph_style("Quote")
+                    } else {
+                        String::new()
+                    };
+                    current = Some((properties, String::new()));
+                }
+            };
+        }
+
+        for event in Parser::new_ext(markdown, options) {
+            match event {
+                Event::Start(Tag::Paragraph) => {
+                    // The first paragraph of a loose list item goes into the numbered paragraph
+                    if !item_fresh {
+                        close!();
+                    }
+                    item_fresh = false;
+                    open!();
+                },
+                Event::End(Tag::Paragraph) => close!(),
+                Event::Start(Tag::Heading(level, _, _)) => {
+                    close!();
+                    let level = (heading_level(level) + heading_offset).min(6);
+                    current = Some((paragraph_style(&format!("Heading{}", level)), String::new()));
+                },
+                Event::End(Tag::Heading(..)) => close!(),
+                Event::Start(Tag::BlockQuote) => {
+                    close!();
+                    quote_depth += 1;
+                },
+                Event::End(Tag::BlockQuote) => {
+                    close!();
+                    quote_depth = quote_depth.saturating_sub(1);
+                },
+                Event::Start(Tag::CodeBlock(_)) => {
+                    close!();
+                    code = Some(String::new());
+                },
+                Event::End(Tag::CodeBlock(_)) => {
+                    let text = code.take().unwrap_or_default();
+                    let runs: Vec<String> = text.trim_end_matches('\n').split('\n')
+                        .map(|line| text_run(line, ""))
+                        .collect();
+                    self.paragraph(&paragraph_style("Code"), &runs.join("<w:r><w:br/></w:r>"));
+                },
+                Event::Start(Tag::List(start)) => {
+                    close!();
+                    item_fresh = false;
+                    let num_id = match start {
+                        Some(start) => self.ordered_list(lists.len().min(LIST_LEVELS - 1), start),
+                        None => BULLET_NUM_ID,
+                    };
+                    lists.push(num_id);
+                },
+                Event::End(Tag::List(_)) => {
+                    close!();
+                    lists.pop();
+                },
+                Event::Start(Tag::Item) => {
+                    close!();
+                    let level = lists.len().saturating_sub(1).min(LIST_LEVELS - 1);
+                    let num_id = lists.last().copied().unwrap_or(BULLET_NUM_ID);
+                    current = Some((
+                        format!(
+                            "<w:pPr><w:pStyle w:val=\"ListParagraph\"/><w:numPr><w:ilvl w:val=\"{}\"/><w:numId w:val=\"{}\"/></w:numPr></w:pPr>",
+                            level, num_id
+                        ),
+                        String::new(),
+                    ));
+                    item_fresh = true;
+                },
+                Event::End(Tag::Item) => {
+                    close!();
+                    item_fresh = false;
+                },
+                Event::Start(Tag::Table(alignments)) => {
+                    close!();
+                    let columns = alignments.len().max(1);
+                    let grid: String = (0..columns).map(|_| "<w:gridCol/>").collect();
+                    self.body.push_str(&format!(
+                        "<w:tbl><w:tblPr><w:tblStyle w:val=\"TableGrid\"/><w:tblW w:w=\"0\" w:type=\"auto\"/></w:tblPr><w:tblGrid>{}</w:tblGrid>",
+                        grid
+                    ));
+                },
+                Event::End(Tag::Table(_)) => self.body.push_str("</w:tbl>"),
+                Event::Start(Tag::TableHead) => {
+                    table_head = true;
+                    self.body.push_str("<w:tr><w:trPr><w:tblHeader/></w:trPr>");
+                },
+                Event::End(Tag::TableHead) => {
+                    table_head = false;
+                    self.body.push_str("</w:tr>");
+                },
+                Event::Start(Tag::TableRow) => self.body.push_str("<w:tr>"),
+                Event::End(Tag::TableRow) => self.body.push_str("</w:tr>"),
+                Event::Start(Tag::TableCell) => {
+                    self.body.push_str("<w:tc><w:tcPr><w:tcW w:w=\"0\" w:type=\"auto\"/></w:tcPr>");
+                    current = Some((String::new(), String::new()));
+                    if table_head {
+                        inline.bold += 1;
+                    }
+                },
+                Event::End(Tag::TableCell) => {
+                    if table_head {
+                        inline.bold = inline.bold.saturating_sub(1);
+                    }
+                    // A cell must contain a paragraph even when it is empty
+                    let (properties, runs) = current.take().unwrap_or_default();
+                    self.paragraph(&properties, &runs);
+                    self.body.push_str("</w:tc>");
+                },
+                Event::Start(Tag::Emphasis) => inline.italic += 1,
+                Event::End(Tag::Emphasis) => inline.italic = inline.italic.saturating_sub(1),
+                Event::Start(Tag::Strong) => inline.bold += 1,
+                Event::End(Tag::Strong) => inline.bold = inline.bold.saturating_sub(1),
+                Event::Start(Tag::Strikethrough) => inline.strike += 1,
+                Event::End(Tag::Strikethrough) => inline.strike = inline.strike.saturating_sub(1),
+                // Images are not embedded; they become links to the original file
+                Event::Start(Tag::Link(_, url, _)) | Event::Start(Tag::Image(_, url, _)) => {
+                    open!();
+                    let tag = if let Some(anchor) = url.strip_prefix('#') {
+                        format!("<w:hyperlink w:anchor=\"{}\">", xml_escape(anchor))
+                    } else {
+                        format!("<w:hyperlink r:id=\"{}\" w:history=\"1\">", self.link_rel(&url))
+                    };
+                    if let Some((_, runs)) = current.as_mut() {
+                        runs.push_str(&tag);
+                    }
+                    inline.link += 1;
+                },
+                Event::End(Tag::Link(..)) | Event::End(Tag::Image(..)) => {
+                    if let Some((_, runs)) = current.as_mut() {
+                        runs.push_str("</w:hyperlink>");
+                    }
+                    inline.link = inline.link.saturating_sub(1);
+                },
+                Event::Start(_) | Event::End(_) => {},
+                Event::Text(text) => {
+                    if let Some(buffer) = code.as_mut() {
+                        buffer.push_str(&text);
+                        continue;
+                    }
+                    open!();
+                    let mut properties = String::new();
+                    if inline.link > 0 {
+                        properties.push_str("<w:rStyle w:val=\"Hyperlink\"/>");
+                    }
+                    if inline.bold > 0 {
+                        properties.push_str("<w:b/>");
+                    }
+                    if inline.italic > 0 {
+                        properties.push_str("<w:i/>");
+                    }
+                    if inline.strike > 0 {
+                        properties.push_str("<w:strike/>");
+                    }
+                    if let Some((_, runs)) = current.as_mut() {
+                        runs.push_str(&text_run(&text, &properties));
+                    }
+                },
+                Event::Code(text) => {
+                    open!();
+                    if let Some((_, runs)) = current.as_mut() {
+                        runs.push_str(&text_run(&text, "<w:rStyle w:val=\"CodeChar\"/>"));
+                    }
+                },
+                Event::Html(raw) => {
+                    let text = strip_tags(&raw);
+                    if !text.trim().is_empty() {
+                        open!();
+                        if let Some((_, runs)) = current.as_mut() {
+                            runs.push_str(&text_run(&text, ""));
+                        }
+                    }
+                },
+                Event::SoftBreak => {
+                    if let Some((_, runs)) = current.as_mut() {
+                        runs.push_str(&text_run(" ", ""));
+                    }
+                },
+                Event::HardBreak => {
+                    if let Some((_, runs)) = current.as_mut() {
+                        runs.push_str("<w:r><w:br/></w:r>");
+                    }
+                },
+                Event::Rule => {
+                    close!();
+                    self.paragraph("<w:pPr><w:pBdr><w:bottom w:val=\"single\" w:sz=\"6\" w:space=\"1\" w:color=\"auto\"/></w:pBdr></w:pPr>", "");
+                },
+                Event::TaskListMarker(checked) => {
+                    open!();
+                    if let Some((_, runs)) = current.as_mut() {
+                        runs.push_str(&text_run(if checked { "☒ " } else { "☐ " }, ""));
+                    }
+                },
+                Event::FootnoteReference(name) => {
+                    open!();
+                    if let Some((_, runs)) = current.as_mut() {
+                        runs.push_str(&text_run(&format!("[{}]", name), ""));
+                    }
+                },
+            }
+        }
+        close!();
+    }
+
+    fn document_xml(&self) -> String {
+        format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+             <w:document {ns}><w:body>{body}\
+             <w:sectPr><w:pgSz w:w=\"11906\" w:h=\"16838\"/>\
+             <w:pgMar w:top=\"1440\" w:right=\"1440\" w:bottom=\"1440\" w:left=\"1440\" w:header=\"708\" w:footer=\"708\" w:gutter=\"0\"/>\
+             </w:sectPr></w:body></w:document>",
+            ns = W_NS,
+            body = self.body,
+        )
+    }
+
+    fn document_rels(&self) -> String {
+        let mut rels = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+             <Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">\
+             <Relationship Id=\"rId1\" Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles\" Target=\"styles.xml\"/>\
+             <Relationship Id=\"rId2\" Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/numbering\" Target=\"numbering.xml\"/>\
+             <Relationship Id=\"rId3\" Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/settings\" Target=\"settings.xml\"/>"
+        );
+        for (index, target) in self.links.iter().enumerate() {
+            rels.push_str(&format!(
+                "<Relationship Id=\"rId{}\" Type=\"{}\" Target=\"{}\" TargetMode=\"External\"/>",
+                FIRST_LINK_REL + index,
+                REL_HYPERLINK,
+                xml_escape(target),
+            ));
+        }
+        rels.push_str("</Relationships>");
+        rels
+    }
+
+    fn numbering_xml(&self) -> String {
+        let levels = |format: &str, text: &dyn Fn(usize) -> String| -> String {
+            (0..LIST_LEVELS).map(|level| format!(
+                "<w:lvl w:ilvl=\"{lvl}\"><w:start w:val=\"1\"/><w:numFmt w:val=\"{fmt}\"/><w:lvlText w:val=\"{text}\"/><w:lvlJc w:val=\"left\"/>\
+                 <w:pPr><w:ind w:left=\"{left}\" w:hanging=\"360\"/></w:pPr></w:lvl>",
+                lvl = level,
+                fmt = format,
+                text = text(level),
+                left = 720 * (level + 1),
+            )).collect()
+        };
+        let bullets = ["•", "◦", "▪"];
+        let mut xml = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+             <w:numbering {ns}>\
+             <w:abstractNum w:abstractNumId=\"0\"><w:multiLevelType w:val=\"hybridMultilevel\"/>{bullet}</w:abstractNum>\
+             <w:abstractNum w:abstractNumId=\"1\"><w:multiLevelType w:val=\"hybridMultilevel\"/>{decimal}</w:abstractNum>\
+             <w:num w:numId=\"{bullet_id}\"><w:abstractNumId w:val=\"0\"/></w:num>",
+            ns = W_NS,
+            bullet = levels("bullet", &|level| bullets[level % bullets.len()].to_string()),
+            decimal = levels("decimal", &|level| format!("%{}.", level + 1)),
+            bullet_id = BULLET_NUM_ID,
+        );
+        for (num_id, level, start) in &self.ordered_lists {
+            xml.push_str(&format!(
+                "<w:num w:numId=\"{}\"><w:abstractNumId w:val=\"1\"/><w:lvlOverride w:ilvl=\"{}\"><w:startOverride w:val=\"{}\"/></w:lvlOverride></w:num>",
+                num_id, level, start
+            ));
+        }
+        xml.push_str("</w:numbering>");
+        xml
+    }
+}
+
+fn styles_xml() -> String {
+    let heading = |level: usize, size: usize| format!(
+        "<w:style w:type=\"paragraph\" w:styleId=\"Heading{level}\"><w:name w:val=\"heading {level}\"/><w:basedOn w:val=\"Normal\"/>\
+         <w:next w:val=\"Normal\"/><w:uiPriority w:val=\"9\"/><w:qFormat/>\
+         <w:pPr><w:keepNext/><w:keepLines/><w:spacing w:before=\"360\" w:after=\"120\"/><w:outlineLvl w:val=\"{outline}\"/></w:pPr>\
+         <w:rPr><w:b/><w:sz w:val=\"{size}\"/></w:rPr></w:style>",
+        level = level,
+        outline = level - 1,
+        size = size,
+    );
+    let headings: String = [(1, 36), (2, 30), (3, 26), (4, 24), (5, 22), (6, 22)]
+        .iter()
+        .map(|(level, size)| heading(*level, *size))
+        .collect();
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+         <w:styles {ns}>\
+         <w:docDefaults><w:rPrDefault><w:rPr><w:sz w:val=\"24\"/><w:szCs w:val=\"24\"/><w:lang w:val=\"en-US\" w:eastAsia=\"zh-TW\"/></w:rPr></w:rPrDefault>\
+         <w:pPrDefault><w:pPr><w:spacing w:after=\"160\" w:line=\"276\" w:lineRule=\"auto\"/></w:pPr></w:pPrDefault></w:docDefaults>\
+         <w:style w:type=\"paragraph\" w:default=\"1\" w:styleId=\"Normal\"><w:name w:val=\"Normal\"/><w:qFormat/></w:style>\
+         <w:style w:type=\"paragraph\" w:styleId=\"Title\"><w:name w:val=\"Title\"/><w:basedOn w:val=\"Normal\"/><w:next w:val=\"Normal\"/><w:qFormat/>\
+         <w:pPr><w:spacing w:before=\"2400\" w:after=\"240\"/><w:jc w:val=\"center\"/></w:pPr><w:rPr><w:b/><w:sz w:val=\"56\"/></w:rPr></w:style>\
+         <w:style w:type=\"paragraph\" w:styleId=\"Subtitle\"><w:name w:val=\"Subtitle\"/><w:basedOn w:val=\"Normal\"/><w:next w:val=\"Normal\"/><w:qFormat/>\
+         <w:pPr><w:jc w:val=\"center\"/></w:pPr><w:rPr><w:sz w:val=\"28\"/></w:rPr></w:style>\
+         {headings}\
+         <w:style w:type=\"paragraph\" w:styleId=\"Quote\"><w:name w:val=\"Quote\"/><w:basedOn w:val=\"Normal\"/><w:qFormat/>\
+         <w:pPr><w:ind w:left=\"720\" w:right=\"720\"/></w:pPr><w:rPr><w:i/></w:rPr></w:style>\
+         <w:style w:type=\"paragraph\" w:styleId=\"Code\"><w:name w:val=\"Code\"/><w:basedOn w:val=\"Normal\"/>\
+         <w:pPr><w:spacing w:after=\"0\" w:line=\"240\" w:lineRule=\"auto\"/><w:ind w:left=\"360\"/></w:pPr>\
+         <w:rPr><w:rFonts w:ascii=\"Courier New\" w:hAnsi=\"Courier New\" w:cs=\"Courier New\"/><w:sz w:val=\"20\"/></w:rPr></w:style>\
+         <w:style w:type=\"paragraph\" w:styleId=\"ListParagraph\"><w:name w:val=\"List Paragraph\"/><w:basedOn w:val=\"Normal\"/><w:qFormat/>\
+         <w:pPr><w:spacing w:after=\"60\"/><w:ind w:left=\"720\"/></w:pPr></w:style>\
+         <w:style w:type=\"paragraph\" w:styleId=\"TOCHeading\"><w:name w:val=\"TOC Heading\"/><w:basedOn w:val=\"Heading1\"/><w:next w:val=\"Normal\"/>\
+         <w:pPr><w:outlineLvl w:val=\"9\"/></w:pPr></w:style>\
+         <w:style w:type=\"paragraph\" w:styleId=\"TOC1\"><w:name w:val=\"toc 1\"/><w:basedOn w:val=\"Normal\"/><w:next w:val=\"Normal\"/>\
+         <w:pPr><w:tabs><w:tab w:val=\"right\" w:leader=\"dot\" w:pos=\"9016\"/></w:tabs><w:spacing w:after=\"100\"/></w:pPr></w:style>\
+         <w:style w:type=\"paragraph\" w:styleId=\"TOC2\"><w:name w:val=\"toc 2\"/><w:basedOn w:val=\"Normal\"/><w:next w:val=\"Normal\"/>\
+         <w:pPr><w:tabs><w:tab w:val=\"right\" w:leader=\"dot\" w:pos=\"9016\"/></w:tabs><w:spacing w:after=\"100\"/><w:ind w:left=\"240\"/></w:pPr></w:style>\
+         <w:style w:type=\"character\" w:styleId=\"Hyperlink\"><w:name w:val=\"Hyperlink\"/><w:rPr><w:color w:val=\"0563C1\"/><w:u w:val=\"single\"/></w:rPr></w:style>\
+         <w:style w:type=\"character\" w:styleId=\"CodeChar\"><w:name w:val=\"Code Char\"/>\
+         <w:rPr><w:rFonts w:ascii=\"Courier New\" w:hAnsi=\"Courier New\" w:cs=\"Courier New\"/><w:sz w:val=\"20\"/></w:rPr></w:style>\
+         <w:style w:type=\"table\" w:styleId=\"TableGrid\"><w:name w:val=\"Table Grid\"/><w:tblPr><w:tblBorders>\
+         <w:top w:val=\"single\" w:sz=\"4\" w:space=\"0\" w:color=\"auto\"/><w:left w:val=\"single\" w:sz=\"4\" w:space=\"0\" w:color=\"auto\"/>\
+         <w:bottom w:val=\"single\" w:sz=\"4\" w:space=\"0\" w:color=\"auto\"/><w:right w:val=\"single\" w:sz=\"4\" w:space=\"0\" w:color=\"auto\"/>\
+         <w:insideH w:val=\"single\" w:sz=\"4\" w:space=\"0\" w:color=\"auto\"/><w:insideV w:val=\"single\" w:sz=\"4\" w:space=\"0\" w:color=\"auto\"/>\
+         </w:tblBorders></w:tblPr></w:style>\
+         </w:styles>",
+        ns = W_NS,
+        headings = headings,
+    )
+}
+
+// 開啟文件時更新目錄欄位
+fn settings_xml() -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+         <w:settings {}><w:defaultTabStop w:val=\"720\"/><w:updateFields w:val=\"true\"/></w:settings>",
+        W_NS
+    )
+}
+
+const CONTENT_TYPES: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+    <Types xmlns=\"http://schemas.openxmlformats.org/package/2006/content-types\">\
+    <Default Extension=\"rels\" ContentType=\"application/vnd.openxmlformats-package.relationships+xml\"/>\
+    <Default Extension=\"xml\" ContentType=\"application/xml\"/>\
+    <Override PartName=\"/word/document.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml\"/>\
+    <Override PartName=\"/word/styles.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.wordprocessingml.styles+xml\"/>\
+    <Override PartName=\"/word/numbering.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.wordprocessingml.numbering+xml\"/>\
+    <Override PartName=\"/word/settings.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.wordprocessingml.settings+xml\"/>\
+    <Override PartName=\"/docProps/core.xml\" ContentType=\"application/vnd.openxmlformats-package.core-properties+xml\"/>\
+    </Types>";
+
+const PACKAGE_RELS: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+    <Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">\
+    <Relationship Id=\"rId1\" Type=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument\" Target=\"word/document.xml\"/>\
+    <Relationship Id=\"rId2\" Type=\"http://schemas.openxmlformats.org/package/2006/relationships/metadata/core-properties\" Target=\"docProps/core.xml\"/>\
+    </Relationships>";
+
+fn core_xml(properties: &DocProperties) -> String {
+    let optional = |tag: &str, value: &Option<String>| -> String {
+        value.as_deref()
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(|v| format!("<{tag}>{}</{tag}>", xml_escape(v), tag = tag))
+            .unwrap_or_default()
+    };
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ");
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n\
+         <cp:coreProperties xmlns:cp=\"http://schemas.openxmlformats.org/package/2006/metadata/core-properties\" \
+         xmlns:dc=\"http://purl.org/dc/elements/1.1/\" xmlns:dcterms=\"http://purl.org/dc/terms/\" \
+         xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\">\
+         <dc:title>{title}</dc:title>{creator}{subject}{description}{keywords}\
+         <dcterms:created xsi:type=\"dcterms:W3CDTF\">{now}</dcterms:created>\
+         <dcterms:modified xsi:type=\"dcterms:W3CDTF\">{now}</dcterms:modified>\
+         </cp:coreProperties>",
+        title = xml_escape(&properties.title),
+        creator = optional("dc:creator", &properties.author),
+        subject = optional("dc:subject", &properties.subject),
+        description = optional("dc:description", &properties.description),
+        keywords = optional("cp:keywords", &Some(properties.keywords.join(", "))),
+        now = now,
+    )
+}
+
+// 打包成 .docx (zip)
+fn write_docx(builder: &DocxBuilder, properties: &DocProperties, path: &Path) -> Result<(), AppError> {
+    let parts = [
+        ("[Content_Types].xml", CONTENT_TYPES.to_string()),
+        ("_rels/.rels", PACKAGE_RELS.to_string()),
+        ("docProps/core.xml", core_xml(properties)),
+        ("word/document.xml", builder.document_xml()),
+        ("word/_rels/document.xml.rels", builder.document_rels()),
+        ("word/styles.xml", styles_xml()),
+        ("word/numbering.xml", builder.numbering_xml()),
+        ("word/settings.xml", settings_xml()),
+    ];
+    for (name, content) in &parts {
+        check_well_formed(content.as_bytes())
+            .map_err(|e| AppError::Internal(format!("The generated document is invalid: {}: {}", name, e)))?;
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let write = || -> Result<(), AppError> {
+        let mut writer = ZipWriter::new(fs::File::create(path)?);
+        for (name, content) in &parts {
+            writer.start_file(*name, FileOptions::default().compression_method(CompressionMethod::Deflated))?;
+            writer.write_all(content.as_bytes())?;
+        }
+        writer.finish()?;
+        Ok(())
+    };
+    if let Err(e) = write() {
+        let _ = fs::remove_file(path);
+        return Err(e.context("Failed to write DOCX"));
+    }
+    Ok(())
+}
+
+fn output_path(state: &SqliteState, options: &DocxExportOptions, title: &str, fallback: &str) -> Result<PathBuf, AppError> {
+    if let Some(path) = options.output_path.as_deref().filter(|p| !p.trim().is_empty()) {
+        return Ok(PathBuf::from(path));
+    }
+    default_export_path(state, "", title, fallback, ".docx")
+}
+
+fn project_properties(project: &Project, title: String, options: &DocxExportOptions) -> DocProperties {
+    DocProperties {
+        title,
+        author: options.author.clone(),
+        subject: project.category.clone(),
+        description: project.description.clone(),
+        keywords: split_keywords(&project.keywords),
+    }
+}
+
+// 書名頁、目錄與各章 (每章從新的一頁開始)
+fn build_book(project: &Project, chapters: &[Chapter], options: &DocxExportOptions) -> DocxBuilder {
+    let mut builder = DocxBuilder::default();
+    builder.paragraph(&paragraph_style("Title"), &text_run(&project.title, ""));
+    if let Some(author) = options.author.as_deref().filter(|a| !a.trim().is_empty()) {
+        builder.paragraph(&paragraph_style("Subtitle"), &text_run(author.trim(), ""));
+    }
+    if let Some(description) = project.description.as_deref().filter(|d| !d.trim().is_empty()) {
+        builder.paragraph("<w:pPr><w:jc w:val=\"center\"/></w:pPr>", &text_run(description.trim(), ""));
+    }
+    builder.page_break();
+
+    // Bookmark names are assigned in order, so the contents can be written before the chapters
+    let entries: Vec<(String, String)> = chapters.iter().enumerate()
+        .map(|(index, chapter)| (chapter_title(chapter), format!("_TocChapter{}", index + 1)))
+        .collect();
+    builder.table_of_contents(&entries);
+
+    for chapter in chapters {
+        builder.chapter_heading(&chapter_title(chapter), true);
+        builder.push_markdown(strip_title_heading(&chapter.content, &chapter.title), 1);
+    }
+    builder
+}
+
+// 匯出單篇文章為 DOCX，回傳檔案路徑
+#[tauri::command]
+pub async fn export_blog_docx(blog_id: i64, options: Option<DocxExportOptions>, state: tauri::State<'_, SqliteState>) -> Result<String, AppError> {
+    let pool = state.inner().clone();
+    let options = options.unwrap_or_default();
+    state.run(move |conn| {
+        let blog = db::load_blog(conn, blog_id)?;
+        let project = db::load_project(conn, blog.project_id)?;
+
+        let mut builder = DocxBuilder::default();
+        builder.paragraph(&paragraph_style("Title"), &text_run(&blog.title, ""));
+        builder.push_markdown(strip_title_heading(&blog.content, &blog.title), 0);
+
+        let mut properties = project_properties(&project, blog.title.clone(), &options);
+        properties.keywords = split_keywords(&blog.keywords);
+        let path = output_path(&pool, &options, &blog.title, &format!("post-{}", blog.id))?;
+        write_docx(&builder, &properties, &path)?;
+        println!("Exported blog {} to {:?}", blog_id, path);
+        Ok(path.to_string_lossy().to_string())
+    }).await
+}
+
+// 匯出單一章節為 DOCX
+#[tauri::command]
+pub async fn export_chapter_docx(chapter_id: i64, options: Option<DocxExportOptions>, state: tauri::State<'_, SqliteState>) -> Result<String, AppError> {
+    let pool = state.inner().clone();
+    let options = options.unwrap_or_default();
+    state.run(move |conn| {
+        let chapter = db::load_chapter(conn, chapter_id)?;
+        let project = db::load_project(conn, chapter.project_id)?;
+
+        let title = chapter_title(&chapter);
+        let mut builder = DocxBuilder::default();
+        builder.chapter_heading(&title, false);
+        builder.push_markdown(strip_title_heading(&chapter.content, &chapter.title), 1);
+
+        let properties = project_properties(&project, title.clone(), &options);
+        let path = output_path(&pool, &options, &title, &format!("chapter-{}", chapter.chapter_number))?;
+        write_docx(&builder, &properties, &path)?;
+        println!("Exported chapter {} to {:?}", chapter_id, path);
+        Ok(path.to_string_lossy().to_string())
+    }).await
+}
+
+// 匯出整本書為 DOCX (含目錄)
+#[tauri::command]
+pub async fn export_project_docx(project_id: i64, options: Option<DocxExportOptions>, state: tauri::State<'_, SqliteState>) -> Result<String, AppError> {
+    let pool = state.inner().clone();
+    let options = options.unwrap_or_default();
+    state.run(move |conn| {
+        let project = db::load_project(conn, project_id)?;
+        if project.type_ != "book" {
+            return Err(AppError::validation("Whole-project DOCX export is only available for book projects"));
+        }
+        let chapters = db::load_chapters(conn, project_id)?;
+        if chapters.is_empty() {
+            return Err(AppError::validation("This book has no chapters to export"));
+        }
+
+        let builder = build_book(&project, &chapters, &options);
+        let properties = project_properties(&project, project.title.clone(), &options);
+        let path = output_path(&pool, &options, &project.title, &format!("book-{}", project_id))?;
+        write_docx(&builder, &properties, &path)?;
+        println!("Exported {} chapters of project {} to {:?}", chapters.len(), project_id, path);
+        Ok(path.to_string_lossy().to_string())
+    }).await
+}
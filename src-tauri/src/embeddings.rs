@@ -0,0 +1,336 @@
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use chrono::Local;
+use tauri::{AppHandle, Manager};
+
+use crate::SqliteState;
+use crate::error::AppError;
+use crate::llm::{self, LlmProvider};
+use crate::rag::{self, RetrievedPassage};
+
+// 離線時的詞彙後備：將詞彙特徵雜湊成向量，不需要網路
+// It matches shared words like the BM25 index, not meaning; semantic similarity needs a provider embedding model
+const LEXICAL_MODEL: &str = "local-hash-v1";
+const LEXICAL_DIMENSIONS: usize = 384;
+
+// 每次送出的片段數量
+const EMBEDDING_BATCH_SIZE: usize = 32;
+const DEFAULT_SEARCH_LIMIT: usize = 10;
+
+// 設定鍵："local" (預設，詞彙後備) 或 "provider"
+const SETTING_EMBEDDING_SOURCE: &str = "embedding_source";
+
+// Only one background embedding pass runs at a time; later requests set PENDING so the running pass loops again
+static EMBEDDING_RUNNING: AtomicBool = AtomicBool::new(false);
+static EMBEDDING_PENDING: AtomicBool = AtomicBool::new(false);
+
+// 向量的性質：詞彙後備只比對相同的詞，語意模型可找到意思相近的段落
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum EmbeddingKind {
+    LexicalFallback,
+    Semantic,
+}
+
+// 嵌入索引狀態
+#[derive(Debug, Serialize)]
+pub struct EmbeddingStatus {
+    pub model: String,
+    pub kind: EmbeddingKind,
+    pub total_chunks: i64,
+    pub embedded_chunks: i64,
+}
+
+// 檢索時使用的查詢向量與產生它的模型
+pub struct QueryEmbedding {
+    pub model: String,
+    pub vector: Vec<f32>,
+}
+
+// 嵌入來源
+pub enum Embedder {
+    LexicalFallback,
+    Provider(Box<dyn LlmProvider>),
+}
+
+impl Embedder {
+    // 儲存在 chunk_embeddings.model 的識別字串
+    pub fn model_id(&self) -> String {
+        match self {
+            Embedder::LexicalFallback => LEXICAL_MODEL.to_string(),
+            Embedder::Provider(provider) => format!(
+                "{}:{}",
+                provider.kind().label().to_lowercase(),
+                provider.embedding_model().unwrap_or_default()
+            ),
+        }
+    }
+
+    pub fn kind(&self) -> EmbeddingKind {
+        match self {
+            Embedder::LexicalFallback => EmbeddingKind::LexicalFallback,
+            Embedder::Provider(_) => EmbeddingKind::Semantic,
+        }
+    }
+
+    pub async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, AppError> {
+        let vectors = match self {
+            Embedder::LexicalFallback => texts.iter().map(|text| lexical_embedding(text)).collect(),
+            Embedder::Provider(provider) => provider.embed(texts).await?,
+        };
+        if vectors.len() != texts.len() {
+            return Err(AppError::Parse(format!("Expected {} embeddings but received {}", texts.len(), vectors.len())));
+        }
+        Ok(vectors)
+    }
+}
+
+// 依設定選擇嵌入來源；供應商沒有嵌入端點時改用詞彙後備
+pub fn resolve_embedder(conn: &Connection) -> Result<Embedder, AppError> {
+    let source: Option<String> = conn.query_row(
+        "SELECT value FROM settings WHERE key = ?1",
+        [SETTING_EMBEDDING_SOURCE],
+        |row| row.get(0)
+    ).optional()?;
+
+    if source.as_deref() != Some("provider") {
+        return Ok(Embedder::LexicalFallback);
+    }
+
+    let provider = llm::default_provider(conn)?;
+    if provider.embedding_model().is_none() {
+        println!("{} has no embedding endpoint, using the lexical fallback", provider.kind().label());
+        return Ok(Embedder::LexicalFallback);
+    }
+    Ok(Embedder::Provider(provider))
+}
+
+// 詞彙後備：將詞彙雜湊到固定維度並做 L2 正規化
+fn lexical_embedding(text: &str) -> Vec<f32> {
+    let mut counts: HashMap<String, f32> = HashMap::new();
+    for token in rag::tokenize(text) {
+        *counts.entry(token).or_insert(0.0) += 1.0;
+    }
+
+    let mut vector = vec![0.0f32; LEXICAL_DIMENSIONS];
+    for (token, count) in counts {
+        let hash = u64::from_str_radix(&rag::content_hash(&token), 16).unwrap_or(0);
+        let index = (hash % LEXICAL_DIMENSIONS as u64) as usize;
+        // The high bit picks the sign so colliding tokens tend to cancel rather than accumulate
+        let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+        vector[index] += sign * (1.0 + count.ln());
+    }
+
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        for v in vector.iter_mut() {
+            *v /= norm;
+        }
+    }
+    vector
+}
+
+fn vector_to_blob(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+fn blob_to_vector(blob: &[u8]) -> Vec<f32> {
+    blob.chunks_exact(4)
+        .map(|bytes| f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .collect()
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f64 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+    let mut dot = 0.0f64;
+    let mut norm_a = 0.0f64;
+    let mut norm_b = 0.0f64;
+    for (x, y) in a.iter().zip(b.iter()) {
+        dot += *x as f64 * *y as f64;
+        norm_a += *x as f64 * *x as f64;
+        norm_b += *y as f64 * *y as f64;
+    }
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a.sqrt() * norm_b.sqrt())
+}
+
+// 為檢索計算查詢向量；失敗時回傳 None，檢索只使用 BM25
+pub async fn embed_query(state: &SqliteState, query: &str) -> Option<QueryEmbedding> {
+    let embedder = match state.run(|conn| resolve_embedder(conn)).await {
+        Ok(embedder) => embedder,
+        Err(e) => {
+            println!("No embedder for retrieval: {}", e);
+            return None;
+        }
+    };
+    match embedder.embed(&[query.to_string()]).await {
+        Ok(mut vectors) => vectors.pop().map(|vector| QueryEmbedding { model: embedder.model_id(), vector }),
+        Err(e) => {
+            println!("Failed to embed retrieval query with {}: {}", embedder.model_id(), e);
+            None
+        }
+    }
+}
+
+// 以查詢向量為同一模型嵌入過的片段評分，依相似度由高到低排列
+// Chunks not yet embedded with the query's model are left out
+pub fn chunk_similarities(conn: &Connection, query: &QueryEmbedding, project_id: Option<i64>) -> Result<Vec<(i64, f64)>, AppError> {
+    let mut stmt = conn.prepare(
+        "SELECT c.id, e.vector
+         FROM content_chunks c
+         JOIN chunk_embeddings e ON e.content_hash = c.content_hash AND e.model = ?1
+         WHERE ?2 IS NULL OR c.project_id = ?2"
+    )?;
+    let rows = stmt.query_map(params![query.model, project_id], |row| {
+        Ok((row.get::<_, i64>(0)?, row.get::<_, Vec<u8>>(1)?))
+    })?;
+
+    let mut scored = Vec::new();
+    for row in rows {
+        let (chunk_id, blob) = row?;
+        let score = cosine_similarity(&query.vector, &blob_to_vector(&blob));
+        // Unrelated chunks score around zero; keeping them would only add noise to the ranking
+        if score > 0.0 {
+            scored.push((chunk_id, score));
+        }
+    }
+    scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    Ok(scored)
+}
+
+// 取得尚未以指定模型嵌入的片段內容
+fn pending_chunks(conn: &Connection, model: &str, limit: usize) -> Result<Vec<(String, String)>, AppError> {
+    let mut stmt = conn.prepare(
+        "SELECT content_hash, MIN(content) FROM content_chunks c
+         WHERE NOT EXISTS (SELECT 1 FROM chunk_embeddings e WHERE e.content_hash = c.content_hash AND e.model = ?1)
+         GROUP BY content_hash
+         LIMIT ?2"
+    )?;
+    let rows = stmt.query_map(params![model, limit as i64], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    rows.collect::<Result<Vec<_>, _>>().map_err(AppError::from)
+}
+
+fn save_embeddings(conn: &Connection, model: &str, hashes: &[String], vectors: &[Vec<f32>]) -> Result<(), AppError> {
+    let now = Local::now().to_rfc3339();
+    for (hash, vector) in hashes.iter().zip(vectors.iter()) {
+        conn.execute(
+            "INSERT OR REPLACE INTO chunk_embeddings (content_hash, model, dimensions, vector, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![hash, model, vector.len() as i64, vector_to_blob(vector), now],
+        )?;
+    }
+    Ok(())
+}
+
+// 刪除已不屬於任何片段的向量
+fn prune_embeddings(conn: &Connection) -> Result<usize, AppError> {
+    conn.execute(
+        "DELETE FROM chunk_embeddings WHERE content_hash NOT IN (SELECT content_hash FROM content_chunks)",
+        [],
+    ).map_err(AppError::from)
+}
+
+// 為所有尚未嵌入的片段計算向量
+async fn embed_pending_chunks(app: &AppHandle) -> Result<usize, AppError> {
+    let state = app.state::<SqliteState>();
+    let embedder = state.run(|conn| resolve_embedder(conn)).await?;
+    let model = embedder.model_id();
+
+    let mut embedded = 0;
+    loop {
+        let batch_model = model.clone();
+        let batch = state.run(move |conn| pending_chunks(conn, &batch_model, EMBEDDING_BATCH_SIZE)).await?;
+        if batch.is_empty() {
+            break;
+        }
+
+        let (hashes, texts): (Vec<String>, Vec<String>) = batch.into_iter().unzip();
+        let vectors = embedder.embed(&texts).await?;
+
+        embedded += hashes.len();
+        let batch_model = model.clone();
+        state.run(move |conn| save_embeddings(conn, &batch_model, &hashes, &vectors)).await?;
+    }
+
+    let pruned = state.run(|conn| prune_embeddings(conn)).await?;
+    println!("Embedded {} chunks with {}, pruned {} stale vectors", embedded, model, pruned);
+    Ok(embedded)
+}
+
+// 在背景更新嵌入索引；內容儲存後呼叫
+pub fn schedule_embedding(app: &AppHandle) {
+    EMBEDDING_PENDING.store(true, Ordering::SeqCst);
+    if EMBEDDING_RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        while EMBEDDING_PENDING.swap(false, Ordering::SeqCst) {
+            if let Err(e) = embed_pending_chunks(&app).await {
+                // Chunks stay pending and are retried on the next save
+                println!("Failed to update embeddings: {}", e);
+                break;
+            }
+        }
+        EMBEDDING_RUNNING.store(false, Ordering::SeqCst);
+    });
+}
+
+// 以嵌入向量搜尋相近的片段；使用詞彙後備時只會找到用詞相同的片段
+#[tauri::command]
+pub async fn search_similar_chunks(
+    query: String,
+    project_id: Option<i64>,
+    limit: Option<usize>,
+    state: tauri::State<'_, SqliteState>
+) -> Result<Vec<RetrievedPassage>, AppError> {
+    println!("search_similar_chunks called with query: {}", query);
+
+    let embedder = state.run(|conn| resolve_embedder(conn)).await?;
+    let model = embedder.model_id();
+    let vector = embedder.embed(&[query]).await?
+        .pop()
+        .ok_or_else(|| AppError::Parse("No embedding returned for query".to_string()))?;
+
+    let query_embedding = QueryEmbedding { model: model.clone(), vector };
+    let passages = state.run(move |conn| {
+        let ranked = chunk_similarities(conn, &query_embedding, project_id)?;
+        rag::load_passages(conn, &ranked, None, limit.unwrap_or(DEFAULT_SEARCH_LIMIT))
+    }).await?;
+
+    println!("Found {} similar chunks using {}", passages.len(), model);
+    Ok(passages)
+}
+
+// 獲取嵌入索引狀態
+#[tauri::command]
+pub async fn get_embedding_status(state: tauri::State<'_, SqliteState>) -> Result<EmbeddingStatus, AppError> {
+    state.run(|conn| {
+        let embedder = resolve_embedder(conn)?;
+        let model = embedder.model_id();
+
+        let total_chunks: i64 = conn.query_row("SELECT COUNT(*) FROM content_chunks", [], |row| row.get(0))?;
+        let embedded_chunks: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM content_chunks c
+             WHERE EXISTS (SELECT 1 FROM chunk_embeddings e WHERE e.content_hash = c.content_hash AND e.model = ?1)",
+            [&model],
+            |row| row.get(0)
+        )?;
+
+        Ok(EmbeddingStatus { model, kind: embedder.kind(), total_chunks, embedded_chunks })
+    }).await
+}
+
+// 補算缺少的嵌入 (例如切換嵌入來源後)
+#[tauri::command]
+pub fn refresh_embeddings(app_handle: AppHandle) -> Result<bool, AppError> {
+    schedule_embedding(&app_handle);
+    Ok(true)
+}
@@ -0,0 +1,234 @@
+use rusqlite::{Connection, DatabaseName};
+use serde::Serialize;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+use crate::SqliteState;
+use crate::error::AppError;
+use crate::migrations::{self, MigrationState, MigrationStatus};
+
+// 整個資料庫加密 (SQLCipher)
+// An encrypted file has no plaintext SQLite header, which is how startup tells the two apart.
+const SQLITE_HEADER: &[u8; 16] = b"SQLite format 3\0";
+const MIN_PASSPHRASE_LEN: usize = 8;
+
+#[derive(Debug, Serialize)]
+pub struct EncryptionStatus {
+    pub encrypted: bool,
+    pub unlocked: bool,
+}
+
+// 檔案存在且不是明文 SQLite 格式時視為已加密
+pub fn is_encrypted(db_path: &Path) -> bool {
+    let mut header = [0u8; 16];
+    match fs::File::open(db_path).and_then(|mut file| file.read_exact(&mut header)) {
+        Ok(()) => &header != SQLITE_HEADER,
+        // Missing or empty files are new databases
+        Err(_) => false,
+    }
+}
+
+// 開啟資料庫；有密碼時先設定金鑰並確認可以讀取
+pub fn open_database(path: &Path, passphrase: Option<&str>) -> rusqlite::Result<Connection> {
+    let conn = Connection::open(path)?;
+    if let Some(passphrase) = passphrase {
+        conn.pragma_update(None, "key", passphrase)?;
+    }
+    // SQLCipher only checks the key when the first page is read
+    conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |_| Ok(()))?;
+    Ok(conn)
+}
+
+// 密碼錯誤時 SQLCipher 回報 "file is not a database"
+pub(crate) fn passphrase_error(error: rusqlite::Error) -> AppError {
+    match &error {
+        rusqlite::Error::SqliteFailure(e, _) if e.code == rusqlite::ErrorCode::NotADatabase => {
+            AppError::validation("Incorrect passphrase")
+        },
+        _ => AppError::from(error),
+    }
+}
+
+fn validate_passphrase(passphrase: &str) -> Result<(), AppError> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(AppError::validation(format!("Passphrase must be at least {} characters", MIN_PASSPHRASE_LEN)));
+    }
+    Ok(())
+}
+
+pub(crate) fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+// 確認檔案可以用指定的密碼開啟且內容完整
+pub(crate) fn verify_database(path: &Path, passphrase: Option<&str>) -> Result<(), AppError> {
+    let conn = open_database(path, passphrase).map_err(passphrase_error)?;
+    let result: String = conn.query_row("PRAGMA quick_check", [], |row| row.get(0))?;
+    if result != "ok" {
+        return Err(AppError::Internal(format!("Database copy failed its integrity check: {}", result)));
+    }
+    Ok(())
+}
+
+// 將已開啟的資料庫完整匯出到新檔案，以指定的密碼加密 (None 為明文)
+pub(crate) fn export_database_copy(conn: &Connection, target: &Path, passphrase: Option<&str>) -> Result<(), AppError> {
+    if target.exists() {
+        fs::remove_file(target)?;
+    }
+    // Fold the WAL into the main file so the export sees every committed change
+    conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+    let user_version: i32 = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+
+    // An empty key attaches a plaintext database
+    conn.execute(
+        "ATTACH DATABASE ?1 AS copy KEY ?2",
+        [target.to_string_lossy().to_string(), passphrase.unwrap_or("").to_string()],
+    )?;
+    let exported = conn.query_row("SELECT sqlcipher_export('copy')", [], |_| Ok(()))
+        // sqlcipher_export does not carry the schema version over
+        .and_then(|_| conn.pragma_update(Some(DatabaseName::Attached("copy")), "user_version", user_version));
+    conn.execute_batch("DETACH DATABASE copy")?;
+    if let Err(e) = exported {
+        let _ = fs::remove_file(target);
+        return Err(AppError::from(e));
+    }
+    Ok(())
+}
+
+// 以新檔案取代資料庫檔案；呼叫前必須關閉所有連線 (DbPool::exclusive)
+pub(crate) fn replace_database_file(path: &Path, replacement: &Path) -> Result<(), AppError> {
+    // The WAL belongs to the old file; remove it so it is not replayed against the new one
+    for suffix in ["-wal", "-shm"] {
+        let sidecar = sibling_path(path, suffix);
+        if sidecar.exists() {
+            fs::remove_file(&sidecar)?;
+        }
+    }
+    fs::rename(replacement, path)?;
+    Ok(())
+}
+
+// 將明文資料庫匯出為加密副本，確認無誤後取代原檔
+fn encrypt_database_file(path: &Path, passphrase: &str) -> Result<(), AppError> {
+    let encrypted_path = sibling_path(path, ".encrypting");
+    {
+        let conn = open_database(path, None)?;
+        export_database_copy(&conn, &encrypted_path, Some(passphrase))?;
+    }
+
+    if let Err(e) = verify_database(&encrypted_path, Some(passphrase)) {
+        let _ = fs::remove_file(&encrypted_path);
+        return Err(e.context("Encryption was not applied"));
+    }
+
+    replace_database_file(path, &encrypted_path)?;
+    println!("Database at {:?} is now encrypted", path);
+    Ok(())
+}
+
+// 獲取加密狀態
+#[tauri::command]
+pub fn get_encryption_status(state: tauri::State<'_, SqliteState>) -> Result<EncryptionStatus, AppError> {
+    let locked = state.is_locked();
+    Ok(EncryptionStatus {
+        encrypted: locked || state.passphrase().is_some() || is_encrypted(state.path()),
+        unlocked: !locked,
+    })
+}
+
+// 以密碼解鎖資料庫，然後執行啟動時延後的遷移與背景工作
+#[tauri::command]
+pub async fn unlock_database(
+    passphrase: String,
+    app_handle: AppHandle,
+    state: tauri::State<'_, SqliteState>,
+    migration_state: tauri::State<'_, MigrationState>,
+) -> Result<MigrationStatus, AppError> {
+    if !state.is_locked() {
+        println!("Database is already unlocked");
+        return Ok(migration_state.0.lock().unwrap().clone());
+    }
+
+    let db_path = state.path().to_path_buf();
+    let key = passphrase.clone();
+    let status = tauri::async_runtime::spawn_blocking(move || -> Result<MigrationStatus, AppError> {
+        open_database(&db_path, Some(&key)).map_err(passphrase_error)?;
+        Ok(migrations::run_migrations(&db_path, Some(&key)))
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Unlock task failed: {}", e)))??;
+
+    state.unlock(passphrase);
+    println!("Database unlocked");
+    *migration_state.0.lock().unwrap() = status.clone();
+
+    if status.error.is_none() {
+        crate::run_startup_tasks(&app_handle)?;
+    }
+    Ok(status)
+}
+
+// 為現有的明文資料庫啟用加密
+#[tauri::command]
+pub async fn enable_database_encryption(passphrase: String, state: tauri::State<'_, SqliteState>) -> Result<bool, AppError> {
+    validate_passphrase(&passphrase)?;
+    if state.is_locked() {
+        return Err(AppError::validation("The database is already encrypted"));
+    }
+
+    let pool = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        pool.exclusive(|path, current| {
+            if current.is_some() || is_encrypted(path) {
+                return Err(AppError::validation("The database is already encrypted"));
+            }
+            encrypt_database_file(path, &passphrase)?;
+            *current = Some(passphrase);
+            // Earlier backups and exports were written before encryption was turned on
+            println!("Note: backups and exports created before now are not encrypted");
+            Ok(true)
+        })
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Encryption task failed: {}", e)))?
+}
+
+// 更換加密資料庫的密碼
+#[tauri::command]
+pub async fn change_database_passphrase(
+    current_passphrase: String,
+    new_passphrase: String,
+    state: tauri::State<'_, SqliteState>,
+) -> Result<bool, AppError> {
+    validate_passphrase(&new_passphrase)?;
+    if state.is_locked() {
+        return Err(AppError::Locked("Unlock the database before changing its passphrase".to_string()));
+    }
+
+    let pool = state.inner().clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        pool.exclusive(|path, current| {
+            match current.as_deref() {
+                None => return Err(AppError::validation("The database is not encrypted")),
+                Some(key) if key != current_passphrase => return Err(AppError::validation("Current passphrase is incorrect")),
+                Some(_) => {}
+            }
+
+            {
+                let conn = open_database(path, Some(&current_passphrase)).map_err(passphrase_error)?;
+                conn.pragma_update(None, "rekey", &new_passphrase)?;
+            }
+            verify_database(path, Some(&new_passphrase))?;
+
+            *current = Some(new_passphrase);
+            println!("Database passphrase changed");
+            Ok(true)
+        })
+    })
+    .await
+    .map_err(|e| AppError::Internal(format!("Passphrase change failed: {}", e)))?
+}
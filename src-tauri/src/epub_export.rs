@@ -0,0 +1,386 @@
+use chrono::Utc;
+use quick_xml::events::Event as XmlEvent;
+use quick_xml::Reader;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::SqliteState;
+use crate::db::{self, Chapter, Project};
+use crate::error::AppError;
+use crate::export_common::{chapter_title, default_export_path, detect_language};
+use crate::markdown_export::{self, markdown_to_xhtml, split_keywords, strip_title_heading};
+
+// EPUB 3 匯出 (書籍專案)
+const MIMETYPE: &str = "application/epub+zip";
+const CONTENT_DIR: &str = "OEBPS";
+
+const STYLESHEET: &str = "body { font-family: serif; line-height: 1.5; margin: 0 5%; }
+h1 { text-align: center; margin: 2em 0 1em; page-break-before: always; }
+h2, h3, h4, h5, h6 { margin: 1.5em 0 0.5em; }
+p { margin: 0; text-indent: 1.5em; }
+h1 + p, h2 + p, h3 + p, blockquote p, li p { text-indent: 0; }
+blockquote { margin: 1em 2em; font-style: italic; }
+pre { white-space: pre-wrap; font-size: 0.9em; }
+table { border-collapse: collapse; margin: 1em 0; }
+th, td { border: 1px solid #999; padding: 0.25em 0.5em; }
+.title-page { text-align: center; margin-top: 30%; }
+.title-page p { text-indent: 0; margin: 1em 0; }
+.author { font-size: 1.2em; }
+nav ol { list-style: none; padding: 0; }
+";
+
+#[derive(Debug, Default, Deserialize)]
+pub struct EpubExportOptions {
+    pub author: Option<String>,
+    // BCP 47 語言代碼；未指定時依書名判斷
+    pub language: Option<String>,
+    // 未指定時寫到 exports 目錄
+    pub output_path: Option<String>,
+}
+
+// 一本書的所有檔案 (路徑相對於 EPUB 根目錄)
+struct EpubPackage {
+    files: Vec<(String, Vec<u8>)>,
+}
+
+impl EpubPackage {
+    fn add(&mut self, path: &str, content: impl Into<Vec<u8>>) {
+        self.files.push((path.to_string(), content.into()));
+    }
+
+    fn get(&self, path: &str) -> Option<&[u8]> {
+        self.files.iter().find(|(p, _)| p == path).map(|(_, c)| c.as_slice())
+    }
+}
+
+struct ChapterDoc {
+    id: String,
+    href: String,
+    title: String,
+}
+
+pub(crate) fn xml_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            // Control characters are not allowed in XML 1.0
+            c if (c as u32) < 0x20 && !matches!(c, '\t' | '\n' | '\r') => {},
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn xhtml_document(title: &str, language: &str, body: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <!DOCTYPE html>\n\
+         <html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\" xml:lang=\"{lang}\" lang=\"{lang}\">\n\
+         <head>\n<meta charset=\"UTF-8\"/>\n<title>{title}</title>\n<link rel=\"stylesheet\" type=\"text/css\" href=\"style.css\"/>\n</head>\n\
+         <body>\n{body}</body>\n</html>\n",
+        lang = xml_escape(language),
+        title = xml_escape(title),
+        body = body,
+    )
+}
+
+fn container_xml() -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <container version=\"1.0\" xmlns=\"urn:oasis:names:tc:opendocument:xmlns:container\">\n\
+         <rootfiles>\n<rootfile full-path=\"{}/content.opf\" media-type=\"application/oebps-package+xml\"/>\n</rootfiles>\n\
+         </container>\n",
+        CONTENT_DIR
+    )
+}
+
+fn title_page(project: &Project, options: &EpubExportOptions, language: &str) -> String {
+    let mut body = String::from("<section class=\"title-page\" epub:type=\"titlepage\">\n");
+    body.push_str(&format!("<h1>{}</h1>\n", xml_escape(&project.title)));
+    if let Some(author) = options.author.as_deref().filter(|a| !a.trim().is_empty()) {
+        body.push_str(&format!("<p class=\"author\">{}</p>\n", xml_escape(author.trim())));
+    }
+    if let Some(description) = project.description.as_deref().filter(|d| !d.trim().is_empty()) {
+        body.push_str(&format!("<p>{}</p>\n", xml_escape(description.trim())));
+    }
+    body.push_str("</section>\n");
+    xhtml_document(&project.title, language, &body)
+}
+
+fn chapter_page(chapter: &Chapter, language: &str) -> String {
+    let title = chapter_title(chapter);
+    let content = strip_title_heading(&chapter.content, &chapter.title);
+    let body = format!(
+        "<section epub:type=\"chapter\">\n<h1>{}</h1>\n{}</section>\n",
+        xml_escape(&title),
+        // The chapter title is the only <h1>, so headings in the text move down a level
+        markdown_to_xhtml(content, 1, true),
+    );
+    xhtml_document(&title, language, &body)
+}
+
+fn nav_document(project: &Project, chapters: &[ChapterDoc], language: &str) -> String {
+    let mut body = String::from("<nav epub:type=\"toc\" id=\"toc\">\n<h1>Contents</h1>\n<ol>\n");
+    for chapter in chapters {
+        body.push_str(&format!("<li><a href=\"{}\">{}</a></li>\n", chapter.href, xml_escape(&chapter.title)));
+    }
+    body.push_str("</ol>\n</nav>\n");
+    body.push_str("<nav epub:type=\"landmarks\" id=\"landmarks\" hidden=\"hidden\">\n<ol>\n");
+    body.push_str("<li><a epub:type=\"titlepage\" href=\"title.xhtml\">Title Page</a></li>\n");
+    if let Some(first) = chapters.first() {
+        body.push_str(&format!("<li><a epub:type=\"bodymatter\" href=\"{}\">Start</a></li>\n", first.href));
+    }
+    body.push_str("</ol>\n</nav>\n");
+    xhtml_document(&project.title, language, &body)
+}
+
+// EPUB 2 閱讀器使用的目錄
+fn ncx_document(project: &Project, identifier: &str, chapters: &[ChapterDoc]) -> String {
+    let mut points = String::new();
+    for (index, chapter) in chapters.iter().enumerate() {
+        points.push_str(&format!(
+            "<navPoint id=\"nav-{id}\" playOrder=\"{order}\">\n<navLabel><text>{title}</text></navLabel>\n<content src=\"{href}\"/>\n</navPoint>\n",
+            id = chapter.id,
+            order = index + 1,
+            title = xml_escape(&chapter.title),
+            href = chapter.href,
+        ));
+    }
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <ncx xmlns=\"http://www.daisy.org/z3986/2005/ncx/\" version=\"2005-1\">\n\
+         <head>\n<meta name=\"dtb:uid\" content=\"{uid}\"/>\n<meta name=\"dtb:depth\" content=\"1\"/>\n\
+         <meta name=\"dtb:totalPageCount\" content=\"0\"/>\n<meta name=\"dtb:maxPageNumber\" content=\"0\"/>\n</head>\n\
+         <docTitle><text>{title}</text></docTitle>\n\
+         <navMap>\n{points}</navMap>\n\
+         </ncx>\n",
+        uid = xml_escape(identifier),
+        title = xml_escape(&project.title),
+        points = points,
+    )
+}
+
+fn package_document(project: &Project, options: &EpubExportOptions, identifier: &str, language: &str, chapters: &[ChapterDoc]) -> String {
+    let mut metadata = String::new();
+    metadata.push_str(&format!("<dc:identifier id=\"book-id\">{}</dc:identifier>\n", xml_escape(identifier)));
+    metadata.push_str(&format!("<dc:title>{}</dc:title>\n", xml_escape(&project.title)));
+    metadata.push_str(&format!("<dc:language>{}</dc:language>\n", xml_escape(language)));
+    if let Some(author) = options.author.as_deref().filter(|a| !a.trim().is_empty()) {
+        metadata.push_str(&format!("<dc:creator id=\"creator\">{}</dc:creator>\n", xml_escape(author.trim())));
+    }
+    if let Some(description) = project.description.as_deref().filter(|d| !d.trim().is_empty()) {
+        metadata.push_str(&format!("<dc:description>{}</dc:description>\n", xml_escape(description.trim())));
+    }
+    // 分類與關鍵字都寫成 dc:subject
+    let mut subjects: Vec<String> = project.category.iter().map(|c| c.trim().to_string()).filter(|c| !c.is_empty()).collect();
+    for keyword in split_keywords(&project.keywords) {
+        if !subjects.contains(&keyword) {
+            subjects.push(keyword);
+        }
+    }
+    for subject in &subjects {
+        metadata.push_str(&format!("<dc:subject>{}</dc:subject>\n", xml_escape(subject)));
+    }
+    if let Some(created) = project.created_at.as_deref().and_then(markdown_export::parse_timestamp) {
+        metadata.push_str(&format!("<dc:date>{}</dc:date>\n", created.format("%Y-%m-%d")));
+    }
+    metadata.push_str(&format!(
+        "<meta property=\"dcterms:modified\">{}</meta>\n",
+        Utc::now().format("%Y-%m-%dT%H:%M:%SZ")
+    ));
+
+    let mut manifest = String::from(
+        "<item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>\n\
+         <item id=\"ncx\" href=\"toc.ncx\" media-type=\"application/x-dtbncx+xml\"/>\n\
+         <item id=\"style\" href=\"style.css\" media-type=\"text/css\"/>\n\
+         <item id=\"title-page\" href=\"title.xhtml\" media-type=\"application/xhtml+xml\"/>\n"
+    );
+    let mut spine = String::from("<itemref idref=\"title-page\"/>\n<itemref idref=\"nav\"/>\n");
+    for chapter in chapters {
+        manifest.push_str(&format!("<item id=\"{}\" href=\"{}\" media-type=\"application/xhtml+xml\"/>\n", chapter.id, chapter.href));
+        spine.push_str(&format!("<itemref idref=\"{}\"/>\n", chapter.id));
+    }
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <package xmlns=\"http://www.idpf.org/2007/opf\" version=\"3.0\" unique-identifier=\"book-id\" xml:lang=\"{lang}\">\n\
+         <metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\">\n{metadata}</metadata>\n\
+         <manifest>\n{manifest}</manifest>\n\
+         <spine toc=\"ncx\">\n{spine}</spine>\n\
+         </package>\n",
+        lang = xml_escape(language),
+        metadata = metadata,
+        manifest = manifest,
+        spine = spine,
+    )
+}
+
+// 組合書籍的所有檔案
+fn build_package(project: &Project, chapters: &[Chapter], options: &EpubExportOptions) -> EpubPackage {
+    let language = options.language.clone()
+        .filter(|l| !l.trim().is_empty())
+        .unwrap_or_else(|| detect_language(project));
+    // Stable per project so re-exports replace the same book in a reader's library
+    let identifier = format!(
+        "urn:stingtao:project:{}:{}",
+        project.id.unwrap_or_default(),
+        project.created_at.as_deref().unwrap_or("").replace(' ', "T"),
+    );
+
+    let docs: Vec<ChapterDoc> = chapters.iter().enumerate().map(|(index, chapter)| ChapterDoc {
+        id: format!("chapter-{:03}", index + 1),
+        href: format!("chapter-{:03}.xhtml", index + 1),
+        title: chapter_title(chapter),
+    }).collect();
+
+    let mut package = EpubPackage { files: Vec::new() };
+    package.add("mimetype", MIMETYPE);
+    package.add("META-INF/container.xml", container_xml());
+    package.add(&format!("{}/content.opf", CONTENT_DIR), package_document(project, options, &identifier, &language, &docs));
+    package.add(&format!("{}/nav.xhtml", CONTENT_DIR), nav_document(project, &docs, &language));
+    package.add(&format!("{}/toc.ncx", CONTENT_DIR), ncx_document(project, &identifier, &docs));
+    package.add(&format!("{}/style.css", CONTENT_DIR), STYLESHEET);
+    package.add(&format!("{}/title.xhtml", CONTENT_DIR), title_page(project, options, &language));
+    for (chapter, doc) in chapters.iter().zip(&docs) {
+        package.add(&format!("{}/{}", CONTENT_DIR, doc.href), chapter_page(chapter, &language));
+    }
+    package
+}
+
+// XML 是否格式正確；回傳錯誤訊息
+pub(crate) fn check_well_formed(xml: &[u8]) -> Result<(), String> {
+    let mut reader = Reader::from_reader(xml);
+    let mut buf = Vec::new();
+    let mut depth = 0usize;
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(XmlEvent::Start(_)) => depth += 1,
+            Ok(XmlEvent::End(_)) => depth = depth.saturating_sub(1),
+            Ok(XmlEvent::Eof) if depth == 0 => return Ok(()),
+            Ok(XmlEvent::Eof) => return Err("unexpected end of document".to_string()),
+            Ok(_) => {},
+            Err(e) => return Err(format!("{} at byte {}", e, reader.buffer_position())),
+        }
+        buf.clear();
+    }
+}
+
+// 寫入前檢查 epubcheck 會驗證的結構規則
+fn validate_package(package: &EpubPackage) -> Result<(), AppError> {
+    let mut problems = Vec::new();
+
+    if package.files.first().map(|(p, c)| (p.as_str(), c.as_slice())) != Some(("mimetype", MIMETYPE.as_bytes())) {
+        problems.push("mimetype must be the first entry".to_string());
+    }
+
+    let mut seen = HashSet::new();
+    for (path, content) in &package.files {
+        if !seen.insert(path.as_str()) {
+            problems.push(format!("{}: duplicate entry", path));
+        }
+        let is_xml = [".xhtml", ".opf", ".ncx", ".xml"].iter().any(|ext| path.ends_with(ext));
+        if is_xml {
+            if let Err(e) = check_well_formed(content) {
+                problems.push(format!("{}: not well-formed ({})", path, e));
+            }
+        }
+    }
+
+    // Every manifest item must exist, and every spine entry must point at a manifest item
+    let opf_path = format!("{}/content.opf", CONTENT_DIR);
+    let opf = package.get(&opf_path).map(|c| String::from_utf8_lossy(c).to_string()).unwrap_or_default();
+    if opf.is_empty() {
+        problems.push("content.opf is missing".to_string());
+    }
+    let attribute = |tag: &str, name: &str| -> Option<String> {
+        let start = tag.find(&format!(" {}=\"", name))? + name.len() + 3;
+        let end = tag[start..].find('"')? + start;
+        Some(tag[start..end].to_string())
+    };
+    let mut manifest_ids = HashSet::new();
+    let mut has_nav = false;
+    for tag in opf.split('<').filter(|t| t.starts_with("item ")) {
+        if let (Some(id), Some(href)) = (attribute(tag, "id"), attribute(tag, "href")) {
+            if package.get(&format!("{}/{}", CONTENT_DIR, href)).is_none() {
+                problems.push(format!("manifest item {} points to missing file {}", id, href));
+            }
+            has_nav |= attribute(tag, "properties").is_some_and(|p| p.split(' ').any(|p| p == "nav"));
+            manifest_ids.insert(id);
+        }
+    }
+    if !has_nav {
+        problems.push("manifest has no navigation document".to_string());
+    }
+    for tag in opf.split('<').filter(|t| t.starts_with("itemref ")) {
+        if let Some(idref) = attribute(tag, "idref") {
+            if !manifest_ids.contains(&idref) {
+                problems.push(format!("spine item {} is not in the manifest", idref));
+            }
+        }
+    }
+    if !opf.contains("dcterms:modified") {
+        problems.push("metadata has no dcterms:modified".to_string());
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(AppError::Internal(format!("The generated EPUB is invalid: {}", problems.join("; "))))
+    }
+}
+
+// mimetype 必須是第一個檔案且不壓縮
+fn write_package(package: &EpubPackage, path: &Path) -> Result<(), AppError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut writer = ZipWriter::new(fs::File::create(path)?);
+    for (name, content) in &package.files {
+        let method = if name == "mimetype" { CompressionMethod::Stored } else { CompressionMethod::Deflated };
+        writer.start_file(name.as_str(), FileOptions::default().compression_method(method))?;
+        writer.write_all(content)?;
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+// 匯出書籍專案為 EPUB，回傳檔案路徑
+#[tauri::command]
+pub async fn export_project_epub(project_id: i64, options: Option<EpubExportOptions>, state: tauri::State<'_, SqliteState>) -> Result<String, AppError> {
+    let pool = state.inner().clone();
+    let options = options.unwrap_or_default();
+    state.run(move |conn| {
+        let project = db::load_project(conn, project_id)?;
+        if project.type_ != "book" {
+            return Err(AppError::validation("EPUB export is only available for book projects"));
+        }
+        let chapters = db::load_chapters(conn, project_id)?;
+        if chapters.is_empty() {
+            return Err(AppError::validation("This book has no chapters to export"));
+        }
+
+        let package = build_package(&project, &chapters, &options);
+        validate_package(&package)?;
+
+        let path = match &options.output_path {
+            Some(path) if !path.trim().is_empty() => PathBuf::from(path),
+            _ => default_export_path(&pool, "", &project.title, &format!("book-{}", project_id), ".epub")?,
+        };
+        if let Err(e) = write_package(&package, &path) {
+            let _ = fs::remove_file(&path);
+            return Err(e.context("Failed to write EPUB"));
+        }
+        println!("Exported {} chapters of project {} to {:?}", chapters.len(), project_id, path);
+        Ok(path.to_string_lossy().to_string())
+    }).await
+}
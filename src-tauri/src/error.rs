@@ -0,0 +1,180 @@
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use serde_json::json;
+use std::fmt;
+
+use crate::ai_agent::AgentError;
+
+// 指令錯誤：序列化為 { code, message, retryable, details }，前端依 code 判斷處理方式
+#[derive(Debug, Clone)]
+pub enum AppError {
+    NotFound(String),
+    Validation(String),
+    Database { message: String, busy: bool },
+    Locked(String),
+    ApiKeyMissing { provider: String },
+    RateLimited { message: String, retry_after: Option<u64>, quota_exhausted: bool },
+    Upstream { message: String, status: Option<u16> },
+    Parse(String),
+    // 使用者取消了 AI 任務
+    Cancelled(String),
+    Internal(String),
+}
+
+impl AppError {
+    pub fn not_found(what: &str, id: i64) -> Self {
+        AppError::NotFound(format!("{} {} not found", what, id))
+    }
+
+    pub fn validation(message: impl Into<String>) -> Self {
+        AppError::Validation(message.into())
+    }
+
+    // 保留錯誤分類，在訊息前加上發生位置的說明
+    pub fn context(self, context: impl fmt::Display) -> Self {
+        let prefix = |message: String| format!("{}: {}", context, message);
+        match self {
+            AppError::NotFound(m) => AppError::NotFound(prefix(m)),
+            AppError::Validation(m) => AppError::Validation(prefix(m)),
+            AppError::Parse(m) => AppError::Parse(prefix(m)),
+            AppError::Cancelled(m) => AppError::Cancelled(prefix(m)),
+            AppError::Internal(m) => AppError::Internal(prefix(m)),
+            AppError::Locked(m) => AppError::Locked(prefix(m)),
+            AppError::Database { message, busy } => AppError::Database { message: prefix(message), busy },
+            AppError::RateLimited { message, retry_after, quota_exhausted } => AppError::RateLimited { message: prefix(message), retry_after, quota_exhausted },
+            AppError::Upstream { message, status } => AppError::Upstream { message: prefix(message), status },
+            AppError::ApiKeyMissing { provider } => AppError::ApiKeyMissing { provider },
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::NotFound(_) => "not_found",
+            AppError::Validation(_) => "validation",
+            AppError::Database { .. } => "database",
+            AppError::Locked(_) => "database_locked",
+            AppError::ApiKeyMissing { .. } => "api_key_missing",
+            AppError::RateLimited { .. } => "rate_limited",
+            AppError::Upstream { .. } => "upstream_http",
+            AppError::Parse(_) => "parse",
+            AppError::Cancelled(_) => "cancelled",
+            AppError::Internal(_) => "internal",
+        }
+    }
+
+    // 是否值得自動重試：暫時性的鎖定、限流與伺服器錯誤
+    pub fn retryable(&self) -> bool {
+        match self {
+            AppError::Database { busy, .. } => *busy,
+            AppError::RateLimited { quota_exhausted, .. } => !quota_exhausted,
+            // No status means the request never got an answer (network failure, timeout)
+            AppError::Upstream { status, .. } => status.is_none_or(|s| s == 408 || s >= 500),
+            _ => false,
+        }
+    }
+
+    pub fn details(&self) -> Option<serde_json::Value> {
+        match self {
+            AppError::ApiKeyMissing { provider } => Some(json!({ "provider": provider })),
+            AppError::RateLimited { retry_after, quota_exhausted, .. } => Some(json!({
+                "retry_after_secs": retry_after,
+                "quota_exhausted": quota_exhausted,
+            })),
+            AppError::Upstream { status: Some(status), .. } => Some(json!({ "status": status })),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AppError::NotFound(message)
+            | AppError::Validation(message)
+            | AppError::Parse(message)
+            | AppError::Cancelled(message)
+            | AppError::Internal(message)
+            | AppError::Locked(message)
+            | AppError::Database { message, .. }
+            | AppError::RateLimited { message, .. }
+            | AppError::Upstream { message, .. } => write!(f, "{}", message),
+            AppError::ApiKeyMissing { provider } => write!(f, "{} API key not set. Please set it in Settings.", provider),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("AppError", 4)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field("retryable", &self.retryable())?;
+        state.serialize_field("details", &self.details())?;
+        state.end()
+    }
+}
+
+impl From<rusqlite::Error> for AppError {
+    fn from(error: rusqlite::Error) -> Self {
+        match &error {
+            rusqlite::Error::QueryReturnedNoRows => AppError::NotFound("Record not found".to_string()),
+            rusqlite::Error::SqliteFailure(e, _) => AppError::Database {
+                message: error.to_string(),
+                busy: matches!(e.code, rusqlite::ErrorCode::DatabaseBusy | rusqlite::ErrorCode::DatabaseLocked),
+            },
+            _ => AppError::Database { message: error.to_string(), busy: false },
+        }
+    }
+}
+
+impl From<AgentError> for AppError {
+    fn from(error: AgentError) -> Self {
+        match error {
+            AgentError::DatabaseError(e) => AppError::from(e),
+            AgentError::InvalidAgentType(t) => AppError::Validation(format!("Invalid agent type: {}", t)),
+            AgentError::ApiKeyNotSet(provider) => AppError::ApiKeyMissing { provider },
+            AgentError::RateLimited { provider, retry_after, quota_exhausted } => AppError::RateLimited {
+                message: if quota_exhausted {
+                    format!("{} API quota exhausted", provider)
+                } else {
+                    format!("{} API rate limit reached, please retry later", provider)
+                },
+                retry_after,
+                quota_exhausted,
+            },
+            AgentError::HttpError { provider, status, message } => AppError::Upstream {
+                message: format!("{} API returned error {}: {}", provider, status, message),
+                status: Some(status),
+            },
+            AgentError::ParseError(message) => AppError::Parse(message),
+            AgentError::SecretError(message) => AppError::Internal(message),
+            AgentError::ApiError(message) => AppError::Upstream { message, status: None },
+        }
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(error: serde_json::Error) -> Self {
+        AppError::Parse(error.to_string())
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(error: std::io::Error) -> Self {
+        AppError::Internal(error.to_string())
+    }
+}
+
+impl From<zip::result::ZipError> for AppError {
+    fn from(error: zip::result::ZipError) -> Self {
+        AppError::Internal(format!("Archive error: {}", error))
+    }
+}
+
+impl From<AppError> for String {
+    fn from(error: AppError) -> Self {
+        error.to_string()
+    }
+}
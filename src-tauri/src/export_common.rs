@@ -0,0 +1,53 @@
+use chrono::Local;
+use std::path::PathBuf;
+
+use crate::SqliteState;
+use crate::db::{Chapter, Project};
+use crate::error::AppError;
+use crate::markdown_export::slugify;
+
+// 各匯出格式共用的輔助函式
+
+// 中日韓文字與全形標點 (分詞、換行與語言判斷使用)
+pub(crate) fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x2E80..=0x2FDF |   // CJK radicals
+        0x3000..=0x30FF |   // CJK symbols and punctuation, Hiragana, Katakana
+        0x3100..=0x31FF |   // Bopomofo, Hangul compatibility jamo
+        0x3400..=0x4DBF |   // CJK Extension A
+        0x4E00..=0x9FFF |   // CJK Unified Ideographs
+        0xAC00..=0xD7AF |   // Hangul syllables
+        0xF900..=0xFAFF |   // CJK Compatibility Ideographs
+        0xFF00..=0xFFEF)    // Halfwidth and fullwidth forms
+}
+
+// 書名含有中日韓文字時預設為繁體中文，否則為英文
+pub(crate) fn detect_language(project: &Project) -> String {
+    let text = format!("{} {}", project.title, project.description.as_deref().unwrap_or(""));
+    if text.chars().any(is_cjk) { "zh-TW".to_string() } else { "en".to_string() }
+}
+
+// 沒有標題的章節以章節編號命名
+pub(crate) fn chapter_title(chapter: &Chapter) -> String {
+    if chapter.title.trim().is_empty() {
+        format!("Chapter {}", chapter.chapter_number)
+    } else {
+        chapter.title.trim().to_string()
+    }
+}
+
+// 預設的匯出位置：資料庫目錄下的 exports/<prefix><標題>_<時間><extension>
+// The fallback names the file when the title has no usable characters
+pub(crate) fn default_export_path(state: &SqliteState, prefix: &str, title: &str, fallback: &str, extension: &str) -> Result<PathBuf, AppError> {
+    let app_dir = state.path().parent()
+        .ok_or_else(|| AppError::Internal("Invalid database path".to_string()))?;
+    let slug = slugify(title);
+    let name = format!(
+        "{}{}_{}{}",
+        prefix,
+        if slug.is_empty() { fallback.to_string() } else { slug },
+        Local::now().format("%Y%m%d_%H%M%S"),
+        extension,
+    );
+    Ok(app_dir.join("exports").join(name))
+}
@@ -0,0 +1,504 @@
+use chrono::Local;
+use rusqlite::{Connection, OptionalExtension, Transaction, params};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::AppHandle;
+
+use crate::SqliteState;
+use crate::backup;
+use crate::db;
+use crate::embeddings;
+use crate::llm;
+use crate::error::AppError;
+use crate::publishing;
+use crate::rag;
+use crate::revisions;
+use crate::secrets;
+use crate::wordpress;
+
+// 匯入時記錄的版本作者
+pub(crate) const IMPORT_AUTHOR: &str = "import";
+// 驗證失敗時最多列出的問題數
+const MAX_REPORTED_PROBLEMS: usize = 10;
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportMode {
+    // 保留現有資料，只加入新的內容
+    Merge,
+    // 先清除所有專案、文章與章節再匯入
+    Replace,
+}
+
+// 匯入結果中的一筆資料
+#[derive(Debug, Serialize)]
+pub struct ImportItem {
+    pub kind: String,
+    pub source_id: Option<i64>,
+    pub target_id: Option<i64>,
+    pub title: String,
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportReport {
+    pub mode: ImportMode,
+    pub created: Vec<ImportItem>,
+    pub skipped: Vec<ImportItem>,
+    pub conflicts: Vec<ImportItem>,
+    pub backup_path: Option<String>,
+}
+
+impl ImportReport {
+    pub(crate) fn new(mode: ImportMode) -> Self {
+        ImportReport { mode, created: Vec::new(), skipped: Vec::new(), conflicts: Vec::new(), backup_path: None }
+    }
+}
+
+pub(crate) fn item(kind: &str, source_id: Option<i64>, target_id: Option<i64>, title: &str, reason: Option<String>) -> ImportItem {
+    ImportItem { kind: kind.to_string(), source_id, target_id, title: title.to_string(), reason }
+}
+
+// export_database_json 的檔案格式
+#[derive(Debug, Deserialize)]
+struct ExportFile {
+    projects: Vec<ExportedProject>,
+    blogs: Vec<ExportedBlog>,
+    #[serde(default)]
+    chapters: Vec<ExportedChapter>,
+    #[serde(default)]
+    settings: Vec<ExportedSetting>,
+}
+
+fn default_project_type() -> String {
+    "blog".to_string()
+}
+
+#[derive(Debug, Deserialize)]
+struct ExportedProject {
+    id: i64,
+    title: String,
+    #[serde(default = "default_project_type")]
+    type_: String,
+    description: Option<String>,
+    category: Option<String>,
+    time_commitment: Option<String>,
+    publishing_frequency: Option<String>,
+    custom_frequency: Option<String>,
+    deadline: Option<String>,
+    availability: Option<String>,
+    reminder_frequency: Option<String>,
+    publishing_platform: Option<String>,
+    wordpress_url: Option<String>,
+    substack_url: Option<String>,
+    custom_platform: Option<String>,
+    monetization_strategy: Option<String>,
+    monetization_goals: Option<String>,
+    keywords: Option<String>,
+    target_audience: Option<String>,
+    reference_links: Option<String>,
+    structure: Option<String>,
+    content_strategy: Option<String>,
+    seo_strategy: Option<String>,
+    goal: Option<String>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+    created_at: Option<String>,
+    updated_at: Option<String>,
+    progress: Option<i32>,
+    article_length: Option<String>,
+    receive_notifications: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExportedBlog {
+    id: i64,
+    project_id: i64,
+    title: String,
+    content: Option<String>,
+    keywords: Option<String>,
+    created_at: Option<String>,
+    updated_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExportedChapter {
+    id: i64,
+    project_id: i64,
+    title: String,
+    content: Option<String>,
+    chapter_number: Option<i32>,
+    created_at: Option<String>,
+    updated_at: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExportedSetting {
+    key: String,
+    value: String,
+}
+
+// 檢查檔案內容是否一致：標題不可為空、ID 不可重複、外鍵必須指向檔案中的專案
+fn validate_export(data: &ExportFile) -> Result<(), AppError> {
+    let mut problems = Vec::new();
+    let mut project_ids = HashSet::new();
+
+    for project in &data.projects {
+        if !project_ids.insert(project.id) {
+            problems.push(format!("project id {} appears more than once", project.id));
+        }
+        if project.title.trim().is_empty() {
+            problems.push(format!("project {} has no title", project.id));
+        }
+    }
+
+    let mut blog_ids = HashSet::new();
+    for blog in &data.blogs {
+        if !blog_ids.insert(blog.id) {
+            problems.push(format!("blog id {} appears more than once", blog.id));
+        }
+        if blog.title.trim().is_empty() {
+            problems.push(format!("blog {} has no title", blog.id));
+        }
+        if !project_ids.contains(&blog.project_id) {
+            problems.push(format!("blog {} refers to missing project {}", blog.id, blog.project_id));
+        }
+    }
+
+    let mut chapter_ids = HashSet::new();
+    for chapter in &data.chapters {
+        if !chapter_ids.insert(chapter.id) {
+            problems.push(format!("chapter id {} appears more than once", chapter.id));
+        }
+        if chapter.title.trim().is_empty() {
+            problems.push(format!("chapter {} has no title", chapter.id));
+        }
+        if !project_ids.contains(&chapter.project_id) {
+            problems.push(format!("chapter {} refers to missing project {}", chapter.id, chapter.project_id));
+        }
+    }
+
+    if problems.is_empty() {
+        return Ok(());
+    }
+    let total = problems.len();
+    problems.truncate(MAX_REPORTED_PROBLEMS);
+    let more = if total > MAX_REPORTED_PROBLEMS { format!(" (and {} more)", total - MAX_REPORTED_PROBLEMS) } else { String::new() };
+    Err(AppError::validation(format!("The import file is inconsistent: {}{}", problems.join("; "), more)))
+}
+
+pub(crate) fn normalize_title(title: &str) -> String {
+    title.trim().to_lowercase()
+}
+
+// 取代前先備份目前的資料庫
+fn backup_before_import(conn: &Connection, db_path: &Path) -> Result<PathBuf, AppError> {
+    // VACUUM INTO fails on an existing file, so each import gets its own name
+    let backup_path = backup::unique_backup_path(&backup::backup_dir(db_path)?, "pre_import_");
+    // VACUUM INTO keeps the encryption key of an encrypted database
+    conn.execute("VACUUM INTO ?1", [backup_path.to_string_lossy().to_string()])?;
+    println!("Backed up database to {:?} before import", backup_path);
+    Ok(backup_path)
+}
+
+// 清除所有內容與相關的 AI 紀錄、索引及版本
+fn clear_content(tx: &Transaction) -> Result<(), AppError> {
+    tx.execute_batch(
+        "DELETE FROM agent_suggestions;
+        DELETE FROM agent_reasoning;
+        DELETE FROM rag_retrievals;
+        DELETE FROM chunk_terms;
+        DELETE FROM content_chunks;
+        DELETE FROM revisions;
+        DELETE FROM site_templates;
+        DELETE FROM publish_log;
+        DELETE FROM chapters;
+        DELETE FROM blogs;
+        DELETE FROM projects;"
+    )?;
+
+    // 專案層級的 LLM 與發佈設定及其金鑰也屬於被刪除的專案
+    for prefix in [llm::project_settings_prefix(), publishing::project_settings_prefix()] {
+        let pattern = format!("{}%", prefix);
+        tx.execute("DELETE FROM settings WHERE key LIKE ?1", [&pattern])?;
+        tx.execute("DELETE FROM secrets WHERE name LIKE ?1", [&pattern])?;
+    }
+    tx.execute("DELETE FROM secrets WHERE name LIKE ?1", [format!("{}:%:credentials", wordpress::PLATFORM)])?;
+    println!("Cleared existing projects before import");
+    Ok(())
+}
+
+// 專案層級的設定鍵含有專案 ID，匯入時換成新的 ID
+// Returns None when the key belongs to a project that was not imported
+fn remap_setting_key(key: &str, project_map: &HashMap<i64, i64>) -> Option<String> {
+    for prefix in [llm::project_settings_prefix(), publishing::project_settings_prefix()] {
+        let Some(rest) = key.strip_prefix(prefix.as_str()) else { continue };
+        let (id, tail) = match rest.find('.') {
+            Some(index) => rest.split_at(index),
+            None => (rest, ""),
+        };
+        let Ok(old_id) = id.parse::<i64>() else { continue };
+        return project_map.get(&old_id).map(|new_id| format!("{}{}{}", prefix, new_id, tail));
+    }
+    Some(key.to_string())
+}
+
+fn insert_project(tx: &Transaction, project: &ExportedProject, now: &str) -> Result<i64, AppError> {
+    tx.execute(
+        "INSERT INTO projects (
+            title, type_, description, category, time_commitment,
+            publishing_frequency, custom_frequency, deadline, availability,
+            reminder_frequency, publishing_platform, wordpress_url, substack_url,
+            custom_platform, monetization_strategy, monetization_goals, keywords,
+            target_audience, reference_links, structure, content_strategy,
+            seo_strategy, goal, start_date, end_date, created_at, updated_at, progress,
+            article_length, receive_notifications
+        )
+        VALUES (
+            ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16,
+            ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28, ?29, ?30
+        )",
+        params![
+            project.title,
+            project.type_,
+            project.description,
+            project.category,
+            project.time_commitment,
+            project.publishing_frequency,
+            project.custom_frequency,
+            project.deadline,
+            project.availability,
+            project.reminder_frequency,
+            project.publishing_platform,
+            project.wordpress_url,
+            project.substack_url,
+            project.custom_platform,
+            project.monetization_strategy,
+            project.monetization_goals,
+            project.keywords,
+            project.target_audience,
+            project.reference_links,
+            project.structure,
+            project.content_strategy,
+            project.seo_strategy,
+            project.goal,
+            project.start_date,
+            project.end_date,
+            project.created_at.as_deref().unwrap_or(now),
+            project.updated_at.as_deref().unwrap_or(now),
+            project.progress.unwrap_or(0),
+            project.article_length,
+            project.receive_notifications.unwrap_or(false) as i32,
+        ],
+    )?;
+    Ok(tx.last_insert_rowid())
+}
+
+// 同一專案中標題相同的現有項目：(id, content, created_at)
+pub(crate) fn find_existing(tx: &Transaction, table: &str, project_id: i64, title: &str) -> Result<Option<(i64, String, Option<String>)>, AppError> {
+    let existing = tx.query_row(
+        &format!(
+            "SELECT id, COALESCE(content, ''), created_at FROM {} WHERE project_id = ?1 AND lower(trim(title)) = ?2 ORDER BY id LIMIT 1",
+            table
+        ),
+        params![project_id, normalize_title(title)],
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    ).optional()?;
+    Ok(existing)
+}
+
+// 標題相同時不匯入；內容相同視為重複，否則為衝突 (回傳 是否衝突, 原因)
+pub(crate) fn classify_duplicate(
+    existing: &(i64, String, Option<String>),
+    content: &str,
+    created_at: &Option<String>,
+) -> (bool, String) {
+    let (_, existing_content, existing_created_at) = existing;
+    if existing_content == content {
+        (false, "identical content already exists".to_string())
+    } else if created_at.is_some() && created_at == existing_created_at {
+        (true, "the same item was edited both here and in the import file; kept the local version".to_string())
+    } else {
+        (true, "a different item with this title already exists in the project".to_string())
+    }
+}
+
+fn import_data(tx: &Transaction, data: &ExportFile, mode: ImportMode, report: &mut ImportReport) -> Result<(Vec<i64>, Vec<i64>), AppError> {
+    let now = Local::now().to_rfc3339();
+    let mut project_map: HashMap<i64, i64> = HashMap::new();
+    // Projects created by this import cannot hold duplicates, so their content skips the lookups
+    let mut new_projects: HashSet<i64> = HashSet::new();
+
+    for project in &data.projects {
+        if mode == ImportMode::Merge {
+            let existing: Option<i64> = tx.query_row(
+                "SELECT id FROM projects WHERE lower(trim(title)) = ?1 AND type_ = ?2 ORDER BY id LIMIT 1",
+                params![normalize_title(&project.title), project.type_],
+                |row| row.get(0),
+            ).optional()?;
+            if let Some(existing_id) = existing {
+                project_map.insert(project.id, existing_id);
+                report.skipped.push(item("project", Some(project.id), Some(existing_id), &project.title,
+                    Some("project already exists; merging its content".to_string())));
+                continue;
+            }
+        }
+
+        let id = insert_project(tx, project, &now)?;
+        project_map.insert(project.id, id);
+        new_projects.insert(id);
+        report.created.push(item("project", Some(project.id), Some(id), &project.title, None));
+    }
+
+    let mut created_blogs = Vec::new();
+    for blog in &data.blogs {
+        let project_id = project_map[&blog.project_id];
+        let content = blog.content.clone().unwrap_or_default();
+
+        if !new_projects.contains(&project_id) {
+            if let Some(existing) = find_existing(tx, "blogs", project_id, &blog.title)? {
+                let (conflict, reason) = classify_duplicate(&existing, &content, &blog.created_at);
+                let entry = item("blog", Some(blog.id), Some(existing.0), &blog.title, Some(reason));
+                if conflict { report.conflicts.push(entry) } else { report.skipped.push(entry) }
+                continue;
+            }
+        }
+
+        tx.execute(
+            "INSERT INTO blogs (project_id, title, content, keywords, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                project_id,
+                blog.title,
+                content,
+                blog.keywords,
+                blog.created_at.as_deref().unwrap_or(&now),
+                blog.updated_at.as_deref().unwrap_or(&now),
+            ],
+        )?;
+        let id = tx.last_insert_rowid();
+        created_blogs.push(id);
+        report.created.push(item("blog", Some(blog.id), Some(id), &blog.title, None));
+    }
+
+    let mut created_chapters = Vec::new();
+    for chapter in &data.chapters {
+        let project_id = project_map[&chapter.project_id];
+        let content = chapter.content.clone().unwrap_or_default();
+
+        if !new_projects.contains(&project_id) {
+            if let Some(existing) = find_existing(tx, "chapters", project_id, &chapter.title)? {
+                let (conflict, reason) = classify_duplicate(&existing, &content, &chapter.created_at);
+                let entry = item("chapter", Some(chapter.id), Some(existing.0), &chapter.title, Some(reason));
+                if conflict { report.conflicts.push(entry) } else { report.skipped.push(entry) }
+                continue;
+            }
+        }
+
+        tx.execute(
+            "INSERT INTO chapters (project_id, title, content, chapter_number, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                project_id,
+                chapter.title,
+                content,
+                chapter.chapter_number.unwrap_or(0),
+                chapter.created_at.as_deref().unwrap_or(&now),
+                chapter.updated_at.as_deref().unwrap_or(&now),
+            ],
+        )?;
+        let id = tx.last_insert_rowid();
+        created_chapters.push(id);
+        report.created.push(item("chapter", Some(chapter.id), Some(id), &chapter.title, None));
+    }
+
+    for setting in &data.settings {
+        // API 金鑰只能透過 secrets API 設定
+        if secrets::is_secret_name(&setting.key) {
+            report.skipped.push(item("setting", None, None, &setting.key, Some("secrets are not imported".to_string())));
+            continue;
+        }
+        let Some(key) = remap_setting_key(&setting.key, &project_map) else {
+            report.skipped.push(item("setting", None, None, &setting.key, Some("its project was not imported".to_string())));
+            continue;
+        };
+
+        let existing: Option<String> = tx.query_row(
+            "SELECT value FROM settings WHERE key = ?1",
+            [&key],
+            |row| row.get(0),
+        ).optional()?;
+        match existing {
+            Some(value) if value == setting.value => {
+                report.skipped.push(item("setting", None, None, &key, Some("already set to the same value".to_string())));
+                continue;
+            },
+            Some(_) if mode == ImportMode::Merge => {
+                report.conflicts.push(item("setting", None, None, &key, Some("a different value is already set; kept the local value".to_string())));
+                continue;
+            },
+            _ => {}
+        }
+
+        tx.execute(
+            "INSERT INTO settings (key, value, created_at, updated_at) VALUES (?1, ?2, ?3, ?3)
+             ON CONFLICT(key) DO UPDATE SET value = ?2, updated_at = ?3",
+            params![key, setting.value, now],
+        )?;
+        report.created.push(item("setting", None, None, &key, None));
+    }
+
+    Ok((created_blogs, created_chapters))
+}
+
+// 匯入 export_database_json 產生的檔案
+#[tauri::command]
+pub async fn import_database_json(path: String, mode: ImportMode, app_handle: AppHandle, state: tauri::State<'_, SqliteState>) -> Result<ImportReport, AppError> {
+    println!("Importing {} in {:?} mode", path, mode);
+
+    let text = fs::read_to_string(&path)
+        .map_err(|e| AppError::validation(format!("Cannot read import file {}: {}", path, e)))?;
+    let data: ExportFile = serde_json::from_str(&text)
+        .map_err(|e| AppError::validation(format!("Not a valid export file: {}", e)))?;
+    validate_export(&data)?;
+    println!(
+        "Import file has {} projects, {} blogs, {} chapters, {} settings",
+        data.projects.len(), data.blogs.len(), data.chapters.len(), data.settings.len()
+    );
+
+    let db_path = state.path().to_path_buf();
+    let report = state.run(move |conn| {
+        let mut report = ImportReport::new(mode);
+        if mode == ImportMode::Replace {
+            report.backup_path = Some(backup_before_import(conn, &db_path)?.to_string_lossy().to_string());
+        }
+
+        // 全部成功才寫入
+        let tx = conn.transaction()?;
+        if mode == ImportMode::Replace {
+            clear_content(&tx)?;
+        }
+        let (blogs, chapters) = import_data(&tx, &data, mode, &mut report)?;
+
+        for id in &blogs {
+            revisions::record_revision_logged(&tx, rag::SOURCE_BLOG, *id, IMPORT_AUTHOR);
+            db::reindex_source(&tx, rag::SOURCE_BLOG, *id);
+        }
+        for id in &chapters {
+            revisions::record_revision_logged(&tx, rag::SOURCE_CHAPTER, *id, IMPORT_AUTHOR);
+            db::reindex_source(&tx, rag::SOURCE_CHAPTER, *id);
+        }
+        tx.commit()?;
+
+        println!(
+            "Import finished: {} created, {} skipped, {} conflicts",
+            report.created.len(), report.skipped.len(), report.conflicts.len()
+        );
+        Ok::<_, AppError>(report)
+    }).await?;
+
+    embeddings::schedule_embedding(&app_handle);
+    Ok(report)
+}
@@ -0,0 +1,480 @@
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use chrono::Local;
+use tauri::async_runtime::{channel, Sender};
+use tauri::{AppHandle, Manager};
+
+use crate::SqliteState;
+use crate::error::AppError;
+use crate::embeddings;
+use crate::rag;
+use crate::ai_agent::{self, AgentType, AiStreamChunk, AiStreamDone, AiStreamError};
+
+// 任務事件名稱 (片段、完成與錯誤沿用 ai-stream-* 事件，request_id 為 job_id)
+pub const AI_JOB_STARTED_EVENT: &str = "ai-job-started";
+pub const AI_JOB_CANCELLED_EVENT: &str = "ai-job-cancelled";
+
+// 累積多少新字元後寫回資料庫，避免每個片段都寫入
+const FLUSH_THRESHOLD: usize = 1024;
+
+static JOB_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+// 任務狀態，對應 agent_reasoning.status
+pub const STATUS_RUNNING: &str = "running";
+pub const STATUS_COMPLETED: &str = "completed";
+pub const STATUS_CANCELLED: &str = "cancelled";
+pub const STATUS_FAILED: &str = "failed";
+pub const STATUS_INTERRUPTED: &str = "interrupted";
+
+// 文章相關 AI 指令共用的參數
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArticleRequest {
+    pub prompt: String,
+    pub selected_text: String,
+    pub project_data: Option<serde_json::Value>,
+    pub current_title: String,
+    pub current_content: String,
+}
+
+// 可以作為背景任務執行的 AI 指令
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AiJobRequest {
+    Generate { prompt: String, agent_type: AgentType },
+    ArticleDraft(ArticleRequest),
+    PlanArticle(ArticleRequest),
+    AnalyzeArticle(ArticleRequest),
+    AdjustStyle(ArticleRequest),
+    ReviewFinal(ArticleRequest),
+    InlineEdit { selected_text: String, article_title: String, user_prompt: String },
+}
+
+impl AiJobRequest {
+    fn agent_type(&self) -> AgentType {
+        match self {
+            AiJobRequest::Generate { agent_type, .. } => agent_type.clone(),
+            AiJobRequest::ArticleDraft(_) => AgentType::DraftGenerator,
+            AiJobRequest::PlanArticle(_) => AgentType::Planning,
+            AiJobRequest::AnalyzeArticle(_) => AgentType::Research,
+            AiJobRequest::AdjustStyle(_) => AgentType::Editor,
+            AiJobRequest::ReviewFinal(_) => AgentType::Reviewer,
+            AiJobRequest::InlineEdit { .. } => AgentType::InlineEditor,
+        }
+    }
+
+    fn build_prompt(&self) -> String {
+        match self {
+            AiJobRequest::Generate { prompt, .. } => prompt.clone(),
+            AiJobRequest::ArticleDraft(r) => ai_agent::build_draft_prompt(&r.prompt, &r.selected_text, &r.project_data, &r.current_title, &r.current_content),
+            AiJobRequest::PlanArticle(r) => ai_agent::build_plan_prompt(&r.prompt, &r.selected_text, &r.project_data, &r.current_title, &r.current_content),
+            AiJobRequest::AnalyzeArticle(r) => ai_agent::build_analysis_prompt(&r.prompt, &r.selected_text, &r.current_title, &r.current_content),
+            AiJobRequest::AdjustStyle(r) => ai_agent::build_style_prompt(&r.prompt, &r.selected_text, &r.current_title, &r.current_content),
+            AiJobRequest::ReviewFinal(r) => ai_agent::build_review_prompt(&r.prompt, &r.selected_text, &r.current_title, &r.current_content),
+            AiJobRequest::InlineEdit { selected_text, article_title, user_prompt } => ai_agent::build_inline_edit_prompt(selected_text, article_title, user_prompt),
+        }
+    }
+
+    // 需要自動檢索參考資料的指令回傳檢索查詢
+    fn rag_query(&self) -> Option<String> {
+        match self {
+            AiJobRequest::ArticleDraft(r) | AiJobRequest::AnalyzeArticle(r) =>
+                Some(rag::build_query(&r.current_title, &r.prompt, &r.selected_text, &r.current_content)),
+            _ => None,
+        }
+    }
+
+    // 對完整輸出做最後處理 (草稿需要解析 JSON)
+    fn finish(&self, output: String) -> Result<String, AppError> {
+        match self {
+            AiJobRequest::ArticleDraft(r) => ai_agent::process_draft_response(output, &r.current_title, &r.current_content),
+            _ => Ok(output),
+        }
+    }
+}
+
+// 任務資訊，回傳給前端
+#[derive(Debug, Clone, Serialize)]
+pub struct GenerationJob {
+    pub job_id: String,
+    pub blog_id: i64,
+    pub agent_type: AgentType,
+    pub reasoning_id: i64,
+    pub started_at: String,
+    pub received_chars: usize,
+}
+
+// 任務已收到的部分輸出
+struct PartialOutput {
+    text: String,
+    flushed_len: usize,
+}
+
+// 任務的最終結果，交給等待中的指令
+type JobResult = Result<String, AppError>;
+
+// 啟動 (或續寫) 任務所需的資料
+struct JobStart {
+    job_id: String,
+    request: AiJobRequest,
+    prompt: String,
+    blog_id: i64,
+    reasoning_id: i64,
+    base_output: String,
+}
+
+struct JobEntry {
+    info: GenerationJob,
+    partial: Arc<Mutex<PartialOutput>>,
+    handle: Option<tauri::async_runtime::JoinHandle<()>>,
+}
+
+// 執行中任務的註冊表
+// Whoever removes a job from the map owns its final status write, so a cancel racing a completion is resolved once
+#[derive(Default)]
+pub struct JobRegistry(Mutex<HashMap<String, JobEntry>>);
+
+impl JobRegistry {
+    fn take(&self, job_id: &str) -> Option<JobEntry> {
+        self.0.lock().unwrap().remove(job_id)
+    }
+
+    fn contains(&self, job_id: &str) -> bool {
+        self.0.lock().unwrap().contains_key(job_id)
+    }
+
+    fn has_reasoning(&self, reasoning_id: i64) -> bool {
+        self.0.lock().unwrap().values().any(|entry| entry.info.reasoning_id == reasoning_id)
+    }
+}
+
+fn next_job_id() -> String {
+    let counter = JOB_COUNTER.fetch_add(1, Ordering::SeqCst);
+    format!("job_{}_{}", Local::now().timestamp_millis(), counter)
+}
+
+// 應用啟動時將上次未完成的任務標記為中斷，以便之後續寫
+pub fn mark_interrupted_jobs(conn: &Connection) -> Result<(), AppError> {
+    let count = conn.execute(
+        "UPDATE agent_reasoning SET status = ?1 WHERE status = ?2",
+        params![STATUS_INTERRUPTED, STATUS_RUNNING],
+    )?;
+    if count > 0 {
+        println!("Marked {} unfinished AI jobs as interrupted", count);
+    }
+    Ok(())
+}
+
+fn update_reasoning_output(conn: &Connection, reasoning_id: i64, output: &str, status: &str) -> Result<(), AppError> {
+    let now = Local::now().to_rfc3339();
+    conn.execute(
+        "UPDATE agent_reasoning SET output = ?1, status = ?2, updated_at = ?3 WHERE id = ?4",
+        params![output, status, now, reasoning_id],
+    )?;
+    Ok(())
+}
+
+// 只在任務仍在執行時寫入部分輸出，較晚完成的寫入不會覆寫已結束任務的狀態
+fn update_running_output(conn: &Connection, reasoning_id: i64, output: &str) -> Result<(), AppError> {
+    let now = Local::now().to_rfc3339();
+    conn.execute(
+        "UPDATE agent_reasoning SET output = ?1, updated_at = ?2 WHERE id = ?3 AND status = ?4",
+        params![output, now, reasoning_id, STATUS_RUNNING],
+    )?;
+    Ok(())
+}
+
+async fn persist_output(pool: &SqliteState, reasoning_id: i64, output: String, status: &'static str) {
+    let result = pool.run(move |conn| update_reasoning_output(conn, reasoning_id, &output, status)).await;
+    if let Err(e) = result {
+        println!("Failed to persist output for reasoning {}: {}", reasoning_id, e);
+    }
+}
+
+fn emit<S: Serialize + Clone>(app: &AppHandle, event: &str, payload: S) {
+    if let Err(e) = app.emit_all(event, payload) {
+        println!("Failed to emit {} event: {}", event, e);
+    }
+}
+
+// 啟動背景任務：在註冊表登記後才開始執行，以確保取消時能找到任務
+fn spawn_job(app: AppHandle, registry: &JobRegistry, start: JobStart, done: Option<Sender<JobResult>>) -> GenerationJob {
+    let JobStart { job_id, request, prompt, blog_id, reasoning_id, base_output } = start;
+    let info = GenerationJob {
+        job_id: job_id.clone(),
+        blog_id,
+        agent_type: request.agent_type(),
+        reasoning_id,
+        started_at: Local::now().to_rfc3339(),
+        received_chars: base_output.len(),
+    };
+    let partial = Arc::new(Mutex::new(PartialOutput {
+        flushed_len: base_output.len(),
+        text: base_output,
+    }));
+
+    let mut jobs = registry.0.lock().unwrap();
+    jobs.insert(job_id.clone(), JobEntry {
+        info: info.clone(),
+        partial: partial.clone(),
+        handle: None,
+    });
+
+    let task_info = info.clone();
+    let handle = tauri::async_runtime::spawn(async move {
+        run_job(app, task_info, request, prompt, partial, done).await;
+    });
+
+    if let Some(entry) = jobs.get_mut(&job_id) {
+        entry.handle = Some(handle);
+    }
+
+    info
+}
+
+async fn run_job(app: AppHandle, info: GenerationJob, request: AiJobRequest, prompt: String, partial: Arc<Mutex<PartialOutput>>, done: Option<Sender<JobResult>>) {
+    let GenerationJob { job_id, blog_id, agent_type, reasoning_id, .. } = info;
+    println!("Starting AI job {} for blog {}", job_id, blog_id);
+
+    let pool = app.state::<SqliteState>().inner().clone();
+    let provider = ai_agent::resolve_provider(&agent_type, blog_id, &pool).await;
+
+    let result = match provider {
+        Ok(provider) => {
+            let on_chunk = |chunk: &str| {
+                let flush = {
+                    let mut partial = partial.lock().unwrap();
+                    partial.text.push_str(chunk);
+                    if partial.text.len() - partial.flushed_len >= FLUSH_THRESHOLD {
+                        partial.flushed_len = partial.text.len();
+                        Some(partial.text.clone())
+                    } else {
+                        None
+                    }
+                };
+                if let Some(text) = flush {
+                    let pool = pool.clone();
+                    tauri::async_runtime::spawn(async move {
+                        if let Err(e) = pool.run(move |conn| update_running_output(conn, reasoning_id, &text)).await {
+                            println!("Failed to persist output for reasoning {}: {}", reasoning_id, e);
+                        }
+                    });
+                }
+                emit(&app, ai_agent::AI_STREAM_CHUNK_EVENT, AiStreamChunk {
+                    request_id: job_id.clone(),
+                    chunk: chunk.to_string(),
+                });
+            };
+            provider.generate_stream(&prompt, &on_chunk).await.map_err(AppError::from)
+        },
+        Err(e) => Err(e),
+    };
+
+    // 任務已被取消時，由取消流程負責寫入狀態
+    let registry = app.state::<JobRegistry>();
+    if registry.take(&job_id).is_none() {
+        println!("AI job {} was cancelled before finishing", job_id);
+        return;
+    }
+
+    // 先處理完整輸出，處理失敗時任務記為失敗，之後可以續寫
+    let output = partial.lock().unwrap().text.clone();
+    let result = result.and_then(|_| request.finish(output.clone()));
+    match &result {
+        Ok(text) => {
+            persist_output(&pool, reasoning_id, output, STATUS_COMPLETED).await;
+            println!("AI job {} completed", job_id);
+            emit(&app, ai_agent::AI_STREAM_DONE_EVENT, AiStreamDone {
+                request_id: job_id.clone(),
+                reasoning_id: Some(reasoning_id),
+                text: text.clone(),
+            });
+        },
+        Err(e) => {
+            println!("AI job {} failed: {}", job_id, e);
+            persist_output(&pool, reasoning_id, output, STATUS_FAILED).await;
+            emit(&app, ai_agent::AI_STREAM_ERROR_EVENT, AiStreamError::new(&job_id, e));
+        }
+    }
+    if let Some(done) = done {
+        let _ = done.send(result).await;
+    }
+}
+
+// 建立推理記錄並啟動任務，部分輸出會寫回這一列
+async fn create_job(app: &AppHandle, request: AiJobRequest, blog_id: i64, job_id: Option<String>, done: Option<Sender<JobResult>>) -> Result<GenerationJob, AppError> {
+    let registry = app.state::<JobRegistry>();
+    let job_id = job_id.unwrap_or_else(next_job_id);
+    if registry.contains(&job_id) {
+        return Err(AppError::validation(format!("AI job {} is already running", job_id)));
+    }
+
+    let prompt = request.build_prompt();
+    let request_json = serde_json::to_string(&request)?;
+    let agent_type = request.agent_type();
+    let rag_query = request.rag_query();
+    let now = Local::now().to_rfc3339();
+
+    // 查詢向量需要在連線之外計算 (可能呼叫供應商)
+    let state = app.state::<SqliteState>();
+    let query_embedding = match &rag_query {
+        Some(query) => embeddings::embed_query(&state, query).await,
+        None => None,
+    };
+
+    let (reasoning_id, prompt) = state.run(move |conn| {
+        let prompt = match rag_query {
+            Some(query) => rag::augment_prompt(conn, blog_id, &agent_type, &query, query_embedding.as_ref(), prompt),
+            None => prompt,
+        };
+        conn.execute(
+            "INSERT INTO agent_reasoning (blog_id, agent_type, title, reasoning, output, status, request, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, '', ?5, ?6, ?7, ?7)",
+            params![
+                blog_id,
+                agent_type.to_string(),
+                "AI Generation",
+                prompt,
+                STATUS_RUNNING,
+                request_json,
+                now
+            ],
+        )?;
+        Ok::<_, AppError>((conn.last_insert_rowid(), prompt))
+    }).await?;
+
+    let start = JobStart { job_id, request, prompt, blog_id, reasoning_id, base_output: String::new() };
+    Ok(spawn_job(app.clone(), &registry, start, done))
+}
+
+// 以任務執行 AI 指令並等待結果，讓一般指令也能被 cancel_ai_job 取消、之後以 resume_ai_job 續寫
+// The job id is announced with ai-job-started; streaming commands pass their request_id as the job id
+pub(crate) async fn run_ai_job(app: AppHandle, request: AiJobRequest, blog_id: i64, job_id: Option<String>) -> Result<String, AppError> {
+    let (done, mut finished) = channel(1);
+    let job = create_job(&app, request, blog_id, job_id, Some(done)).await?;
+    emit(&app, AI_JOB_STARTED_EVENT, job.clone());
+    match finished.recv().await {
+        Some(result) => result,
+        // The task was aborted by cancel_ai_job
+        None => Err(AppError::Cancelled(format!("AI job {} was cancelled", job.job_id))),
+    }
+}
+
+// 啟動 AI 生成任務，立即回傳任務資訊；輸出以 ai-stream-* 事件送出
+#[tauri::command]
+pub async fn start_ai_job(request: AiJobRequest, blog_id: i64, app_handle: AppHandle) -> Result<GenerationJob, AppError> {
+    println!("start_ai_job called for blog {}: {:?}", blog_id, request.agent_type());
+    create_job(&app_handle, request, blog_id, None, None).await
+}
+
+// 取消任務並保存已收到的部分輸出
+#[tauri::command]
+pub async fn cancel_ai_job(job_id: String, app_handle: AppHandle, registry: tauri::State<'_, JobRegistry>) -> Result<i64, AppError> {
+    println!("cancel_ai_job called for {}", job_id);
+
+    let entry = registry.take(&job_id)
+        .ok_or_else(|| AppError::NotFound(format!("AI job {} not found or already finished", job_id)))?;
+
+    if let Some(handle) = &entry.handle {
+        handle.abort();
+    }
+
+    let output = entry.partial.lock().unwrap().text.clone();
+    persist_output(&app_handle.state::<SqliteState>(), entry.info.reasoning_id, output.clone(), STATUS_CANCELLED).await;
+
+    #[derive(Clone, Serialize)]
+    struct JobCancelled {
+        request_id: String,
+        reasoning_id: i64,
+        partial_output: String,
+    }
+    emit(&app_handle, AI_JOB_CANCELLED_EVENT, JobCancelled {
+        request_id: job_id.clone(),
+        reasoning_id: entry.info.reasoning_id,
+        partial_output: output,
+    });
+
+    println!("AI job {} cancelled", job_id);
+    Ok(entry.info.reasoning_id)
+}
+
+// 列出執行中的任務
+#[tauri::command]
+pub fn list_ai_jobs(registry: tauri::State<'_, JobRegistry>) -> Result<Vec<GenerationJob>, AppError> {
+    let jobs = registry.0.lock().unwrap();
+    let mut result: Vec<GenerationJob> = jobs.values().map(|entry| {
+        let mut info = entry.info.clone();
+        info.received_chars = entry.partial.lock().unwrap().text.len();
+        info
+    }).collect();
+    result.sort_by(|a, b| a.started_at.cmp(&b.started_at));
+    Ok(result)
+}
+
+// 續寫被取消、失敗或中斷的任務，新的輸出會接在原有部分輸出之後
+#[tauri::command]
+pub async fn resume_ai_job(
+    reasoning_id: i64,
+    app_handle: AppHandle,
+    state: tauri::State<'_, SqliteState>,
+    registry: tauri::State<'_, JobRegistry>
+) -> Result<GenerationJob, AppError> {
+    println!("resume_ai_job called for reasoning {}", reasoning_id);
+
+    let (blog_id, agent_type, prompt, output, status, request) = state.run(move |conn| {
+        conn.query_row(
+            "SELECT blog_id, agent_type, reasoning, output, status, request FROM agent_reasoning WHERE id = ?1",
+            [reasoning_id],
+            |row| Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, Option<String>>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, Option<String>>(5)?,
+            ))
+        ).optional()?
+        .ok_or_else(|| AppError::not_found("Agent reasoning", reasoning_id))
+    }).await?;
+
+    if status == STATUS_RUNNING || status == STATUS_COMPLETED || registry.has_reasoning(reasoning_id) {
+        return Err(AppError::validation(format!("AI job for reasoning {} is {} and cannot be resumed", reasoning_id, status)));
+    }
+
+    // Older rows have no stored request; fall back to a plain generation with the stored prompt
+    let request = match request.as_deref().map(serde_json::from_str::<AiJobRequest>) {
+        Some(Ok(request)) => request,
+        _ => AiJobRequest::Generate {
+            prompt: prompt.clone(),
+            agent_type: AgentType::from_string(&agent_type)?,
+        },
+    };
+
+    let output = output.unwrap_or_default();
+    let continuation_prompt = if output.is_empty() {
+        prompt
+    } else {
+        format!(
+            "{}\n\nYour previous response was interrupted. Here is the text you had produced so far:\n\n{}\n\nContinue exactly where the text stops. Do not repeat any of the text above and do not add any preamble.",
+            prompt, output
+        )
+    };
+
+    // 以狀態判斷搶下這筆紀錄，同時送出的兩次續寫只有一次會成功
+    let stored = output.clone();
+    let claimed = state.run(move |conn| -> Result<usize, AppError> {
+        Ok(conn.execute(
+            "UPDATE agent_reasoning SET output = ?1, status = ?2, updated_at = ?3
+             WHERE id = ?4 AND status NOT IN (?2, ?5)",
+            params![stored, STATUS_RUNNING, Local::now().to_rfc3339(), reasoning_id, STATUS_COMPLETED],
+        )?)
+    }).await?;
+    if claimed == 0 {
+        return Err(AppError::validation(format!("AI job for reasoning {} is already running", reasoning_id)));
+    }
+
+    let start = JobStart { job_id: next_job_id(), request, prompt: continuation_prompt, blog_id, reasoning_id, base_output: output };
+    Ok(spawn_job(app_handle, &registry, start, None))
+}
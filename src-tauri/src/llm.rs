@@ -0,0 +1,1101 @@
+use async_trait::async_trait;
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use chrono::Local;
+
+use crate::SqliteState;
+use crate::ai_agent::{AgentError, AgentType};
+use crate::error::AppError;
+use crate::secrets;
+
+// 預設模型與端點
+const DEFAULT_GEMINI_MODEL: &str = "gemini-2.0-flash";
+const DEFAULT_GEMINI_BASE_URL: &str = "https://generativelanguage.googleapis.com";
+const DEFAULT_OPENAI_MODEL: &str = "gpt-4o-mini";
+const DEFAULT_OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+const DEFAULT_ANTHROPIC_MODEL: &str = "claude-3-5-sonnet-latest";
+const DEFAULT_ANTHROPIC_BASE_URL: &str = "https://api.anthropic.com/v1";
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+const DEFAULT_OLLAMA_MODEL: &str = "llama3";
+const DEFAULT_OLLAMA_BASE_URL: &str = "http://localhost:11434";
+
+// 預設嵌入模型
+const DEFAULT_GEMINI_EMBEDDING_MODEL: &str = "text-embedding-004";
+const DEFAULT_OPENAI_EMBEDDING_MODEL: &str = "text-embedding-3-small";
+const DEFAULT_OLLAMA_EMBEDDING_MODEL: &str = "nomic-embed-text";
+
+const DEFAULT_TEMPERATURE: f64 = 0.7;
+const DEFAULT_MAX_OUTPUT_TOKENS: u32 = 6000;
+
+// Settings key prefix for provider configuration
+const PROVIDER_SETTING_KEY: &str = "llm_provider";
+
+// 支援的 LLM 供應商
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProviderKind {
+    Gemini,
+    OpenAi,
+    Anthropic,
+    Ollama,
+}
+
+impl ProviderKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ProviderKind::Gemini => "Gemini",
+            ProviderKind::OpenAi => "OpenAI",
+            ProviderKind::Anthropic => "Anthropic",
+            ProviderKind::Ollama => "Ollama",
+        }
+    }
+
+    // Settings key holding the API key for this provider
+    pub fn api_key_setting(&self) -> Option<&'static str> {
+        match self {
+            ProviderKind::Gemini => Some("gemini_api_key"),
+            ProviderKind::OpenAi => Some("openai_api_key"),
+            ProviderKind::Anthropic => Some("anthropic_api_key"),
+            ProviderKind::Ollama => None,
+        }
+    }
+}
+
+// 供應商設定，以 JSON 形式儲存在 settings 表中
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProviderConfig {
+    pub kind: ProviderKind,
+    pub model: Option<String>,
+    pub base_url: Option<String>,
+    pub api_key: Option<String>,
+    pub temperature: Option<f64>,
+    pub max_output_tokens: Option<u32>,
+    pub embedding_model: Option<String>,
+}
+
+impl Default for ProviderConfig {
+    fn default() -> Self {
+        ProviderConfig {
+            kind: ProviderKind::Gemini,
+            model: None,
+            base_url: None,
+            api_key: None,
+            temperature: None,
+            max_output_tokens: None,
+            embedding_model: None,
+        }
+    }
+}
+
+// Callback receiving each streamed text fragment
+pub type ChunkCallback<'a> = dyn Fn(&str) + Send + Sync + 'a;
+
+#[async_trait]
+pub trait LlmProvider: Send + Sync {
+    fn kind(&self) -> ProviderKind;
+
+    fn model(&self) -> &str;
+
+    async fn generate(&self, prompt: &str) -> Result<String, AgentError>;
+
+    // 串流生成：每收到一段文字就呼叫 on_chunk，最後回傳完整文字
+    // Providers without a streaming endpoint deliver the whole reply as a single chunk
+    async fn generate_stream(&self, prompt: &str, on_chunk: &ChunkCallback<'_>) -> Result<String, AgentError> {
+        let text = self.generate(prompt).await?;
+        on_chunk(&text);
+        Ok(text)
+    }
+
+    // 嵌入模型名稱；沒有嵌入端點的供應商回傳 None
+    fn embedding_model(&self) -> Option<&str> {
+        None
+    }
+
+    // 為每段文字產生嵌入向量，順序與輸入相同
+    async fn embed(&self, _texts: &[String]) -> Result<Vec<Vec<f32>>, AgentError> {
+        Err(AgentError::ApiError(format!("{} does not provide an embedding endpoint", self.kind().label())))
+    }
+}
+
+// Read a JSON array of numbers as an embedding vector
+fn parse_vector(value: Option<&serde_json::Value>, provider: &str) -> Result<Vec<f32>, AgentError> {
+    value
+        .and_then(|v| v.as_array())
+        .map(|values| values.iter().filter_map(|v| v.as_f64()).map(|v| v as f32).collect::<Vec<f32>>())
+        .filter(|vector| !vector.is_empty())
+        .ok_or_else(|| AgentError::ParseError(format!("No embedding found in {} response", provider)))
+}
+
+// Shared generation parameters resolved from a ProviderConfig
+struct GenerationOptions {
+    temperature: f64,
+    max_output_tokens: u32,
+}
+
+impl GenerationOptions {
+    fn from_config(config: &ProviderConfig) -> Self {
+        GenerationOptions {
+            temperature: config.temperature.unwrap_or(DEFAULT_TEMPERATURE),
+            max_output_tokens: config.max_output_tokens.unwrap_or(DEFAULT_MAX_OUTPUT_TOKENS),
+        }
+    }
+}
+
+// 將失敗的 HTTP 回應分類：限流 / 額度用盡與其他狀態碼分開，讓前端可以決定是否重試
+async fn status_error(response: reqwest::Response, provider: &str) -> AgentError {
+    let status = response.status().as_u16();
+    let retry_after = response.headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok());
+    let error_text = response.text().await.unwrap_or_default();
+    println!("API error response: {}", error_text);
+
+    // An exhausted quota or billing limit is also reported as 429 but will not recover by retrying
+    let lowered = error_text.to_lowercase();
+    let quota_exhausted = lowered.contains("insufficient_quota") || lowered.contains("billing");
+    if status == 429 || quota_exhausted {
+        return AgentError::RateLimited { provider: provider.to_string(), retry_after, quota_exhausted };
+    }
+
+    let message = serde_json::from_str::<serde_json::Value>(&error_text).ok()
+        .and_then(|v| {
+            let error = v.get("error")?;
+            error.get("message").and_then(|m| m.as_str()).or_else(|| error.as_str()).map(|m| m.to_string())
+        })
+        .unwrap_or_else(|| error_text.chars().take(300).collect());
+    AgentError::HttpError { provider: provider.to_string(), status, message }
+}
+
+// Send a JSON request and return the parsed JSON body, mapping HTTP failures to AgentError
+async fn send_json(request: reqwest::RequestBuilder, body: &serde_json::Value, provider: &str) -> Result<serde_json::Value, AgentError> {
+    let response = request
+        .json(body)
+        .send()
+        .await
+        .map_err(|e| {
+            // Strip the URL: Gemini passes the API key as a query parameter
+            let e = e.without_url();
+            println!("Request error: {}", e);
+            AgentError::ApiError(format!("Failed to send request to {} API: {}", provider, e))
+        })?;
+
+    let status = response.status();
+    println!("{} API response status: {}", provider, status);
+
+    if !status.is_success() {
+        return Err(status_error(response, provider).await);
+    }
+
+    response.json::<serde_json::Value>().await.map_err(|e| {
+        println!("JSON parsing error: {}", e);
+        AgentError::ParseError(format!("Failed to parse {} API response: {}", provider, e))
+    })
+}
+
+// Send a streaming request, returning the response once the status has been checked
+async fn send_stream(request: reqwest::RequestBuilder, body: &serde_json::Value, provider: &str) -> Result<reqwest::Response, AgentError> {
+    let response = request
+        .json(body)
+        .send()
+        .await
+        .map_err(|e| {
+            // Strip the URL: Gemini passes the API key as a query parameter
+            let e = e.without_url();
+            println!("Request error: {}", e);
+            AgentError::ApiError(format!("Failed to send request to {} API: {}", provider, e))
+        })?;
+
+    let status = response.status();
+    println!("{} API stream response status: {}", provider, status);
+
+    if !status.is_success() {
+        return Err(status_error(response, provider).await);
+    }
+
+    Ok(response)
+}
+
+// Read a streamed body line by line (SSE or NDJSON), buffering partial lines across chunks
+async fn read_lines<F>(mut response: reqwest::Response, provider: &str, mut on_line: F) -> Result<(), AgentError>
+where
+    F: FnMut(&str) -> Result<bool, AgentError> + Send,
+{
+    let mut buffer: Vec<u8> = Vec::new();
+
+    loop {
+        let chunk = response.chunk().await.map_err(|e| {
+            println!("Stream error: {}", e);
+            AgentError::ApiError(format!("Failed to read {} API stream: {}", provider, e))
+        })?;
+
+        let Some(chunk) = chunk else { break };
+        buffer.extend_from_slice(&chunk);
+
+        while let Some(pos) = buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\r', '\n']);
+            if line.is_empty() {
+                continue;
+            }
+            // on_line returns false once the stream signals completion
+            if !on_line(line)? {
+                return Ok(());
+            }
+        }
+    }
+
+    // Flush a trailing line without a newline
+    let rest = String::from_utf8_lossy(&buffer);
+    let rest = rest.trim();
+    if !rest.is_empty() {
+        on_line(rest)?;
+    }
+
+    Ok(())
+}
+
+// Extract the payload of an SSE `data:` line
+fn sse_data(line: &str) -> Option<&str> {
+    line.strip_prefix("data:").map(|data| data.trim())
+}
+
+fn parse_stream_json(data: &str, provider: &str) -> Result<serde_json::Value, AgentError> {
+    serde_json::from_str(data).map_err(|e| {
+        println!("Failed to parse {} stream event: {}", provider, data);
+        AgentError::ParseError(format!("Failed to parse {} stream event: {}", provider, e))
+    })
+}
+
+// Gemini generateContent API
+pub struct GeminiProvider {
+    api_key: String,
+    model: String,
+    embedding_model: String,
+    base_url: String,
+    options: GenerationOptions,
+}
+
+#[async_trait]
+impl LlmProvider for GeminiProvider {
+    fn kind(&self) -> ProviderKind {
+        ProviderKind::Gemini
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn generate(&self, prompt: &str) -> Result<String, AgentError> {
+        let url = format!("{}/v1beta/models/{}:generateContent", self.base_url, self.model);
+        println!("Using Gemini API URL: {}", url);
+
+        let request_body = self.request_body(prompt);
+
+        let client = reqwest::Client::new();
+        let request = client.post(url).query(&[("key", self.api_key.as_str())]);
+        let response_json = send_json(request, &request_body, "Gemini").await?;
+
+        response_json
+            .pointer("/candidates/0/content/parts/0/text")
+            .and_then(|text| text.as_str())
+            .map(|text| text.to_string())
+            .ok_or_else(|| {
+                println!("No text found in Gemini response: {:?}", response_json);
+                AgentError::ParseError("No text found in Gemini response".to_string())
+            })
+    }
+
+    async fn generate_stream(&self, prompt: &str, on_chunk: &ChunkCallback<'_>) -> Result<String, AgentError> {
+        let url = format!("{}/v1beta/models/{}:streamGenerateContent", self.base_url, self.model);
+        println!("Using Gemini streaming API URL: {}", url);
+
+        let request_body = self.request_body(prompt);
+
+        let client = reqwest::Client::new();
+        let request = client.post(url).query(&[("alt", "sse"), ("key", self.api_key.as_str())]);
+        let response = send_stream(request, &request_body, "Gemini").await?;
+
+        let mut full_text = String::new();
+        read_lines(response, "Gemini", |line| {
+            let Some(data) = sse_data(line) else { return Ok(true) };
+            let event = parse_stream_json(data, "Gemini")?;
+            if let Some(parts) = event.pointer("/candidates/0/content/parts").and_then(|p| p.as_array()) {
+                for text in parts.iter().filter_map(|part| part.get("text").and_then(|t| t.as_str())) {
+                    full_text.push_str(text);
+                    on_chunk(text);
+                }
+            }
+            Ok(true)
+        }).await?;
+
+        Ok(full_text)
+    }
+
+    fn embedding_model(&self) -> Option<&str> {
+        Some(&self.embedding_model)
+    }
+
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, AgentError> {
+        let url = format!("{}/v1beta/models/{}:batchEmbedContents", self.base_url, self.embedding_model);
+        println!("Using Gemini embedding API URL: {}", url);
+
+        let requests: Vec<serde_json::Value> = texts.iter().map(|text| json!({
+            "model": format!("models/{}", self.embedding_model),
+            "content": { "parts": [{ "text": text }] }
+        })).collect();
+        let request_body = json!({ "requests": requests });
+
+        let client = reqwest::Client::new();
+        let request = client.post(url).query(&[("key", self.api_key.as_str())]);
+        let response_json = send_json(request, &request_body, "Gemini").await?;
+
+        let embeddings = response_json.get("embeddings").and_then(|e| e.as_array()).cloned().unwrap_or_default();
+        embeddings.iter()
+            .map(|embedding| parse_vector(embedding.get("values"), "Gemini"))
+            .collect()
+    }
+}
+
+impl GeminiProvider {
+    fn request_body(&self, prompt: &str) -> serde_json::Value {
+        json!({
+            "contents": [
+                {
+                    "parts": [
+                        {
+                            "text": prompt
+                        }
+                    ]
+                }
+            ],
+            "generationConfig": {
+                "temperature": self.options.temperature,
+                "topK": 40,
+                "topP": 0.95,
+                "maxOutputTokens": self.options.max_output_tokens,
+            }
+        })
+    }
+}
+
+// OpenAI-compatible chat completions API (OpenAI, LM Studio, vLLM, ...)
+pub struct OpenAiProvider {
+    api_key: Option<String>,
+    model: String,
+    embedding_model: String,
+    base_url: String,
+    options: GenerationOptions,
+}
+
+#[async_trait]
+impl LlmProvider for OpenAiProvider {
+    fn kind(&self) -> ProviderKind {
+        ProviderKind::OpenAi
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn generate(&self, prompt: &str) -> Result<String, AgentError> {
+        let url = format!("{}/chat/completions", self.base_url);
+        println!("Using OpenAI-compatible API URL: {}", url);
+
+        let request_body = self.request_body(prompt, false);
+        let response_json = send_json(self.request(url), &request_body, "OpenAI").await?;
+
+        response_json
+            .pointer("/choices/0/message/content")
+            .and_then(|text| text.as_str())
+            .map(|text| text.to_string())
+            .ok_or_else(|| {
+                println!("No message content found in OpenAI response: {:?}", response_json);
+                AgentError::ParseError("No message content found in OpenAI response".to_string())
+            })
+    }
+
+    async fn generate_stream(&self, prompt: &str, on_chunk: &ChunkCallback<'_>) -> Result<String, AgentError> {
+        let url = format!("{}/chat/completions", self.base_url);
+        println!("Using OpenAI-compatible streaming API URL: {}", url);
+
+        let request_body = self.request_body(prompt, true);
+        let response = send_stream(self.request(url), &request_body, "OpenAI").await?;
+
+        let mut full_text = String::new();
+        read_lines(response, "OpenAI", |line| {
+            let Some(data) = sse_data(line) else { return Ok(true) };
+            if data == "[DONE]" {
+                return Ok(false);
+            }
+            let event = parse_stream_json(data, "OpenAI")?;
+            if let Some(text) = event.pointer("/choices/0/delta/content").and_then(|t| t.as_str()) {
+                full_text.push_str(text);
+                on_chunk(text);
+            }
+            Ok(true)
+        }).await?;
+
+        Ok(full_text)
+    }
+
+    fn embedding_model(&self) -> Option<&str> {
+        Some(&self.embedding_model)
+    }
+
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, AgentError> {
+        let url = format!("{}/embeddings", self.base_url);
+        println!("Using OpenAI-compatible embedding API URL: {}", url);
+
+        let request_body = json!({
+            "model": self.embedding_model,
+            "input": texts
+        });
+        let response_json = send_json(self.request(url), &request_body, "OpenAI").await?;
+
+        let data = response_json.get("data").and_then(|d| d.as_array()).cloned().unwrap_or_default();
+        data.iter()
+            .map(|item| parse_vector(item.get("embedding"), "OpenAI"))
+            .collect()
+    }
+}
+
+impl OpenAiProvider {
+    fn request(&self, url: String) -> reqwest::RequestBuilder {
+        let client = reqwest::Client::new();
+        let request = client.post(url);
+        match &self.api_key {
+            Some(api_key) => request.bearer_auth(api_key),
+            None => request,
+        }
+    }
+
+    fn request_body(&self, prompt: &str, stream: bool) -> serde_json::Value {
+        json!({
+            "model": self.model,
+            "messages": [
+                {
+                    "role": "user",
+                    "content": prompt
+                }
+            ],
+            "temperature": self.options.temperature,
+            "max_tokens": self.options.max_output_tokens,
+            "stream": stream,
+        })
+    }
+}
+
+// Anthropic-style messages API
+pub struct AnthropicProvider {
+    api_key: String,
+    model: String,
+    base_url: String,
+    options: GenerationOptions,
+}
+
+#[async_trait]
+impl LlmProvider for AnthropicProvider {
+    fn kind(&self) -> ProviderKind {
+        ProviderKind::Anthropic
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn generate(&self, prompt: &str) -> Result<String, AgentError> {
+        let url = format!("{}/messages", self.base_url);
+        println!("Using Anthropic API URL: {}", url);
+
+        let request_body = self.request_body(prompt, false);
+        let response_json = send_json(self.request(url), &request_body, "Anthropic").await?;
+
+        // Concatenate all text blocks of the reply
+        let blocks = response_json.get("content").and_then(|c| c.as_array()).ok_or_else(|| {
+            println!("No content found in Anthropic response: {:?}", response_json);
+            AgentError::ParseError("No content found in Anthropic response".to_string())
+        })?;
+
+        let text: String = blocks.iter()
+            .filter(|block| block.get("type").and_then(|t| t.as_str()) == Some("text"))
+            .filter_map(|block| block.get("text").and_then(|t| t.as_str()))
+            .collect();
+
+        if text.is_empty() {
+            return Err(AgentError::ParseError("No text found in Anthropic response".to_string()));
+        }
+        Ok(text)
+    }
+
+    async fn generate_stream(&self, prompt: &str, on_chunk: &ChunkCallback<'_>) -> Result<String, AgentError> {
+        let url = format!("{}/messages", self.base_url);
+        println!("Using Anthropic streaming API URL: {}", url);
+
+        let request_body = self.request_body(prompt, true);
+        let response = send_stream(self.request(url), &request_body, "Anthropic").await?;
+
+        let mut full_text = String::new();
+        read_lines(response, "Anthropic", |line| {
+            let Some(data) = sse_data(line) else { return Ok(true) };
+            let event = parse_stream_json(data, "Anthropic")?;
+            match event.get("type").and_then(|t| t.as_str()) {
+                Some("content_block_delta") => {
+                    if let Some(text) = event.pointer("/delta/text").and_then(|t| t.as_str()) {
+                        full_text.push_str(text);
+                        on_chunk(text);
+                    }
+                    Ok(true)
+                },
+                Some("message_stop") => Ok(false),
+                Some("error") => {
+                    let message = event.pointer("/error/message").and_then(|m| m.as_str()).unwrap_or("unknown error");
+                    Err(AgentError::ApiError(format!("Anthropic stream error: {}", message)))
+                },
+                _ => Ok(true),
+            }
+        }).await?;
+
+        Ok(full_text)
+    }
+}
+
+impl AnthropicProvider {
+    fn request(&self, url: String) -> reqwest::RequestBuilder {
+        let client = reqwest::Client::new();
+        client.post(url)
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+    }
+
+    fn request_body(&self, prompt: &str, stream: bool) -> serde_json::Value {
+        json!({
+            "model": self.model,
+            "max_tokens": self.options.max_output_tokens,
+            "temperature": self.options.temperature,
+            "messages": [
+                {
+                    "role": "user",
+                    "content": prompt
+                }
+            ],
+            "stream": stream,
+        })
+    }
+}
+
+// Ollama / local model server
+pub struct OllamaProvider {
+    model: String,
+    embedding_model: String,
+    base_url: String,
+    options: GenerationOptions,
+}
+
+#[async_trait]
+impl LlmProvider for OllamaProvider {
+    fn kind(&self) -> ProviderKind {
+        ProviderKind::Ollama
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn generate(&self, prompt: &str) -> Result<String, AgentError> {
+        let url = format!("{}/api/generate", self.base_url);
+        println!("Using Ollama API URL: {}", url);
+
+        let request_body = self.request_body(prompt, false);
+
+        let client = reqwest::Client::new();
+        let response_json = send_json(client.post(url), &request_body, "Ollama").await?;
+
+        response_json
+            .get("response")
+            .and_then(|text| text.as_str())
+            .map(|text| text.to_string())
+            .ok_or_else(|| {
+                println!("No response text found in Ollama response: {:?}", response_json);
+                AgentError::ParseError("No response text found in Ollama response".to_string())
+            })
+    }
+
+    async fn generate_stream(&self, prompt: &str, on_chunk: &ChunkCallback<'_>) -> Result<String, AgentError> {
+        let url = format!("{}/api/generate", self.base_url);
+        println!("Using Ollama streaming API URL: {}", url);
+
+        let request_body = self.request_body(prompt, true);
+
+        let client = reqwest::Client::new();
+        let response = send_stream(client.post(url), &request_body, "Ollama").await?;
+
+        // Ollama streams newline-delimited JSON objects rather than SSE
+        let mut full_text = String::new();
+        read_lines(response, "Ollama", |line| {
+            let event = parse_stream_json(line, "Ollama")?;
+            if let Some(error) = event.get("error").and_then(|e| e.as_str()) {
+                return Err(AgentError::ApiError(format!("Ollama stream error: {}", error)));
+            }
+            if let Some(text) = event.get("response").and_then(|t| t.as_str()) {
+                full_text.push_str(text);
+                on_chunk(text);
+            }
+            Ok(!event.get("done").and_then(|d| d.as_bool()).unwrap_or(false))
+        }).await?;
+
+        Ok(full_text)
+    }
+
+    fn embedding_model(&self) -> Option<&str> {
+        Some(&self.embedding_model)
+    }
+
+    async fn embed(&self, texts: &[String]) -> Result<Vec<Vec<f32>>, AgentError> {
+        let url = format!("{}/api/embed", self.base_url);
+        println!("Using Ollama embedding API URL: {}", url);
+
+        let request_body = json!({
+            "model": self.embedding_model,
+            "input": texts
+        });
+
+        let client = reqwest::Client::new();
+        let response_json = send_json(client.post(url), &request_body, "Ollama").await?;
+
+        let embeddings = response_json.get("embeddings").and_then(|e| e.as_array()).cloned().unwrap_or_default();
+        embeddings.iter()
+            .map(|embedding| parse_vector(Some(embedding), "Ollama"))
+            .collect()
+    }
+}
+
+impl OllamaProvider {
+    fn request_body(&self, prompt: &str, stream: bool) -> serde_json::Value {
+        json!({
+            "model": self.model,
+            "prompt": prompt,
+            "stream": stream,
+            "options": {
+                "temperature": self.options.temperature,
+                "num_predict": self.options.max_output_tokens,
+            }
+        })
+    }
+}
+
+fn trim_base_url(base_url: &Option<String>, default: &str) -> String {
+    base_url.as_deref()
+        .filter(|url| !url.trim().is_empty())
+        .unwrap_or(default)
+        .trim_end_matches('/')
+        .to_string()
+}
+
+// 根據設定建立供應商實例
+pub fn build_provider(config: &ProviderConfig, api_key: Option<String>) -> Result<Box<dyn LlmProvider>, AgentError> {
+    let options = GenerationOptions::from_config(config);
+    let model = config.model.clone().filter(|m| !m.trim().is_empty());
+    let embedding_model = config.embedding_model.clone().filter(|m| !m.trim().is_empty());
+    let api_key = api_key.filter(|k| !k.is_empty());
+
+    let provider: Box<dyn LlmProvider> = match config.kind {
+        ProviderKind::Gemini => Box::new(GeminiProvider {
+            api_key: api_key.ok_or_else(|| AgentError::ApiKeyNotSet(config.kind.label().to_string()))?,
+            model: model.unwrap_or_else(|| DEFAULT_GEMINI_MODEL.to_string()),
+            embedding_model: embedding_model.unwrap_or_else(|| DEFAULT_GEMINI_EMBEDDING_MODEL.to_string()),
+            base_url: trim_base_url(&config.base_url, DEFAULT_GEMINI_BASE_URL),
+            options,
+        }),
+        ProviderKind::OpenAi => {
+            // A key is only required for the hosted OpenAI endpoint; local compatible servers may not need one
+            let base_url = trim_base_url(&config.base_url, DEFAULT_OPENAI_BASE_URL);
+            if api_key.is_none() && base_url == DEFAULT_OPENAI_BASE_URL {
+                return Err(AgentError::ApiKeyNotSet(config.kind.label().to_string()));
+            }
+            Box::new(OpenAiProvider {
+                api_key,
+                model: model.unwrap_or_else(|| DEFAULT_OPENAI_MODEL.to_string()),
+                embedding_model: embedding_model.unwrap_or_else(|| DEFAULT_OPENAI_EMBEDDING_MODEL.to_string()),
+                base_url,
+                options,
+            })
+        },
+        ProviderKind::Anthropic => Box::new(AnthropicProvider {
+            api_key: api_key.ok_or_else(|| AgentError::ApiKeyNotSet(config.kind.label().to_string()))?,
+            model: model.unwrap_or_else(|| DEFAULT_ANTHROPIC_MODEL.to_string()),
+            base_url: trim_base_url(&config.base_url, DEFAULT_ANTHROPIC_BASE_URL),
+            options,
+        }),
+        ProviderKind::Ollama => Box::new(OllamaProvider {
+            model: model.unwrap_or_else(|| DEFAULT_OLLAMA_MODEL.to_string()),
+            embedding_model: embedding_model.unwrap_or_else(|| DEFAULT_OLLAMA_EMBEDDING_MODEL.to_string()),
+            base_url: trim_base_url(&config.base_url, DEFAULT_OLLAMA_BASE_URL),
+            options,
+        }),
+    };
+
+    Ok(provider)
+}
+
+// settings 表中的供應商設定鍵
+// project + agent > project > agent > default
+fn provider_setting_key(agent_type: Option<&AgentType>, project_id: Option<i64>) -> String {
+    match (project_id, agent_type) {
+        (Some(project_id), Some(agent_type)) => format!("{}.project.{}.{}", PROVIDER_SETTING_KEY, project_id, agent_type.to_string()),
+        (Some(project_id), None) => format!("{}.project.{}", PROVIDER_SETTING_KEY, project_id),
+        (None, Some(agent_type)) => format!("{}.agent.{}", PROVIDER_SETTING_KEY, agent_type.to_string()),
+        (None, None) => PROVIDER_SETTING_KEY.to_string(),
+    }
+}
+
+// 專案層級的設定鍵前綴，刪除專案時連同各代理的設定一起移除
+pub(crate) fn project_setting_key(project_id: i64) -> String {
+    provider_setting_key(None, Some(project_id))
+}
+
+// 所有專案層級設定鍵的共同前綴，後接專案 ID
+pub(crate) fn project_settings_prefix() -> String {
+    format!("{}.project.", PROVIDER_SETTING_KEY)
+}
+
+fn read_setting(conn: &Connection, key: &str) -> Result<Option<String>, AgentError> {
+    conn.query_row(
+        "SELECT value FROM settings WHERE key = ?1",
+        [key],
+        |row| row.get::<_, String>(0)
+    ).optional().map_err(AgentError::DatabaseError)
+}
+
+fn read_provider_config(conn: &Connection, key: &str) -> Result<Option<ProviderConfig>, AgentError> {
+    match read_setting(conn, key)? {
+        Some(value) => serde_json::from_str(&value)
+            .map(Some)
+            .map_err(|e| AgentError::ParseError(format!("Invalid provider configuration in {}: {}", key, e))),
+        None => Ok(None),
+    }
+}
+
+// 讀取供應商設定，並帶入另外加密保存的 API 金鑰
+fn load_provider_config(conn: &Connection, key: &str) -> Result<Option<ProviderConfig>, AgentError> {
+    let mut config = match read_provider_config(conn, key)? {
+        Some(config) => config,
+        None => return Ok(None),
+    };
+    if config.api_key.is_none() {
+        config.api_key = secrets::get_secret(conn, &secrets::provider_secret_name(key))
+            .map_err(|e| AgentError::SecretError(e.to_string()))?;
+    }
+    Ok(Some(config))
+}
+
+// 依序尋找最具體的設定，沒有設定時使用 Gemini 預設值
+pub fn resolve_provider_config(conn: &Connection, agent_type: &AgentType, project_id: Option<i64>) -> Result<ProviderConfig, AgentError> {
+    let mut candidates = Vec::new();
+    if let Some(project_id) = project_id {
+        candidates.push(provider_setting_key(Some(agent_type), Some(project_id)));
+        candidates.push(provider_setting_key(None, Some(project_id)));
+    }
+    candidates.push(provider_setting_key(Some(agent_type), None));
+    candidates.push(provider_setting_key(None, None));
+
+    for key in candidates {
+        if let Some(config) = load_provider_config(conn, &key)? {
+            println!("Using LLM provider {} from setting {}", config.kind.label(), key);
+            return Ok(config);
+        }
+    }
+
+    Ok(ProviderConfig::default())
+}
+
+// 解析 API 金鑰：設定內的金鑰優先，否則讀取供應商對應的加密金鑰
+fn resolve_api_key(conn: &Connection, config: &ProviderConfig) -> Result<Option<String>, AgentError> {
+    if let Some(key) = config.api_key.as_ref().filter(|k| !k.is_empty()) {
+        return Ok(Some(key.clone()));
+    }
+    match config.kind.api_key_setting() {
+        Some(name) => secrets::get_secret(conn, name).map_err(|e| AgentError::SecretError(e.to_string())),
+        None => Ok(None),
+    }
+}
+
+// 取得部落格所屬專案 ID
+fn project_id_for_blog(conn: &Connection, blog_id: i64) -> Result<Option<i64>, AgentError> {
+    conn.query_row(
+        "SELECT project_id FROM blogs WHERE id = ?1",
+        [blog_id],
+        |row| row.get::<_, i64>(0)
+    ).optional().map_err(AgentError::DatabaseError)
+}
+
+// 使用全域設定建立供應商 (不屬於特定代理的工作，例如嵌入)
+pub fn default_provider(conn: &Connection) -> Result<Box<dyn LlmProvider>, AgentError> {
+    let config = load_provider_config(conn, &provider_setting_key(None, None))?.unwrap_or_default();
+    let api_key = resolve_api_key(conn, &config)?;
+    build_provider(&config, api_key)
+}
+
+// 為指定的代理與部落格建立供應商
+pub fn provider_for(conn: &Connection, agent_type: &AgentType, blog_id: i64) -> Result<Box<dyn LlmProvider>, AgentError> {
+    let project_id = project_id_for_blog(conn, blog_id)?;
+    let config = resolve_provider_config(conn, agent_type, project_id)?;
+    let api_key = resolve_api_key(conn, &config)?;
+    build_provider(&config, api_key)
+}
+
+// 獲取供應商設定
+#[tauri::command]
+pub async fn get_llm_provider_config(agent_type: Option<AgentType>, project_id: Option<i64>, state: tauri::State<'_, SqliteState>) -> Result<Option<ProviderConfig>, AppError> {
+    state.run(move |conn| {
+        let key = provider_setting_key(agent_type.as_ref(), project_id);
+        // The API key is stored encrypted and never sent back to the UI
+        let config = read_provider_config(conn, &key)?;
+        Ok(config.map(|config| ProviderConfig { api_key: None, ..config }))
+    }).await
+}
+
+// 保存供應商設定
+#[tauri::command]
+pub async fn set_llm_provider_config(config: ProviderConfig, agent_type: Option<AgentType>, project_id: Option<i64>, state: tauri::State<'_, SqliteState>) -> Result<bool, AppError> {
+    state.run(move |conn| {
+        let key = provider_setting_key(agent_type.as_ref(), project_id);
+
+        // API 金鑰另外加密保存；未提供時保留原本的金鑰 (用 delete_secret_value 清除)
+        let mut config = config;
+        if let Some(api_key) = config.api_key.take().filter(|k| !k.is_empty()) {
+            secrets::set_secret(conn, &secrets::provider_secret_name(&key), &api_key)?;
+        }
+        let value = serde_json::to_string(&config)?;
+        let now = Local::now().to_rfc3339();
+
+        conn.execute(
+            "INSERT INTO settings (key, value, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?3)
+             ON CONFLICT(key) DO UPDATE SET value = ?2, updated_at = ?3",
+            params![key, value, now],
+        )?;
+
+        println!("Saved LLM provider {} for {}", config.kind.label(), key);
+        Ok(true)
+    }).await
+}
+
+// 刪除供應商設定，回到較通用的設定
+#[tauri::command]
+pub async fn delete_llm_provider_config(agent_type: Option<AgentType>, project_id: Option<i64>, state: tauri::State<'_, SqliteState>) -> Result<bool, AppError> {
+    state.run(move |conn| {
+        let key = provider_setting_key(agent_type.as_ref(), project_id);
+        secrets::delete_secret(conn, &secrets::provider_secret_name(&key))?;
+        conn.execute("DELETE FROM settings WHERE key = ?1", [key])?;
+        Ok(true)
+    }).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{MockResponse, MockServer};
+    use std::sync::Mutex;
+
+    fn provider(kind: ProviderKind, server: &MockServer) -> Box<dyn LlmProvider> {
+        let config = ProviderConfig { kind, base_url: Some(format!("{}/", server.url)), ..Default::default() };
+        build_provider(&config, Some("test-key".to_string())).unwrap()
+    }
+
+    // 串流測試：收集每段文字並回傳完整結果
+    async fn stream(provider: &dyn LlmProvider) -> (Result<String, AgentError>, Vec<String>) {
+        let chunks = Mutex::new(Vec::new());
+        let result = provider.generate_stream("Hi", &|text: &str| chunks.lock().unwrap().push(text.to_string())).await;
+        (result, chunks.into_inner().unwrap())
+    }
+
+    #[tokio::test]
+    async fn gemini_generate_reads_first_candidate() {
+        let server = MockServer::start(vec![MockResponse::json(200, json!({
+            "candidates": [{ "content": { "parts": [{ "text": "Hello from Gemini" }] } }]
+        }))]);
+        let text = provider(ProviderKind::Gemini, &server).generate("Hi").await.unwrap();
+        assert_eq!(text, "Hello from Gemini");
+
+        let request = &server.requests()[0];
+        assert_eq!(request.path, format!("/v1beta/models/{}:generateContent?key=test-key", DEFAULT_GEMINI_MODEL));
+        assert_eq!(request.json().pointer("/contents/0/parts/0/text"), Some(&json!("Hi")));
+    }
+
+    #[tokio::test]
+    async fn gemini_stream_parses_sse_split_across_chunks() {
+        let server = MockServer::start(vec![MockResponse::stream("text/event-stream", &[
+            "data: {\"candidates\":[{\"content\":{\"parts\":[{\"text\":\"Hel\"}]}}]}\r\n\r\ndata: {\"candid",
+            "ates\":[{\"content\":{\"parts\":[{\"text\":\"lo\"}]}}]}\r\n\r\n",
+        ])]);
+        let (result, chunks) = stream(provider(ProviderKind::Gemini, &server).as_ref()).await;
+        assert_eq!(result.unwrap(), "Hello");
+        assert_eq!(chunks, vec!["Hel", "lo"]);
+        assert!(server.requests()[0].path.contains(":streamGenerateContent?alt=sse&key=test-key"));
+    }
+
+    #[tokio::test]
+    async fn openai_generate_sends_bearer_key() {
+        let server = MockServer::start(vec![MockResponse::json(200, json!({
+            "choices": [{ "message": { "role": "assistant", "content": "Hello from OpenAI" } }]
+        }))]);
+        let text = provider(ProviderKind::OpenAi, &server).generate("Hi").await.unwrap();
+        assert_eq!(text, "Hello from OpenAI");
+
+        let request = &server.requests()[0];
+        assert_eq!((request.method.as_str(), request.path.as_str()), ("POST", "/chat/completions"));
+        assert_eq!(request.header("authorization"), Some("Bearer test-key"));
+        assert_eq!(request.json()["stream"], json!(false));
+    }
+
+    #[tokio::test]
+    async fn openai_stream_stops_at_done() {
+        let server = MockServer::start(vec![MockResponse::stream("text/event-stream", &[
+            "data: {\"choices\":[{\"delta\":{\"role\":\"assistant\"}}]}\n\n",
+            "data: {\"choices\":[{\"delta\":{\"content\":\"Hi \"}}]}\n\ndata: {\"choices\":[{\"delta\":{\"content\":\"there\"}}]}\n\n",
+            "data: [DONE]\n\ndata: {\"choices\":[{\"delta\":{\"content\":\"ignored\"}}]}\n\n",
+        ])]);
+        let (result, chunks) = stream(provider(ProviderKind::OpenAi, &server).as_ref()).await;
+        assert_eq!(result.unwrap(), "Hi there");
+        assert_eq!(chunks, vec!["Hi ", "there"]);
+        assert_eq!(server.requests()[0].json()["stream"], json!(true));
+    }
+
+    #[tokio::test]
+    async fn openai_embed_keeps_input_order() {
+        let server = MockServer::start(vec![MockResponse::json(200, json!({
+            "data": [{ "embedding": [0.1, 0.2] }, { "embedding": [0.3, 0.4] }]
+        }))]);
+        let vectors = provider(ProviderKind::OpenAi, &server).embed(&["a".to_string(), "b".to_string()]).await.unwrap();
+        assert_eq!(vectors, vec![vec![0.1, 0.2], vec![0.3, 0.4]]);
+        assert_eq!(server.requests()[0].path, "/embeddings");
+    }
+
+    #[tokio::test]
+    async fn anthropic_generate_joins_text_blocks() {
+        let server = MockServer::start(vec![MockResponse::json(200, json!({
+            "content": [{ "type": "text", "text": "Hello " }, { "type": "tool_use", "id": "x" }, { "type": "text", "text": "Claude" }]
+        }))]);
+        let text = provider(ProviderKind::Anthropic, &server).generate("Hi").await.unwrap();
+        assert_eq!(text, "Hello Claude");
+
+        let request = &server.requests()[0];
+        assert_eq!(request.path, "/messages");
+        assert_eq!(request.header("x-api-key"), Some("test-key"));
+        assert_eq!(request.header("anthropic-version"), Some(ANTHROPIC_VERSION));
+    }
+
+    #[tokio::test]
+    async fn anthropic_stream_reads_deltas_until_message_stop() {
+        let server = MockServer::start(vec![MockResponse::stream("text/event-stream", &[
+            "event: message_start\ndata: {\"type\":\"message_start\"}\n\n",
+            "event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"delta\":{\"type\":\"text_delta\",\"text\":\"Hel\"}}\n\n",
+            "event: content_block_delta\ndata: {\"type\":\"content_block_delta\",\"delta\":{\"type\":\"text_delta\",\"text\":\"lo\"}}\n\n",
+            "event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n",
+        ])]);
+        let (result, chunks) = stream(provider(ProviderKind::Anthropic, &server).as_ref()).await;
+        assert_eq!(result.unwrap(), "Hello");
+        assert_eq!(chunks, vec!["Hel", "lo"]);
+    }
+
+    #[tokio::test]
+    async fn anthropic_stream_error_event_fails() {
+        let server = MockServer::start(vec![MockResponse::stream("text/event-stream", &[
+            "event: error\ndata: {\"type\":\"error\",\"error\":{\"type\":\"overloaded_error\",\"message\":\"Overloaded\"}}\n\n",
+        ])]);
+        let (result, _) = stream(provider(ProviderKind::Anthropic, &server).as_ref()).await;
+        match result {
+            Err(AgentError::ApiError(message)) => assert!(message.contains("Overloaded")),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn ollama_generate_reads_response() {
+        let server = MockServer::start(vec![MockResponse::json(200, json!({ "response": "Hello from Ollama", "done": true }))]);
+        let text = provider(ProviderKind::Ollama, &server).generate("Hi").await.unwrap();
+        assert_eq!(text, "Hello from Ollama");
+        assert_eq!(server.requests()[0].path, "/api/generate");
+    }
+
+    #[tokio::test]
+    async fn ollama_stream_parses_ndjson_until_done() {
+        let server = MockServer::start(vec![MockResponse::stream("application/x-ndjson", &[
+            "{\"response\":\"Hel\",\"done\":false}\n{\"respo",
+            "nse\":\"lo\",\"done\":false}\n{\"response\":\"\",\"done\":true}",
+        ])]);
+        let (result, chunks) = stream(provider(ProviderKind::Ollama, &server).as_ref()).await;
+        assert_eq!(result.unwrap(), "Hello");
+        assert_eq!(chunks, vec!["Hel", "lo", ""]);
+    }
+
+    #[tokio::test]
+    async fn ollama_stream_error_line_fails() {
+        let server = MockServer::start(vec![MockResponse::stream("application/x-ndjson", &["{\"error\":\"model not found\"}\n"])]);
+        let (result, _) = stream(provider(ProviderKind::Ollama, &server).as_ref()).await;
+        match result {
+            Err(AgentError::ApiError(message)) => assert!(message.contains("model not found")),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn rate_limit_keeps_retry_after() {
+        let server = MockServer::start(vec![
+            MockResponse::json(429, json!({ "error": { "message": "Too many requests" } })).header("Retry-After", "7"),
+        ]);
+        match provider(ProviderKind::OpenAi, &server).generate("Hi").await {
+            Err(AgentError::RateLimited { provider, retry_after, quota_exhausted }) => {
+                assert_eq!(provider, "OpenAI");
+                assert_eq!(retry_after, Some(7));
+                assert!(!quota_exhausted);
+            },
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn exhausted_quota_is_not_retryable() {
+        let server = MockServer::start(vec![
+            MockResponse::json(429, json!({ "error": { "type": "insufficient_quota", "message": "You exceeded your current quota" } })),
+        ]);
+        let (result, _) = stream(provider(ProviderKind::OpenAi, &server).as_ref()).await;
+        match result {
+            Err(AgentError::RateLimited { quota_exhausted, .. }) => assert!(quota_exhausted),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn http_error_uses_error_message() {
+        let server = MockServer::start(vec![
+            MockResponse::json(400, json!({ "error": { "code": 400, "message": "API key not valid" } })),
+            MockResponse::text(502, "text/plain", "Bad gateway"),
+        ]);
+        let gemini = provider(ProviderKind::Gemini, &server);
+        match gemini.generate("Hi").await {
+            Err(AgentError::HttpError { provider, status, message }) => {
+                assert_eq!((provider.as_str(), status, message.as_str()), ("Gemini", 400, "API key not valid"));
+            },
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match gemini.generate("Hi").await {
+            Err(AgentError::HttpError { status, message, .. }) => assert_eq!((status, message.as_str()), (502, "Bad gateway")),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn hosted_providers_require_a_key() {
+        for kind in [ProviderKind::Gemini, ProviderKind::OpenAi, ProviderKind::Anthropic] {
+            let config = ProviderConfig { kind, ..Default::default() };
+            assert!(matches!(build_provider(&config, None), Err(AgentError::ApiKeyNotSet(_))));
+        }
+        let local = ProviderConfig { kind: ProviderKind::OpenAi, base_url: Some("http://localhost:1234/v1".to_string()), ..Default::default() };
+        assert!(build_provider(&local, None).is_ok());
+        assert!(build_provider(&ProviderConfig { kind: ProviderKind::Ollama, ..Default::default() }, None).is_ok());
+    }
+}
@@ -3,83 +3,143 @@
 
 mod db;
 mod ai_agent;
+mod llm;
+mod jobs;
+mod rag;
+mod embeddings;
+mod search;
+mod diff;
+mod revisions;
+mod suggestions;
+mod migrations;
+mod db_pool;
+mod error;
+mod secrets;
+mod encryption;
+mod import;
+mod backup;
+mod markdown_export;
+mod markdown_import;
+mod document_import;
+mod export_common;
+mod epub_export;
+mod docx_export;
+mod pdf_export;
+mod static_site;
+mod wordpress;
+mod publishing;
+#[cfg(test)]
+mod test_support;
 
-use rusqlite::{Connection, Result};
+use rusqlite::Result;
 use std::sync::Mutex;
 use tauri::Manager;
+use error::AppError;
 
 // 定義資料庫名稱常量
 pub const DB_NAME: &str = "stingtao.db";
 
-pub struct SqliteState(pub Mutex<Connection>);
+// 所有指令共用的資料庫連線池
+pub type SqliteState = db_pool::DbPool;
 
 #[tauri::command]
-async fn init_db(state: tauri::State<'_, SqliteState>) -> Result<(), String> {
-    let conn = state.0.lock().unwrap();
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS settings (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            key TEXT UNIQUE NOT NULL,
-            value TEXT NOT NULL,
-            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
-        )",
-        [],
-    ).map_err(|e| e.to_string())?;
+async fn init_db(state: tauri::State<'_, SqliteState>) -> Result<(), AppError> {
+    // Tables are created by migrations at startup; report whether they completed
+    state.run(|conn| migrations::check_schema(conn)).await?;
     println!("Database initialized successfully");
     Ok(())
 }
 
 #[tauri::command]
-async fn get_setting(key: String, state: tauri::State<'_, SqliteState>) -> Result<Option<String>, String> {
-    let conn = state.0.lock().unwrap();
-    let mut stmt = conn.prepare("SELECT value FROM settings WHERE key = ?")
-        .map_err(|e| e.to_string())?;
-    let mut rows = stmt.query([key]).map_err(|e| e.to_string())?;
-    
-    if let Some(row) = rows.next().map_err(|e| e.to_string())? {
-        let value: String = row.get(0).map_err(|e| e.to_string())?;
-        Ok(Some(value))
-    } else {
-        Ok(None)
+async fn get_setting(key: String, state: tauri::State<'_, SqliteState>) -> Result<Option<String>, AppError> {
+    // API 金鑰只能透過 secrets 指令存取
+    if secrets::is_secret_name(&key) {
+        println!("Refusing to read secret {} through get_setting", key);
+        return Ok(None);
     }
+    state.run(move |conn| {
+        let mut stmt = conn.prepare("SELECT value FROM settings WHERE key = ?")?;
+        let mut rows = stmt.query([key])?;
+        
+        if let Some(row) = rows.next()? {
+            let value: String = row.get(0)?;
+            Ok(Some(value))
+        } else {
+            Ok(None)
+        }
+    }).await
 }
 
 #[tauri::command]
-async fn set_setting(key: String, value: String, state: tauri::State<'_, SqliteState>) -> Result<(), String> {
-    let conn = state.0.lock().unwrap();
-    conn.execute(
-        "INSERT INTO settings (key, value) VALUES (?1, ?2)
-         ON CONFLICT(key) DO UPDATE SET value = ?2, updated_at = CURRENT_TIMESTAMP",
-        [&key, &value],
-    ).map_err(|e| e.to_string())?;
-    println!("Setting {} saved successfully", key);
-    Ok(())
+async fn set_setting(key: String, value: String, state: tauri::State<'_, SqliteState>) -> Result<(), AppError> {
+    state.run(move |conn| {
+        // 舊版設定頁面仍以 set_setting 保存 API 金鑰，改存到加密的 secrets 表
+        if secrets::is_secret_name(&key) {
+            // The settings page never receives the stored key, so an empty value must not wipe it
+            if !value.is_empty() {
+                secrets::set_secret(conn, &key, &value)?;
+            }
+            return Ok(());
+        }
+
+        conn.execute(
+            "INSERT INTO settings (key, value) VALUES (?1, ?2)
+             ON CONFLICT(key) DO UPDATE SET value = ?2, updated_at = CURRENT_TIMESTAMP",
+            [&key, &value],
+        )?;
+        println!("Setting {} saved successfully", key);
+        Ok(())
+    }).await
 }
 
 #[tauri::command]
-async fn delete_setting(key: String, state: tauri::State<'_, SqliteState>) -> Result<(), String> {
-    let conn = state.0.lock().unwrap();
-    conn.execute("DELETE FROM settings WHERE key = ?", [key])
-        .map_err(|e| e.to_string())?;
-    Ok(())
+async fn delete_setting(key: String, state: tauri::State<'_, SqliteState>) -> Result<(), AppError> {
+    state.run(move |conn| {
+        if secrets::is_secret_name(&key) {
+            secrets::delete_secret(conn, &key)?;
+        }
+        conn.execute("DELETE FROM settings WHERE key = ?", [key])?;
+        Ok(())
+    }).await
 }
 
 #[tauri::command]
-async fn get_all_settings(state: tauri::State<'_, SqliteState>) -> Result<Vec<(String, String)>, String> {
-    let conn = state.0.lock().unwrap();
-    let mut stmt = conn.prepare("SELECT key, value FROM settings")
-        .map_err(|e| e.to_string())?;
-    let rows = stmt.query_map([], |row| {
-        Ok((row.get(0)?, row.get(1)?))
-    }).map_err(|e| e.to_string())?;
+async fn get_all_settings(state: tauri::State<'_, SqliteState>) -> Result<Vec<(String, String)>, AppError> {
+    state.run(|conn| {
+        let mut stmt = conn.prepare("SELECT key, value FROM settings")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })?;
+        
+        let mut settings = Vec::new();
+        for row in rows {
+            let (key, value): (String, String) = row?;
+            if !secrets::is_secret_name(&key) {
+                settings.push((key, value));
+            }
+        }
+        println!("Retrieved {} settings", settings.len());
+        Ok(settings)
+    }).await
+}
+
+// 啟動時的資料庫工作；加密的資料庫在解鎖後執行
+pub(crate) fn run_startup_tasks(app: &tauri::AppHandle) -> Result<(), AppError> {
+    let state = app.state::<SqliteState>();
+    let conn = state.get()?;
+    
+    // Mark AI jobs left running by a previous session as interrupted
+    jobs::mark_interrupted_jobs(&conn)
+        .map_err(|e| e.context("Failed to update interrupted AI jobs"))?;
     
-    let mut settings = Vec::new();
-    for row in rows {
-        settings.push(row.map_err(|e| e.to_string())?);
+    // Index content saved before the retrieval index existed
+    if let Err(e) = rag::index_missing_sources(&conn) {
+        println!("Failed to build retrieval index: {}", e);
     }
-    println!("Retrieved {} settings", settings.len());
-    Ok(settings)
+    embeddings::schedule_embedding(app);
+    
+    println!("Database initialized in setup");
+    Ok(())
 }
 
 fn main() {
@@ -92,11 +152,25 @@ fn main() {
     
     // Open the database connection - use app.db instead of settings.db
     let db_path = app_dir.join(DB_NAME);
+    
+    // 加密的資料庫要等使用者輸入密碼 (unlock_database) 後才能遷移與使用
+    let encrypted = encryption::is_encrypted(&db_path);
+    
+    // Bring the schema up to date before anything else uses the database
+    let migration_status = if encrypted {
+        println!("Database is encrypted; waiting for unlock");
+        migrations::locked_status()
+    } else {
+        migrations::run_migrations(&db_path, None)
+    };
+    
     println!("Opening database at: {:?}", db_path);
-    let conn = Connection::open(db_path).expect("Failed to open database");
+    let pool = db_pool::DbPool::new(db_path, db_pool::DEFAULT_POOL_SIZE, encrypted);
     
     tauri::Builder::default()
-        .manage(SqliteState(Mutex::new(conn)))
+        .manage(pool)
+        .manage(migrations::MigrationState(Mutex::new(migration_status)))
+        .manage(jobs::JobRegistry::default())
         .invoke_handler(tauri::generate_handler![
             init_db,
             get_setting,
@@ -121,6 +195,36 @@ fn main() {
             db::export_database,
             db::open_export_location,
             db::export_database_json,
+            import::import_database_json,
+            backup::restore_database,
+            backup::get_backup_schedule,
+            backup::set_backup_schedule,
+            backup::create_snapshot,
+            backup::list_snapshots,
+            backup::prune_snapshots,
+            backup::restore_snapshot,
+            markdown_export::export_blog_markdown,
+            markdown_export::export_project_markdown,
+            markdown_import::import_markdown_folder,
+            document_import::import_document_chapters,
+            epub_export::export_project_epub,
+            docx_export::export_blog_docx,
+            docx_export::export_chapter_docx,
+            docx_export::export_project_docx,
+            pdf_export::export_project_pdf,
+            static_site::get_site_templates,
+            static_site::save_site_template,
+            static_site::reset_site_template,
+            static_site::generate_static_site,
+            wordpress::get_wordpress_username,
+            wordpress::set_wordpress_credentials,
+            wordpress::delete_wordpress_credentials,
+            wordpress::publish_blog_to_wordpress,
+            publishing::publish_blog,
+            publishing::get_publishing_config,
+            publishing::set_publishing_config,
+            publishing::delete_publishing_config,
+            publishing::get_publish_log,
             ai_agent::save_agent_reasoning,
             ai_agent::save_agent_suggestion,
             ai_agent::save_rag_retrieval,
@@ -133,39 +237,61 @@ fn main() {
             ai_agent::get_gemini_api_key,
             ai_agent::generate_with_gemini,
             ai_agent::generate_article_draft,
+            ai_agent::stream_with_gemini,
+            ai_agent::stream_article_draft,
             ai_agent::plan_article_content,
             ai_agent::analyze_article_content,
             ai_agent::adjust_article_style,
             ai_agent::review_article_final,
             ai_agent::inline_edit_text,
             ai_agent::get_article_data,
+            jobs::start_ai_job,
+            jobs::cancel_ai_job,
+            jobs::resume_ai_job,
+            jobs::list_ai_jobs,
+            llm::get_llm_provider_config,
+            llm::set_llm_provider_config,
+            llm::delete_llm_provider_config,
+            rag::rebuild_rag_index,
+            embeddings::search_similar_chunks,
+            embeddings::get_embedding_status,
+            embeddings::refresh_embeddings,
+            search::search_content,
+            revisions::list_revisions,
+            revisions::get_revision,
+            revisions::diff_revisions,
+            revisions::restore_revision,
+            suggestions::diff_suggestion,
+            suggestions::apply_suggestion_hunks,
+            migrations::get_migration_status,
+            secrets::set_secret_value,
+            secrets::has_secret_value,
+            secrets::delete_secret_value,
+            secrets::list_secrets,
+            encryption::get_encryption_status,
+            encryption::unlock_database,
+            encryption::enable_database_encryption,
+            encryption::change_database_passphrase,
         ])
         .setup(|app| {
-            // Initialize database tables
-            db::init_db().expect("Failed to initialize database");
-            
-            // Get the state for AI agent tables initialization
-            let state = app.state::<SqliteState>();
-            let conn = state.0.lock().unwrap();
-            
-            // Initialize AI agent tables
-            ai_agent::init_ai_agent_tables(&conn).expect("Failed to initialize AI agent tables");
+            // Snapshots are skipped while an encrypted database is still locked
+            backup::start_scheduler(app.handle());
             
-            // Ensure settings table exists
-            conn.execute(
-                "CREATE TABLE IF NOT EXISTS settings (
-                    id INTEGER PRIMARY KEY AUTOINCREMENT,
-                    key TEXT UNIQUE NOT NULL,
-                    value TEXT NOT NULL,
-                    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-                    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
-                )",
-                [],
-            ).expect("Failed to create settings table");
+            // Startup work needs the current schema; the UI reads the failure from get_migration_status
+            let migration_error = app.state::<migrations::MigrationState>().0.lock().unwrap().error.clone();
+            if let Some(e) = migration_error {
+                println!("Skipping startup database tasks: {}", e);
+                return Ok(());
+            }
             
-            println!("Database initialized in setup");
+            run_startup_tasks(&app.handle())?;
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|app_handle, event| {
+            if let tauri::RunEvent::Exit = event {
+                backup::snapshot_on_exit(app_handle);
+            }
+        });
 }
@@ -0,0 +1,441 @@
+use chrono::{DateTime, FixedOffset, Local, NaiveDateTime};
+use pulldown_cmark::{html, Event, HeadingLevel, Options, Parser, Tag};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::SqliteState;
+use crate::db::{self, Blog, Project};
+use crate::error::AppError;
+use crate::export_common::default_export_path;
+
+// 摘要的最大長度 (description 欄位)
+const EXCERPT_LEN: usize = 160;
+
+// 靜態網站產生器的目錄與欄位慣例
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MarkdownPreset {
+    #[default]
+    Plain,
+    Hugo,
+    Jekyll,
+    Astro,
+    Zola,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FrontMatterFormat {
+    Yaml,
+    Toml,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct MarkdownExportOptions {
+    #[serde(default)]
+    pub preset: MarkdownPreset,
+    // 未指定時使用預設格式 (Hugo / Zola 用 TOML，其他用 YAML)
+    pub front_matter: Option<FrontMatterFormat>,
+    // 未指定時寫到 exports 目錄
+    pub output_dir: Option<String>,
+    #[serde(default)]
+    pub draft: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MarkdownExportResult {
+    pub output_dir: String,
+    pub files: Vec<String>,
+}
+
+impl MarkdownPreset {
+    fn content_dir(&self) -> &'static str {
+        match self {
+            MarkdownPreset::Plain => "",
+            MarkdownPreset::Hugo => "content/posts",
+            MarkdownPreset::Jekyll => "_posts",
+            MarkdownPreset::Astro => "src/content/blog",
+            MarkdownPreset::Zola => "content/blog",
+        }
+    }
+
+    fn default_format(&self) -> FrontMatterFormat {
+        match self {
+            MarkdownPreset::Hugo | MarkdownPreset::Zola => FrontMatterFormat::Toml,
+            _ => FrontMatterFormat::Yaml,
+        }
+    }
+
+    // Jekyll and Astro only read YAML front matter
+    fn supports(&self, format: FrontMatterFormat) -> bool {
+        match self {
+            MarkdownPreset::Jekyll | MarkdownPreset::Astro => format == FrontMatterFormat::Yaml,
+            _ => true,
+        }
+    }
+}
+
+// 前置資料的值
+enum FrontValue {
+    Text(String),
+    // Written unquoted so YAML and TOML both read it as a date
+    Date(String),
+    Bool(bool),
+    List(Vec<String>),
+    Table(Vec<(&'static str, FrontValue)>),
+}
+
+// JSON string escaping is valid in both YAML double-quoted scalars and TOML basic strings
+fn quote(value: &str) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| "\"\"".to_string())
+}
+
+fn render_yaml_value(out: &mut String, key: &str, value: &FrontValue, indent: usize) {
+    let pad = " ".repeat(indent);
+    match value {
+        FrontValue::Text(text) => out.push_str(&format!("{}{}: {}\n", pad, key, quote(text))),
+        FrontValue::Date(date) => out.push_str(&format!("{}{}: {}\n", pad, key, date)),
+        FrontValue::Bool(flag) => out.push_str(&format!("{}{}: {}\n", pad, key, flag)),
+        FrontValue::List(items) if items.is_empty() => out.push_str(&format!("{}{}: []\n", pad, key)),
+        FrontValue::List(items) => {
+            out.push_str(&format!("{}{}:\n", pad, key));
+            for item in items {
+                out.push_str(&format!("{}  - {}\n", pad, quote(item)));
+            }
+        },
+        FrontValue::Table(fields) => {
+            out.push_str(&format!("{}{}:\n", pad, key));
+            for (sub_key, sub_value) in fields {
+                render_yaml_value(out, sub_key, sub_value, indent + 2);
+            }
+        },
+    }
+}
+
+fn render_toml_value(key: &str, value: &FrontValue) -> String {
+    match value {
+        FrontValue::Text(text) => format!("{} = {}\n", key, quote(text)),
+        FrontValue::Date(date) => format!("{} = {}\n", key, date),
+        FrontValue::Bool(flag) => format!("{} = {}\n", key, flag),
+        FrontValue::List(items) => {
+            let items: Vec<String> = items.iter().map(|item| quote(item)).collect();
+            format!("{} = [{}]\n", key, items.join(", "))
+        },
+        // Tables are written by render_front_matter after the plain keys
+        FrontValue::Table(_) => String::new(),
+    }
+}
+
+fn render_front_matter(fields: &[(&'static str, FrontValue)], format: FrontMatterFormat) -> String {
+    match format {
+        FrontMatterFormat::Yaml => {
+            let mut out = String::from("---\n");
+            for (key, value) in fields {
+                render_yaml_value(&mut out, key, value, 0);
+            }
+            out.push_str("---\n");
+            out
+        },
+        FrontMatterFormat::Toml => {
+            let mut out = String::from("+++\n");
+            for (key, value) in fields {
+                out.push_str(&render_toml_value(key, value));
+            }
+            // TOML tables must follow every top-level key
+            for (key, value) in fields {
+                if let FrontValue::Table(sub_fields) = value {
+                    out.push_str(&format!("\n[{}]\n", key));
+                    for (sub_key, sub_value) in sub_fields {
+                        out.push_str(&render_toml_value(sub_key, sub_value));
+                    }
+                }
+            }
+            out.push_str("+++\n");
+            out
+        },
+    }
+}
+
+// 網址用的名稱：保留文字與數字 (包含中文)，其他字元改成連字號
+pub(crate) fn slugify(title: &str) -> String {
+    let mut slug = String::new();
+    for c in title.trim().chars().flat_map(|c| c.to_lowercase()) {
+        if c.is_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    slug.trim_end_matches('-').to_string()
+}
+
+// 資料庫中的時間可能是 RFC 3339 或 SQLite datetime('now') (UTC)
+pub(crate) fn parse_timestamp(value: &str) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(value).ok().or_else(|| {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S").ok().map(|t| t.and_utc().fixed_offset())
+    })
+}
+
+// 以逗號分隔的關鍵字
+pub(crate) fn split_keywords(keywords: &Option<String>) -> Vec<String> {
+    keywords.as_deref().unwrap_or("")
+        .split([',', '，', '、'])
+        .map(|k| k.trim().to_string())
+        .filter(|k| !k.is_empty())
+        .collect()
+}
+
+// 第一段文字作為摘要
+pub(crate) fn excerpt(content: &str) -> String {
+    let paragraph = content.lines()
+        .map(|line| line.trim())
+        .find(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with("```") && !line.starts_with('!'))
+        .unwrap_or("");
+    let plain: String = paragraph.trim_start_matches(['>', '-', '*', ' '])
+        .chars()
+        .filter(|c| !matches!(c, '*' | '_' | '`'))
+        .collect();
+    if plain.chars().count() > EXCERPT_LEN {
+        let cut: String = plain.chars().take(EXCERPT_LEN).collect();
+        format!("{}…", cut.trim_end())
+    } else {
+        plain
+    }
+}
+
+fn shift_heading(level: HeadingLevel, offset: usize) -> HeadingLevel {
+    const LEVELS: [HeadingLevel; 6] = [
+        HeadingLevel::H1, HeadingLevel::H2, HeadingLevel::H3,
+        HeadingLevel::H4, HeadingLevel::H5, HeadingLevel::H6,
+    ];
+    let index = LEVELS.iter().position(|l| *l == level).unwrap_or(0);
+    LEVELS[(index + offset).min(LEVELS.len() - 1)]
+}
+
+// 移除 HTML 標籤，只保留文字
+pub(crate) fn strip_tags(html: &str) -> String {
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {},
+        }
+    }
+    text
+}
+
+// Markdown 轉成 XHTML 片段 (EPUB、HTML 匯出共用)
+// Raw HTML is not guaranteed to be well-formed XML, so its tags are dropped and only the text is kept.
+// heading_offset pushes headings down a level when the caller adds its own <h1>;
+// images_as_links turns remote images into links for formats that must be self-contained.
+pub(crate) fn markdown_to_xhtml(markdown: &str, heading_offset: usize, images_as_links: bool) -> String {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    options.insert(Options::ENABLE_TASKLISTS);
+
+    let events = Parser::new_ext(markdown, options).map(|event| match event {
+        Event::Html(raw) => Event::Text(strip_tags(&raw).into()),
+        Event::Start(Tag::Heading(level, id, classes)) => Event::Start(Tag::Heading(shift_heading(level, heading_offset), id, classes)),
+        Event::End(Tag::Heading(level, id, classes)) => Event::End(Tag::Heading(shift_heading(level, heading_offset), id, classes)),
+        Event::Start(Tag::Image(kind, url, title)) if images_as_links => Event::Start(Tag::Link(kind, url, title)),
+        Event::End(Tag::Image(kind, url, title)) if images_as_links => Event::End(Tag::Link(kind, url, title)),
+        other => other,
+    });
+
+    let mut out = String::new();
+    html::push_html(&mut out, events);
+    out
+}
+
+// 內容開頭若重複了標題 (# 標題) 就移除
+pub(crate) fn strip_title_heading<'a>(content: &'a str, title: &str) -> &'a str {
+    let trimmed = content.trim_start();
+    let first_line = trimmed.lines().next().unwrap_or("");
+    if first_line.trim_start_matches('#').trim() == title.trim() && first_line.starts_with("# ") {
+        &trimmed[first_line.len()..]
+    } else {
+        content
+    }
+}
+
+fn build_front_matter(blog: &Blog, project: &Project, preset: MarkdownPreset, draft: bool, created: &DateTime<FixedOffset>, updated: &DateTime<FixedOffset>) -> Vec<(&'static str, FrontValue)> {
+    let tags = split_keywords(&blog.keywords);
+    let categories: Vec<String> = project.category.iter().filter(|c| !c.trim().is_empty()).cloned().collect();
+    let description = excerpt(&blog.content);
+    let audience = project.target_audience.clone().filter(|a| !a.trim().is_empty());
+    let title = FrontValue::Text(blog.title.clone());
+
+    let mut fields = match preset {
+        MarkdownPreset::Plain => vec![
+            ("title", title),
+            ("date", FrontValue::Date(created.to_rfc3339())),
+            ("updated", FrontValue::Date(updated.to_rfc3339())),
+            ("project", FrontValue::Text(project.title.clone())),
+            ("tags", FrontValue::List(tags)),
+            ("categories", FrontValue::List(categories)),
+        ],
+        MarkdownPreset::Hugo => vec![
+            ("title", title),
+            ("date", FrontValue::Date(created.to_rfc3339())),
+            ("lastmod", FrontValue::Date(updated.to_rfc3339())),
+            ("draft", FrontValue::Bool(draft)),
+            ("description", FrontValue::Text(description)),
+            ("tags", FrontValue::List(tags)),
+            ("categories", FrontValue::List(categories)),
+        ],
+        MarkdownPreset::Jekyll => vec![
+            ("layout", FrontValue::Text("post".to_string())),
+            ("title", title),
+            ("date", FrontValue::Date(created.format("%Y-%m-%d %H:%M:%S %z").to_string())),
+            ("last_modified_at", FrontValue::Date(updated.format("%Y-%m-%d %H:%M:%S %z").to_string())),
+            ("published", FrontValue::Bool(!draft)),
+            ("description", FrontValue::Text(description)),
+            ("tags", FrontValue::List(tags)),
+            ("categories", FrontValue::List(categories)),
+        ],
+        MarkdownPreset::Astro => vec![
+            ("title", title),
+            ("description", FrontValue::Text(description)),
+            ("pubDate", FrontValue::Date(created.to_rfc3339())),
+            ("updatedDate", FrontValue::Date(updated.to_rfc3339())),
+            ("draft", FrontValue::Bool(draft)),
+            ("tags", FrontValue::List(tags)),
+            ("categories", FrontValue::List(categories)),
+        ],
+        // Zola only allows known keys at the top level; the rest goes under [taxonomies] and [extra]
+        MarkdownPreset::Zola => vec![
+            ("title", title),
+            ("date", FrontValue::Date(created.to_rfc3339())),
+            ("updated", FrontValue::Date(updated.to_rfc3339())),
+            ("draft", FrontValue::Bool(draft)),
+            ("description", FrontValue::Text(description)),
+            ("taxonomies", FrontValue::Table(vec![
+                ("tags", FrontValue::List(tags)),
+                ("categories", FrontValue::List(categories)),
+            ])),
+        ],
+    };
+
+    if let Some(audience) = audience {
+        match preset {
+            MarkdownPreset::Zola => fields.push(("extra", FrontValue::Table(vec![("audience", FrontValue::Text(audience))]))),
+            _ => fields.push(("audience", FrontValue::Text(audience))),
+        }
+    }
+    fields
+}
+
+// 依預設組合輸出的檔名
+fn file_name(preset: MarkdownPreset, slug: &str, created: &DateTime<FixedOffset>) -> String {
+    match preset {
+        MarkdownPreset::Jekyll => format!("{}-{}.md", created.format("%Y-%m-%d"), slug),
+        _ => format!("{}.md", slug),
+    }
+}
+
+fn default_output_dir(state: &SqliteState, project: &Project, preset: MarkdownPreset) -> Result<PathBuf, AppError> {
+    default_export_path(state, &format!("markdown_{:?}_", preset).to_lowercase(), &project.title, "project", "")
+}
+
+// Zola 的每個目錄需要 _index.md
+fn write_zola_section(dir: &Path, project: &Project) -> Result<Option<PathBuf>, AppError> {
+    let index = dir.join("_index.md");
+    if index.exists() {
+        return Ok(None);
+    }
+    let fields = vec![
+        ("title", FrontValue::Text(project.title.clone())),
+        ("sort_by", FrontValue::Text("date".to_string())),
+    ];
+    fs::write(&index, render_front_matter(&fields, FrontMatterFormat::Toml))?;
+    Ok(Some(index))
+}
+
+// 將文章寫成 Markdown 檔
+fn write_blogs(blogs: &[Blog], project: &Project, options: &MarkdownExportOptions, output_dir: &Path) -> Result<Vec<String>, AppError> {
+    let preset = options.preset;
+    let format = options.front_matter.unwrap_or_else(|| preset.default_format());
+    if !preset.supports(format) {
+        return Err(AppError::validation(format!("{:?} only supports YAML front matter", preset)));
+    }
+
+    let content_dir = output_dir.join(preset.content_dir());
+    fs::create_dir_all(&content_dir)?;
+
+    let mut files = Vec::new();
+    if preset == MarkdownPreset::Zola {
+        if let Some(index) = write_zola_section(&content_dir, project)? {
+            files.push(index.to_string_lossy().to_string());
+        }
+    }
+
+    let now = Local::now().fixed_offset();
+    let mut used_names = HashSet::new();
+    for blog in blogs {
+        let created = parse_timestamp(&blog.created_at).unwrap_or(now);
+        let updated = parse_timestamp(&blog.updated_at).unwrap_or(created);
+
+        // Titles without letters or digits, and repeated titles, fall back to the blog id
+        let mut slug = slugify(&blog.title);
+        if slug.is_empty() {
+            slug = format!("post-{}", blog.id);
+        }
+        let mut name = file_name(preset, &slug, &created);
+        if !used_names.insert(name.clone()) {
+            name = file_name(preset, &format!("{}-{}", slug, blog.id), &created);
+            used_names.insert(name.clone());
+        }
+
+        let fields = build_front_matter(blog, project, preset, options.draft, &created, &updated);
+        let mut text = render_front_matter(&fields, format);
+        text.push('\n');
+        text.push_str(blog.content.trim_end());
+        text.push('\n');
+
+        let path = content_dir.join(&name);
+        fs::write(&path, text)?;
+        println!("Exported blog {} to {:?}", blog.id, path);
+        files.push(path.to_string_lossy().to_string());
+    }
+    Ok(files)
+}
+
+fn export(state: &SqliteState, project: &Project, blogs: &[Blog], options: &MarkdownExportOptions) -> Result<MarkdownExportResult, AppError> {
+    let output_dir = match &options.output_dir {
+        Some(dir) if !dir.trim().is_empty() => PathBuf::from(dir),
+        _ => default_output_dir(state, project, options.preset)?,
+    };
+    let files = write_blogs(blogs, project, options, &output_dir)?;
+    Ok(MarkdownExportResult { output_dir: output_dir.to_string_lossy().to_string(), files })
+}
+
+// 匯出單篇文章為 Markdown
+#[tauri::command]
+pub async fn export_blog_markdown(blog_id: i64, options: Option<MarkdownExportOptions>, state: tauri::State<'_, SqliteState>) -> Result<MarkdownExportResult, AppError> {
+    let pool = state.inner().clone();
+    state.run(move |conn| {
+        let blog = db::load_blog(conn, blog_id)?;
+        let project = db::load_project(conn, blog.project_id)?;
+        export(&pool, &project, &[blog], &options.unwrap_or_default())
+    }).await
+}
+
+// 匯出專案的所有文章為 Markdown
+#[tauri::command]
+pub async fn export_project_markdown(project_id: i64, options: Option<MarkdownExportOptions>, state: tauri::State<'_, SqliteState>) -> Result<MarkdownExportResult, AppError> {
+    let pool = state.inner().clone();
+    state.run(move |conn| {
+        let project = db::load_project(conn, project_id)?;
+        let blogs = db::load_blogs(conn, project_id)?;
+        if blogs.is_empty() {
+            return Err(AppError::validation("This project has no blog posts to export"));
+        }
+        export(&pool, &project, &blogs, &options.unwrap_or_default())
+    }).await
+}
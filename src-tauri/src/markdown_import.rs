@@ -0,0 +1,337 @@
+use chrono::{DateTime, Local, NaiveDate, TimeZone};
+use rusqlite::{params, Transaction};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use tauri::AppHandle;
+
+use crate::SqliteState;
+use crate::db;
+use crate::embeddings;
+use crate::error::AppError;
+use crate::import::{self, item, ImportMode, ImportReport, IMPORT_AUTHOR};
+use crate::markdown_export::parse_timestamp;
+use crate::rag;
+use crate::revisions;
+
+// 各靜態網站產生器常用的日期欄位名稱
+const DATE_KEYS: [&str; 4] = ["date", "pubDate", "published", "created"];
+const UPDATED_KEYS: [&str; 5] = ["updated", "lastmod", "last_modified_at", "updatedDate", "modified"];
+
+#[derive(Debug, Default, Deserialize)]
+pub struct MarkdownImportOptions {
+    // 是否包含子目錄 (例如 Hugo 的 page bundle)
+    #[serde(default)]
+    pub recursive: bool,
+}
+
+// 一個解析後的 Markdown 檔
+struct MarkdownFile {
+    relative: String,
+    title: String,
+    content: String,
+    tags: Vec<String>,
+    created_at: String,
+    updated_at: String,
+}
+
+fn collect_files(dir: &Path, recursive: bool, files: &mut Vec<std::path::PathBuf>) -> Result<(), AppError> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            if recursive {
+                collect_files(&path, recursive, files)?;
+            }
+        } else if path.extension().and_then(|e| e.to_str()).map(|e| matches!(e.to_lowercase().as_str(), "md" | "markdown")).unwrap_or(false) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+// 自然排序鍵：每層目錄與檔名先依開頭的數字排序 (2 在 10 之前，沒有數字的排在後面)，再依名稱
+fn natural_key(relative: &str) -> Vec<(bool, u64, String)> {
+    relative.split('/').map(|part| {
+        let digits: String = part.chars().take_while(|c| c.is_ascii_digit()).collect();
+        match digits.parse::<u64>() {
+            Ok(number) => (false, number, part.to_lowercase()),
+            Err(_) => (true, 0, part.to_lowercase()),
+        }
+    }).collect()
+}
+
+fn toml_to_json(value: toml::Value) -> Value {
+    match value {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => Value::from(i),
+        toml::Value::Float(f) => Value::from(f),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(d) => Value::String(d.to_string()),
+        toml::Value::Array(items) => Value::Array(items.into_iter().map(toml_to_json).collect()),
+        toml::Value::Table(table) => Value::Object(table.into_iter().map(|(k, v)| (k, toml_to_json(v))).collect()),
+    }
+}
+
+// 拆出 front matter (YAML 用 ---，TOML 用 +++)，回傳 (欄位, 內文)
+fn split_front_matter(text: &str) -> Result<(Value, &str), String> {
+    let text = text.trim_start_matches('\u{feff}');
+    let first_line = text.lines().next().unwrap_or("").trim_end();
+    let (closing, is_toml) = match first_line {
+        "---" => (["---", "..."], false),
+        "+++" => (["+++", "+++"], true),
+        _ => return Ok((Value::Null, text)),
+    };
+
+    let start = text.find('\n').map(|i| i + 1).unwrap_or(text.len());
+    let mut offset = start;
+    for line in text[start..].split_inclusive('\n') {
+        if closing.contains(&line.trim_end()) {
+            let front = &text[start..offset];
+            let body = &text[offset + line.len()..];
+            let fields = if is_toml {
+                toml::from_str::<toml::Table>(front)
+                    .map(|table| toml_to_json(toml::Value::Table(table)))
+                    .map_err(|e| format!("invalid TOML front matter: {}", e))?
+            } else if front.trim().is_empty() {
+                Value::Null
+            } else {
+                serde_yaml::from_str::<Value>(front).map_err(|e| format!("invalid YAML front matter: {}", e))?
+            };
+            return Ok((fields, body));
+        }
+        offset += line.len();
+    }
+    Err("front matter is not closed".to_string())
+}
+
+fn field_text(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) if !s.trim().is_empty() => Some(s.trim().to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+fn first_field(fields: &Value, keys: &[&str]) -> Option<String> {
+    keys.iter().find_map(|key| fields.get(*key).and_then(field_text))
+}
+
+// tags 可能是陣列或逗號分隔的字串；Zola 放在 taxonomies 底下
+fn front_matter_tags(fields: &Value) -> Vec<String> {
+    let value = fields.get("tags")
+        .or_else(|| fields.get("keywords"))
+        .or_else(|| fields.get("taxonomies").and_then(|t| t.get("tags")));
+    let mut tags: Vec<String> = match value {
+        Some(Value::Array(items)) => items.iter().filter_map(field_text).collect(),
+        Some(Value::String(s)) => s.split([',', '，', '、']).map(|t| t.trim().to_string()).collect(),
+        _ => Vec::new(),
+    };
+    tags.retain(|t| !t.is_empty());
+    tags.dedup();
+    tags
+}
+
+// 接受 RFC 3339、Jekyll 的 "2024-01-01 10:00:00 +0800" 與純日期
+fn normalize_date(value: &str) -> Option<String> {
+    if let Some(date) = parse_timestamp(value) {
+        return Some(date.to_rfc3339());
+    }
+    if let Ok(date) = DateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S %z") {
+        return Some(date.to_rfc3339());
+    }
+    let date = NaiveDate::parse_from_str(value.get(..10)?, "%Y-%m-%d").ok()?;
+    Local.from_local_datetime(&date.and_hms_opt(0, 0, 0)?).earliest().map(|d| d.to_rfc3339())
+}
+
+// Jekyll 檔名開頭的日期 (2024-01-01-title.md)
+fn date_from_file_name(stem: &str) -> Option<String> {
+    if stem.len() > 10 && stem.as_bytes()[10] == b'-' {
+        normalize_date(&stem[..10])
+    } else {
+        None
+    }
+}
+
+fn title_from_path(path: &Path) -> String {
+    let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    // page bundle 的 index.md 以目錄名稱為標題
+    let name = if stem.eq_ignore_ascii_case("index") {
+        path.parent().and_then(|p| p.file_name()).map(|s| s.to_string_lossy().to_string()).unwrap_or(stem.clone())
+    } else {
+        stem
+    };
+    let name = if date_from_file_name(&name).is_some() { name[11..].to_string() } else { name };
+    name.replace(['-', '_'], " ").trim().to_string()
+}
+
+fn parse_file(path: &Path, relative: String) -> Result<MarkdownFile, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("cannot read file: {}", e))?;
+    let (fields, body) = split_front_matter(&text)?;
+
+    let heading = body.lines()
+        .map(|line| line.trim())
+        .find(|line| !line.is_empty())
+        .and_then(|line| line.strip_prefix("# "))
+        .map(|line| line.trim().to_string());
+    let title = first_field(&fields, &["title"])
+        .or(heading)
+        .unwrap_or_else(|| title_from_path(path));
+    if title.is_empty() {
+        return Err("no title in front matter, heading or file name".to_string());
+    }
+
+    let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let modified = fs::metadata(path).and_then(|m| m.modified()).ok()
+        .map(|t| DateTime::<Local>::from(t).to_rfc3339());
+    let created_at = first_field(&fields, &DATE_KEYS).and_then(|d| normalize_date(&d))
+        .or_else(|| date_from_file_name(&stem))
+        .or(modified)
+        .unwrap_or_else(|| Local::now().to_rfc3339());
+    let updated_at = first_field(&fields, &UPDATED_KEYS).and_then(|d| normalize_date(&d))
+        .unwrap_or_else(|| created_at.clone());
+
+    Ok(MarkdownFile {
+        relative,
+        title,
+        content: body.trim_start_matches(['\r', '\n']).trim_end().to_string(),
+        tags: front_matter_tags(&fields),
+        created_at,
+        updated_at,
+    })
+}
+
+fn import_files(tx: &Transaction, project_id: i64, is_book: bool, files: &[MarkdownFile], report: &mut ImportReport) -> Result<Vec<i64>, AppError> {
+    let (kind, table) = if is_book { ("chapter", "chapters") } else { ("blog", "blogs") };
+    let mut next_chapter: i32 = tx.query_row(
+        "SELECT COALESCE(MAX(chapter_number), 0) FROM chapters WHERE project_id = ?1",
+        [project_id],
+        |row| row.get(0),
+    )?;
+    // 同一資料夾內重複的標題
+    let mut seen: HashMap<String, &str> = HashMap::new();
+    let mut created = Vec::new();
+
+    for file in files {
+        if let Some(other) = seen.get(&import::normalize_title(&file.title)) {
+            report.conflicts.push(item(kind, None, None, &file.title,
+                Some(format!("{}: the same title is also used by {}", file.relative, other))));
+            continue;
+        }
+        seen.insert(import::normalize_title(&file.title), &file.relative);
+
+        if let Some(existing) = import::find_existing(tx, table, project_id, &file.title)? {
+            let (conflict, reason) = import::classify_duplicate(&existing, &file.content, &None);
+            let entry = item(kind, None, Some(existing.0), &file.title, Some(format!("{}: {}", file.relative, reason)));
+            if conflict { report.conflicts.push(entry) } else { report.skipped.push(entry) }
+            continue;
+        }
+
+        if is_book {
+            next_chapter += 1;
+            tx.execute(
+                "INSERT INTO chapters (project_id, title, content, chapter_number, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![project_id, file.title, file.content, next_chapter, file.created_at, file.updated_at],
+            )?;
+        } else {
+            let keywords = if file.tags.is_empty() { None } else { Some(file.tags.join(", ")) };
+            tx.execute(
+                "INSERT INTO blogs (project_id, title, content, keywords, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![project_id, file.title, file.content, keywords, file.created_at, file.updated_at],
+            )?;
+        }
+        let id = tx.last_insert_rowid();
+        created.push(id);
+        report.created.push(item(kind, None, Some(id), &file.title, None));
+    }
+    Ok(created)
+}
+
+// 匯入資料夾中的 Markdown 檔：部落格專案建立文章，書籍專案依檔名順序建立章節
+#[tauri::command]
+pub async fn import_markdown_folder(path: String, project_id: i64, options: Option<MarkdownImportOptions>, app_handle: AppHandle, state: tauri::State<'_, SqliteState>) -> Result<ImportReport, AppError> {
+    let options = options.unwrap_or_default();
+    let root = Path::new(&path).to_path_buf();
+    if !root.is_dir() {
+        return Err(AppError::validation(format!("{} is not a folder", path)));
+    }
+
+    let mut paths = Vec::new();
+    collect_files(&root, options.recursive, &mut paths)?;
+    let mut entries: Vec<(String, std::path::PathBuf)> = paths.into_iter()
+        .map(|p| (p.strip_prefix(&root).unwrap_or(&p).to_string_lossy().replace('\\', "/"), p))
+        .collect();
+    entries.sort_by_cached_key(|(relative, _)| natural_key(relative));
+    println!("Importing {} Markdown files from {} into project {}", entries.len(), path, project_id);
+
+    let mut report = ImportReport::new(ImportMode::Merge);
+    let mut files = Vec::new();
+    for (relative, file_path) in entries {
+        let name = file_path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        // Hugo / Zola 的 _index.md 是目錄頁，不是文章
+        if name.eq_ignore_ascii_case("_index.md") {
+            report.skipped.push(item("file", None, None, &relative, Some("section index page".to_string())));
+            continue;
+        }
+        match parse_file(&file_path, relative.clone()) {
+            Ok(file) => files.push(file),
+            Err(reason) => report.skipped.push(item("file", None, None, &relative, Some(reason))),
+        }
+    }
+    if files.is_empty() {
+        return Err(AppError::validation(format!("No Markdown files could be imported from {}", path)));
+    }
+
+    let report = state.run(move |conn| {
+        let project = db::load_project(conn, project_id)?;
+        let is_book = project.type_ == "book";
+        let source = if is_book { rag::SOURCE_CHAPTER } else { rag::SOURCE_BLOG };
+
+        let tx = conn.transaction()?;
+        let created = import_files(&tx, project_id, is_book, &files, &mut report)?;
+        for id in &created {
+            revisions::record_revision_logged(&tx, source, *id, IMPORT_AUTHOR);
+            db::reindex_source(&tx, source, *id);
+        }
+        tx.commit()?;
+
+        println!(
+            "Markdown import finished: {} created, {} skipped, {} conflicts",
+            report.created.len(), report.skipped.len(), report.conflicts.len()
+        );
+        Ok::<_, AppError>(report)
+    }).await?;
+
+    embeddings::schedule_embedding(&app_handle);
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(names: &[&str]) -> Vec<String> {
+        let mut names: Vec<String> = names.iter().map(|n| n.to_string()).collect();
+        names.sort_by_cached_key(|n| natural_key(n));
+        names
+    }
+
+    #[test]
+    fn numeric_prefixes_sort_numerically() {
+        assert_eq!(
+            sorted(&["10-epilogue.md", "2-middle.md", "1-start.md", "appendix.md", "Afterword.md"]),
+            vec!["1-start.md", "2-middle.md", "10-epilogue.md", "Afterword.md", "appendix.md"],
+        );
+    }
+
+    #[test]
+    fn each_folder_level_sorts_naturally() {
+        assert_eq!(
+            sorted(&["10-part/1.md", "2-part/10.md", "2-part/9.md", "1-intro.md"]),
+            vec!["1-intro.md", "2-part/9.md", "2-part/10.md", "10-part/1.md"],
+        );
+    }
+}
@@ -0,0 +1,506 @@
+use rusqlite::{Connection, Transaction};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use chrono::Local;
+
+use crate::error::AppError;
+
+// 資料庫結構遷移
+// Each step runs in its own transaction and bumps PRAGMA user_version on success.
+// Never edit a step that has shipped; add a new one instead.
+struct Migration {
+    version: i32,
+    description: &'static str,
+    up: fn(&Transaction) -> rusqlite::Result<()>,
+}
+
+const MIGRATIONS: &[Migration] = &[
+    Migration { version: 1, description: "core tables", up: create_core_tables },
+    Migration { version: 2, description: "AI agent tables", up: create_agent_tables },
+    Migration { version: 3, description: "AI generation job columns", up: add_generation_job_columns },
+    Migration { version: 4, description: "retrieval index", up: create_retrieval_index },
+    Migration { version: 5, description: "chunk embeddings", up: create_chunk_embeddings },
+    Migration { version: 6, description: "full-text search", up: create_full_text_search },
+    Migration { version: 7, description: "revision history", up: create_revisions },
+    Migration { version: 8, description: "suggestion hunks", up: add_suggestion_hunk_columns },
+    Migration { version: 9, description: "encrypted secrets", up: create_secrets },
+    Migration { version: 10, description: "static site templates", up: create_site_templates },
+    Migration { version: 11, description: "blog publishing status", up: add_blog_publishing_columns },
+    Migration { version: 12, description: "publish log", up: create_publish_log },
+];
+
+pub fn latest_version() -> i32 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+// 遷移結果，提供給前端顯示
+#[derive(Debug, Clone, Serialize)]
+pub struct MigrationStatus {
+    pub current_version: i32,
+    pub latest_version: i32,
+    pub applied: Vec<i32>,
+    pub backup_path: Option<String>,
+    pub error: Option<String>,
+}
+
+pub struct MigrationState(pub Mutex<MigrationStatus>);
+
+fn user_version(conn: &Connection) -> rusqlite::Result<i32> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+}
+
+fn existing_columns(tx: &Transaction, table: &str) -> rusqlite::Result<HashSet<String>> {
+    let mut stmt = tx.prepare(&format!("PRAGMA table_info({})", table))?;
+    let columns = stmt.query_map([], |row| row.get::<_, String>(1))?
+        .collect::<rusqlite::Result<HashSet<_>>>()?;
+    Ok(columns)
+}
+
+// 補上缺少的欄位；資料庫在有版本號之前可能已經有部分欄位
+fn add_missing_columns(tx: &Transaction, table: &str, columns: &[(&str, &str)]) -> rusqlite::Result<()> {
+    let existing = existing_columns(tx, table)?;
+    for (name, definition) in columns {
+        if !existing.contains(*name) {
+            tx.execute(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, name, definition), [])?;
+            println!("Added column {}.{}", table, name);
+        }
+    }
+    Ok(())
+}
+
+// v1: projects, blogs, chapters, settings
+fn create_core_tables(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS projects (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            title TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            progress INTEGER DEFAULT 0
+        );
+        CREATE TABLE IF NOT EXISTS blogs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            project_id INTEGER NOT NULL,
+            title TEXT NOT NULL,
+            content TEXT,
+            keywords TEXT,
+            created_at TEXT,
+            updated_at TEXT,
+            FOREIGN KEY (project_id) REFERENCES projects (id) ON DELETE CASCADE
+        );
+        CREATE TABLE IF NOT EXISTS chapters (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            project_id INTEGER NOT NULL,
+            title TEXT NOT NULL,
+            content TEXT,
+            chapter_number INTEGER,
+            created_at TEXT,
+            updated_at TEXT,
+            FOREIGN KEY (project_id) REFERENCES projects (id) ON DELETE CASCADE
+        );
+        CREATE TABLE IF NOT EXISTS settings (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            key TEXT UNIQUE NOT NULL,
+            value TEXT NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );"
+    )?;
+
+    add_missing_columns(tx, "projects", &[
+        ("type_", "TEXT NOT NULL DEFAULT 'blog'"),
+        ("description", "TEXT"),
+        ("category", "TEXT"),
+        ("time_commitment", "TEXT"),
+        ("publishing_frequency", "TEXT"),
+        ("custom_frequency", "TEXT"),
+        ("deadline", "TEXT"),
+        ("availability", "TEXT"),
+        ("reminder_frequency", "TEXT"),
+        ("publishing_platform", "TEXT"),
+        ("wordpress_url", "TEXT"),
+        ("substack_url", "TEXT"),
+        ("custom_platform", "TEXT"),
+        ("monetization_strategy", "TEXT"),
+        ("monetization_goals", "TEXT"),
+        ("keywords", "TEXT"),
+        ("target_audience", "TEXT"),
+        ("reference_links", "TEXT"),
+        ("structure", "TEXT"),
+        ("content_strategy", "TEXT"),
+        ("seo_strategy", "TEXT"),
+        ("goal", "TEXT"),
+        ("start_date", "TEXT"),
+        ("end_date", "TEXT"),
+        ("progress", "INTEGER DEFAULT 0"),
+        ("article_length", "TEXT"),
+        ("receive_notifications", "INTEGER DEFAULT 0"),
+    ])?;
+    add_missing_columns(tx, "blogs", &[("keywords", "TEXT")])?;
+
+    Ok(())
+}
+
+// v2: agent_reasoning, agent_suggestions, rag_retrievals
+fn create_agent_tables(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS agent_reasoning (
+            id INTEGER PRIMARY KEY,
+            blog_id INTEGER NOT NULL,
+            agent_type TEXT NOT NULL,
+            title TEXT NOT NULL,
+            reasoning TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            FOREIGN KEY (blog_id) REFERENCES blogs (id) ON DELETE CASCADE
+        );
+        CREATE TABLE IF NOT EXISTS agent_suggestions (
+            id INTEGER PRIMARY KEY,
+            reasoning_id INTEGER NOT NULL,
+            suggestion TEXT NOT NULL,
+            applied BOOLEAN DEFAULT 0,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            FOREIGN KEY (reasoning_id) REFERENCES agent_reasoning (id) ON DELETE CASCADE
+        );
+        CREATE TABLE IF NOT EXISTS rag_retrievals (
+            id INTEGER PRIMARY KEY,
+            blog_id INTEGER NOT NULL,
+            agent_type TEXT NOT NULL,
+            source_type TEXT NOT NULL,
+            source_id INTEGER NOT NULL,
+            relevance_score REAL NOT NULL,
+            content TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            FOREIGN KEY (blog_id) REFERENCES blogs (id) ON DELETE CASCADE
+        );"
+    )
+}
+
+// v3: 生成任務的輸出內容、狀態與原始請求
+fn add_generation_job_columns(tx: &Transaction) -> rusqlite::Result<()> {
+    add_missing_columns(tx, "agent_reasoning", &[
+        ("output", "TEXT"),
+        ("status", "TEXT NOT NULL DEFAULT 'completed'"),
+        ("request", "TEXT"),
+    ])
+}
+
+// v4: 檢索片段與詞頻
+fn create_retrieval_index(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS content_chunks (
+            id INTEGER PRIMARY KEY,
+            source_type TEXT NOT NULL,
+            source_id INTEGER NOT NULL,
+            project_id INTEGER NOT NULL,
+            chunk_index INTEGER NOT NULL,
+            content TEXT NOT NULL,
+            content_hash TEXT NOT NULL DEFAULT '',
+            term_count INTEGER NOT NULL,
+            created_at TEXT NOT NULL
+        );
+        CREATE TABLE IF NOT EXISTS chunk_terms (
+            chunk_id INTEGER NOT NULL,
+            term TEXT NOT NULL,
+            frequency INTEGER NOT NULL,
+            PRIMARY KEY (chunk_id, term),
+            FOREIGN KEY (chunk_id) REFERENCES content_chunks (id) ON DELETE CASCADE
+        );
+        CREATE INDEX IF NOT EXISTS idx_content_chunks_source ON content_chunks(source_type, source_id);
+        CREATE INDEX IF NOT EXISTS idx_content_chunks_project ON content_chunks(project_id);
+        CREATE INDEX IF NOT EXISTS idx_chunk_terms_term ON chunk_terms(term);"
+    )?;
+
+    // Chunks indexed before content_hash existed are dropped and rebuilt on startup
+    if !existing_columns(tx, "content_chunks")?.contains("content_hash") {
+        tx.execute_batch(
+            "ALTER TABLE content_chunks ADD COLUMN content_hash TEXT NOT NULL DEFAULT '';
+             DELETE FROM chunk_terms;
+             DELETE FROM content_chunks;"
+        )?;
+    }
+    Ok(())
+}
+
+// v5: 以內容雜湊為鍵的嵌入向量
+fn create_chunk_embeddings(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS chunk_embeddings (
+            content_hash TEXT NOT NULL,
+            model TEXT NOT NULL,
+            dimensions INTEGER NOT NULL,
+            vector BLOB NOT NULL,
+            created_at TEXT NOT NULL,
+            PRIMARY KEY (content_hash, model)
+        );
+        CREATE INDEX IF NOT EXISTS idx_content_chunks_hash ON content_chunks(content_hash);"
+    )
+}
+
+// 建立外部內容 FTS5 表與同步觸發器，並載入既有資料
+fn create_fts_index(tx: &Transaction, fts: &str, source: &str, columns: &[&str]) -> rusqlite::Result<()> {
+    let cols = columns.join(", ");
+    let new_values = columns.iter().map(|c| format!("new.{}", c)).collect::<Vec<_>>().join(", ");
+    let old_values = columns.iter().map(|c| format!("old.{}", c)).collect::<Vec<_>>().join(", ");
+
+    tx.execute_batch(&format!(
+        "CREATE VIRTUAL TABLE IF NOT EXISTS {fts} USING fts5(
+            {cols}, content='{source}', content_rowid='id', tokenize='trigram'
+        );
+        CREATE TRIGGER IF NOT EXISTS {fts}_ai AFTER INSERT ON {source} BEGIN
+            INSERT INTO {fts}(rowid, {cols}) VALUES (new.id, {new_values});
+        END;
+        CREATE TRIGGER IF NOT EXISTS {fts}_ad AFTER DELETE ON {source} BEGIN
+            INSERT INTO {fts}({fts}, rowid, {cols}) VALUES ('delete', old.id, {old_values});
+        END;
+        CREATE TRIGGER IF NOT EXISTS {fts}_au AFTER UPDATE ON {source} BEGIN
+            INSERT INTO {fts}({fts}, rowid, {cols}) VALUES ('delete', old.id, {old_values});
+            INSERT INTO {fts}(rowid, {cols}) VALUES (new.id, {new_values});
+        END;
+        INSERT INTO {fts}({fts}) VALUES ('rebuild');"
+    ))
+}
+
+// v6: 專案、部落格與章節的全文索引
+fn create_full_text_search(tx: &Transaction) -> rusqlite::Result<()> {
+    create_fts_index(tx, "projects_fts", "projects", &["title", "description", "keywords"])?;
+    create_fts_index(tx, "blogs_fts", "blogs", &["title", "content", "keywords"])?;
+    create_fts_index(tx, "chapters_fts", "chapters", &["title", "content"])
+}
+
+// v7: 部落格與章節的版本記錄
+fn create_revisions(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS revisions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            source_type TEXT NOT NULL,
+            source_id INTEGER NOT NULL,
+            project_id INTEGER NOT NULL,
+            title TEXT NOT NULL,
+            content TEXT NOT NULL,
+            keywords TEXT,
+            chapter_number INTEGER,
+            author TEXT NOT NULL,
+            restored_from INTEGER,
+            created_at TEXT NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_revisions_source ON revisions(source_type, source_id);"
+    )
+}
+
+// v8: 逐段修改建議的區塊序號、原文、位置與審核狀態
+fn add_suggestion_hunk_columns(tx: &Transaction) -> rusqlite::Result<()> {
+    add_missing_columns(tx, "agent_suggestions", &[
+        ("hunk_index", "INTEGER"),
+        ("original_text", "TEXT"),
+        ("start_offset", "INTEGER"),
+        ("end_offset", "INTEGER"),
+        ("status", "TEXT NOT NULL DEFAULT 'pending'"),
+    ])
+}
+
+// v9: API 金鑰改為加密保存，並移除 settings 表中的明文
+fn create_secrets(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS secrets (
+            name TEXT PRIMARY KEY,
+            nonce BLOB NOT NULL,
+            ciphertext BLOB NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL
+        );"
+    )?;
+    crate::secrets::move_plaintext_secrets(tx)
+        .map(|_| ())
+        .map_err(|e| rusqlite::Error::SqliteFailure(rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_ERROR), Some(e)))
+}
+
+// v10: 每個專案自訂的靜態網站範本
+fn create_site_templates(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS site_templates (
+            project_id INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            content TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            PRIMARY KEY (project_id, name),
+            FOREIGN KEY (project_id) REFERENCES projects (id) ON DELETE CASCADE
+        );"
+    )
+}
+
+// v11: 文章發佈到外部平台後的遠端文章 ID 與網址
+fn add_blog_publishing_columns(tx: &Transaction) -> rusqlite::Result<()> {
+    add_missing_columns(tx, "blogs", &[
+        ("remote_platform", "TEXT"),
+        ("remote_post_id", "TEXT"),
+        ("remote_url", "TEXT"),
+        ("published_at", "TEXT"),
+    ])
+}
+
+// v12: 每次發佈到外部平台的紀錄 (含失敗)，用來找出更新的遠端文章
+fn create_publish_log(tx: &Transaction) -> rusqlite::Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS publish_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            blog_id INTEGER NOT NULL,
+            project_id INTEGER NOT NULL,
+            platform TEXT NOT NULL,
+            action TEXT NOT NULL,
+            status TEXT NOT NULL,
+            post_status TEXT,
+            remote_id TEXT,
+            remote_url TEXT,
+            canonical_url TEXT,
+            error TEXT,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (blog_id) REFERENCES blogs (id) ON DELETE CASCADE
+        );
+        CREATE INDEX IF NOT EXISTS idx_publish_log_blog ON publish_log (blog_id, platform);"
+    )
+}
+
+// 遷移前備份資料庫到 backups 目錄
+fn backup_before_migration(conn: &Connection, db_path: &Path, from_version: i32) -> Result<Option<PathBuf>, String> {
+    let table_count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table'",
+        [],
+        |row| row.get(0)
+    ).map_err(|e| e.to_string())?;
+    // A brand new database has nothing to lose
+    if table_count == 0 {
+        return Ok(None);
+    }
+
+    let backup_dir = db_path.parent()
+        .ok_or_else(|| "Invalid database path".to_string())?
+        .join("backups");
+    std::fs::create_dir_all(&backup_dir).map_err(|e| e.to_string())?;
+
+    let timestamp = Local::now().format("%Y%m%d_%H%M%S");
+    let backup_path = backup_dir.join(format!("pre_migration_v{}_{}.db", from_version, timestamp));
+
+    // VACUUM INTO writes a consistent copy even if another connection has the file open
+    conn.execute("VACUUM INTO ?1", [backup_path.to_string_lossy().to_string()])
+        .map_err(|e| e.to_string())?;
+
+    println!("Backed up database to {:?} before migrating", backup_path);
+    Ok(Some(backup_path))
+}
+
+// 加密資料庫解鎖前的狀態，解鎖後才執行遷移
+pub fn locked_status() -> MigrationStatus {
+    MigrationStatus {
+        current_version: 0,
+        latest_version: latest_version(),
+        applied: Vec::new(),
+        backup_path: None,
+        error: Some("The database is encrypted and has not been unlocked yet.".to_string()),
+    }
+}
+
+// 將資料庫更新到最新結構
+// Encrypted databases need their passphrase; VACUUM INTO backups inherit the key.
+pub fn run_migrations(db_path: &Path, passphrase: Option<&str>) -> MigrationStatus {
+    let mut status = MigrationStatus {
+        current_version: 0,
+        latest_version: latest_version(),
+        applied: Vec::new(),
+        backup_path: None,
+        error: None,
+    };
+
+    let mut conn = match crate::encryption::open_database(db_path, passphrase) {
+        Ok(conn) => conn,
+        Err(e) => {
+            status.error = Some(format!("Failed to open database: {}", e));
+            return status;
+        }
+    };
+
+    let version = match user_version(&conn) {
+        Ok(version) => version,
+        Err(e) => {
+            status.error = Some(format!("Failed to read database version: {}", e));
+            return status;
+        }
+    };
+    status.current_version = version;
+    println!("Database schema version {} (latest {})", version, status.latest_version);
+
+    if version > status.latest_version {
+        status.error = Some(format!(
+            "This database was created by a newer version of the app (schema version {}, this app supports up to {}). Please update the app.",
+            version, status.latest_version
+        ));
+        return status;
+    }
+
+    let pending: Vec<&Migration> = MIGRATIONS.iter().filter(|m| m.version > version).collect();
+    if pending.is_empty() {
+        return status;
+    }
+
+    match backup_before_migration(&conn, db_path, version) {
+        Ok(path) => status.backup_path = path.map(|p| p.to_string_lossy().to_string()),
+        Err(e) => {
+            status.error = Some(format!("Could not back up the database before upgrading it, so no changes were made: {}", e));
+            return status;
+        }
+    }
+
+    for migration in pending {
+        println!("Applying migration {}: {}", migration.version, migration.description);
+        let result = conn.transaction().and_then(|tx| {
+            (migration.up)(&tx)?;
+            tx.pragma_update(None, "user_version", migration.version)?;
+            tx.commit()
+        });
+
+        if let Err(e) = result {
+            let backup_note = match &status.backup_path {
+                Some(path) => format!(" A backup made before upgrading is at {}.", path),
+                None => String::new(),
+            };
+            status.error = Some(format!(
+                "Database upgrade step {} ({}) failed: {}. The database was left at version {}.{}",
+                migration.version, migration.description, e, status.current_version, backup_note
+            ));
+            println!("{}", status.error.as_ref().unwrap());
+            return status;
+        }
+
+        status.current_version = migration.version;
+        status.applied.push(migration.version);
+    }
+
+    println!("Database migrated to version {}", status.current_version);
+    status
+}
+
+// 確認資料庫結構為最新版本，否則回傳可顯示給使用者的錯誤
+pub fn check_schema(conn: &Connection) -> Result<(), AppError> {
+    let version = user_version(conn)?;
+    let latest = latest_version();
+    if version != latest {
+        return Err(AppError::Database {
+            message: format!(
+                "Database schema is at version {} but this app needs version {}. The upgrade did not complete; see the startup error for details.",
+                version, latest
+            ),
+            busy: false,
+        });
+    }
+    Ok(())
+}
+
+// 獲取啟動時的遷移結果
+#[tauri::command]
+pub fn get_migration_status(state: tauri::State<'_, MigrationState>) -> Result<MigrationStatus, AppError> {
+    Ok(state.0.lock().unwrap().clone())
+}
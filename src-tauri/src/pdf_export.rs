@@ -0,0 +1,623 @@
+use owned_ttf_parser::{AsFaceRef, OwnedFace};
+use printpdf::{
+    BuiltinFont, CustomPdfConformance, IndirectFontRef, Line, Mm, PdfConformance, PdfDocument,
+    PdfDocumentReference, PdfLayerReference, PdfPageIndex, Point,
+};
+use pulldown_cmark::{Event, Options, Parser, Tag};
+use serde::Deserialize;
+use std::fs;
+use std::io::{BufWriter, Cursor};
+use std::path::{Path, PathBuf};
+
+use crate::SqliteState;
+use crate::db::{self, Chapter, Project};
+use crate::error::AppError;
+use crate::export_common::{chapter_title, default_export_path, is_cjk};
+use crate::markdown_export::{self, strip_tags, strip_title_heading};
+
+// 標準稿件格式 (standard manuscript format) 的 PDF 匯出
+// US Letter, 1" margins, 12pt Courier, double spaced, header with surname / title / page on every page but the first.
+const PAGE_WIDTH: f32 = 612.0;
+const PAGE_HEIGHT: f32 = 792.0;
+const MARGIN: f32 = 72.0;
+const TEXT_WIDTH: f32 = PAGE_WIDTH - 2.0 * MARGIN;
+const FONT_SIZE: f32 = 12.0;
+const LEADING: f32 = 24.0;
+const CONTACT_LEADING: f32 = 14.0;
+const INDENT: f32 = 36.0;
+const HEADER_BASELINE: f32 = PAGE_HEIGHT - 48.0;
+const FIRST_BASELINE: f32 = PAGE_HEIGHT - MARGIN - FONT_SIZE;
+// Chapters open about a third of the way down the page
+const CHAPTER_BASELINE: f32 = PAGE_HEIGHT * 2.0 / 3.0;
+// Courier advances 600 units per 1000
+const COURIER_ADVANCE: f32 = 0.6;
+// Characters Courier can show through WinAnsiEncoding beyond ASCII and Latin-1
+const WIN_ANSI_EXTRA: &str = "€‚ƒ„…†‡ˆ‰Š‹ŒŽ‘’“”•–—˜™š›œžŸ";
+
+#[derive(Debug, Default, Deserialize)]
+pub struct PdfExportOptions {
+    pub author: Option<String>,
+    // 書名頁左上角的聯絡資訊 (地址、電話、Email)，每行一項
+    pub contact: Option<String>,
+    // TrueType 字型；書中有 Courier 無法顯示的文字 (例如中文) 時必須指定
+    pub font_path: Option<String>,
+    // 未指定時寫到 exports 目錄
+    pub output_path: Option<String>,
+}
+
+// 字寬量測：內建 Courier 或外部 TrueType 字型
+struct Metrics {
+    face: Option<OwnedFace>,
+}
+
+impl Metrics {
+    fn char_width(&self, c: char) -> f32 {
+        match &self.face {
+            None => COURIER_ADVANCE * FONT_SIZE,
+            Some(face) => {
+                let face = face.as_face_ref();
+                let advance = face.glyph_index(c)
+                    .and_then(|glyph| face.glyph_hor_advance(glyph))
+                    .unwrap_or(0);
+                advance as f32 * FONT_SIZE / face.units_per_em() as f32
+            },
+        }
+    }
+
+    fn width(&self, text: &str) -> f32 {
+        text.chars().map(|c| self.char_width(c)).sum()
+    }
+
+    fn supports(&self, c: char) -> bool {
+        match &self.face {
+            None => matches!(c, ' '..='~' | '\u{A0}'..='\u{FF}') || WIN_ANSI_EXTRA.contains(c),
+            Some(face) => c.is_whitespace() || face.as_face_ref().glyph_index(c).is_some(),
+        }
+    }
+}
+
+// 段落中的一段文字；強調的文字依稿件慣例加底線
+struct Span {
+    text: String,
+    underline: bool,
+}
+
+enum Block {
+    Paragraph { spans: Vec<Span>, first_indent: bool, left: f32 },
+    Centered(String),
+    Blank,
+}
+
+// 排版後一行中的一段
+struct Piece {
+    x: f32,
+    text: String,
+    underline: bool,
+}
+
+// 中日韓標點不放在行首
+fn is_closing_punctuation(text: &str) -> bool {
+    text.chars().count() == 1 && "，。、；：？！」』）》〉】,.;:?!)".contains(text)
+}
+
+// 字數估計：英文以空白分詞，中日韓文字每個字算一個字
+pub(crate) fn count_words(text: &str) -> usize {
+    let mut count = 0;
+    let mut in_word = false;
+    for c in text.chars() {
+        if is_cjk(c) {
+            if c.is_alphanumeric() {
+                count += 1;
+            }
+            in_word = false;
+        } else if c.is_alphanumeric() {
+            if !in_word {
+                count += 1;
+            }
+            in_word = true;
+        } else if c != '\'' && c != '’' && c != '-' {
+            in_word = false;
+        }
+    }
+    count
+}
+
+// 稿件慣例：一萬字以下取到百位，以上取到千位
+fn rounded_word_count(words: usize) -> String {
+    let step = if words < 10_000 { 100 } else { 1_000 };
+    let rounded = ((words + step / 2) / step * step).max(step);
+    let digits = rounded.to_string();
+    let mut out = String::new();
+    for (index, c) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index) % 3 == 0 {
+            out.push(',');
+        }
+        out.push(c);
+    }
+    format!("About {} words", out)
+}
+
+fn plain_text(spans: &[Span]) -> String {
+    spans.iter().map(|s| s.text.as_str()).collect()
+}
+
+// Markdown 轉成稿件的段落
+fn markdown_blocks(markdown: &str) -> Vec<Block> {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+
+    let mut blocks = Vec::new();
+    let mut current: Option<(Vec<Span>, bool, f32)> = None;
+    let mut underline = 0usize;
+    let mut quote_depth = 0usize;
+    // Ordered lists keep their next number; bullet lists are None
+    let mut lists: Vec<Option<u64>> = Vec::new();
+    let mut heading = false;
+    let mut code = false;
+    let mut first_cell = false;
+
+    macro_rules! close {
+        () => {
+            if let Some((spans, first_indent, left)) = current.take() {
+                if heading {
+                    blocks.push(Block::Blank);
+                    blocks.push(Block::Centered(plain_text(&spans)));
+                } else if !plain_text(&spans).trim().is_empty() {
+                    blocks.push(Block::Paragraph { spans, first_indent, left });
+                }
+            }
+        };
+    }
+    macro_rules! open {
+        ($first_indent:expr) => {
+            if current.is_none() {
+                let left = INDENT * (quote_depth + lists.len()) as f32;
+                current = Some((Vec::new(), $first_indent && lists.is_empty(), left));
+            }
+        };
+    }
+    macro_rules! push {
+        ($text:expr) => {{
+            let text: String = $text;
+            if let Some((spans, _, _)) = current.as_mut() {
+                spans.push(Span { text, underline: underline > 0 });
+            }
+        }};
+    }
+
+    for event in Parser::new_ext(markdown, options) {
+        match event {
+            Event::Start(Tag::Paragraph) => open!(true),
+            Event::End(Tag::Paragraph) => close!(),
+            Event::Start(Tag::Heading(..)) => {
+                close!();
+                heading = true;
+                open!(false);
+            },
+            Event::End(Tag::Heading(..)) => {
+                close!();
+                heading = false;
+            },
+            Event::Start(Tag::BlockQuote) => {
+                close!();
+                quote_depth += 1;
+            },
+            Event::End(Tag::BlockQuote) => {
+                close!();
+                quote_depth = quote_depth.saturating_sub(1);
+            },
+            Event::Start(Tag::CodeBlock(_)) => {
+                close!();
+                code = true;
+            },
+            Event::End(Tag::CodeBlock(_)) => code = false,
+            Event::Start(Tag::List(start)) => {
+                close!();
+                lists.push(start);
+            },
+            Event::End(Tag::List(_)) => {
+                close!();
+                lists.pop();
+            },
+            Event::Start(Tag::Item) => {
+                close!();
+                let marker = match lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}. ", *number - 1)
+                    },
+                    _ => "- ".to_string(),
+                };
+                // Items hang from the enclosing level
+                let left = INDENT * (quote_depth + lists.len().saturating_sub(1)) as f32;
+                current = Some((Vec::new(), false, left));
+                push!(marker);
+            },
+            Event::End(Tag::Item) => close!(),
+            Event::Start(Tag::TableRow) | Event::Start(Tag::TableHead) => {
+                close!();
+                open!(false);
+                first_cell = true;
+            },
+            Event::End(Tag::TableRow) | Event::End(Tag::TableHead) => close!(),
+            Event::Start(Tag::TableCell) => {
+                if !first_cell {
+                    push!(" | ".to_string());
+                }
+                first_cell = false;
+            },
+            Event::Start(Tag::Emphasis) | Event::Start(Tag::Strong) => underline += 1,
+            Event::End(Tag::Emphasis) | Event::End(Tag::Strong) => underline = underline.saturating_sub(1),
+            Event::Start(_) | Event::End(_) => {},
+            Event::Text(text) if code => {
+                for line in text.trim_end_matches('\n').split('\n') {
+                    blocks.push(Block::Paragraph {
+                        spans: vec![Span { text: line.to_string(), underline: false }],
+                        first_indent: false,
+                        left: INDENT * (quote_depth + lists.len() + 1) as f32,
+                    });
+                }
+            },
+            Event::Text(text) | Event::Code(text) => {
+                open!(true);
+                push!(text.to_string());
+            },
+            Event::Html(raw) => {
+                let text = strip_tags(&raw);
+                if !text.trim().is_empty() {
+                    open!(true);
+                    push!(text);
+                }
+            },
+            Event::SoftBreak => push!(" ".to_string()),
+            Event::HardBreak => {
+                let left = current.as_ref().map(|(_, _, left)| *left);
+                close!();
+                if let Some(left) = left {
+                    current = Some((Vec::new(), false, left));
+                }
+            },
+            // 場景分隔
+            Event::Rule => {
+                close!();
+                blocks.push(Block::Centered("#".to_string()));
+            },
+            Event::TaskListMarker(checked) => push!(if checked { "[x] " } else { "[ ] " }.to_string()),
+            Event::FootnoteReference(name) => push!(format!("[{}]", name)),
+        }
+    }
+    close!();
+    blocks
+}
+
+// 斷行：英文在空白處斷開，中日韓文字可在任兩字之間斷開
+fn layout(spans: &[Span], metrics: &Metrics, left: f32, first_indent: f32) -> Vec<Vec<Piece>> {
+    // (text, underline, is_space) atoms
+    let mut atoms: Vec<(String, bool, bool)> = Vec::new();
+    for span in spans {
+        for c in span.text.chars() {
+            let space = c.is_whitespace();
+            let c = if space { ' ' } else { c };
+            match atoms.last_mut() {
+                Some((text, underline, is_space))
+                    if *underline == span.underline && *is_space == space && !is_cjk(c)
+                        && !text.chars().last().is_some_and(is_cjk) =>
+                {
+                    if !space {
+                        text.push(c);
+                    }
+                },
+                _ => atoms.push((c.to_string(), span.underline, space)),
+            }
+        }
+    }
+
+    let right = MARGIN + TEXT_WIDTH;
+    let mut lines = Vec::new();
+    let mut line: Vec<Piece> = Vec::new();
+    let mut x = MARGIN + left + first_indent;
+    let mut pending_space: Option<bool> = None;
+
+    for (text, underline, space) in atoms {
+        if space {
+            if !line.is_empty() {
+                pending_space = Some(underline);
+            }
+            continue;
+        }
+        let width = metrics.width(&text);
+        let space_width = if pending_space.is_some() { metrics.width(" ") } else { 0.0 };
+        if x + space_width + width > right && !line.is_empty() && !is_closing_punctuation(&text) {
+            lines.push(std::mem::take(&mut line));
+            x = MARGIN + left;
+            pending_space = None;
+        }
+
+        if let Some(space_underline) = pending_space.take() {
+            // Keep underlines continuous across the space between two underlined words
+            let joined = space_underline && underline;
+            match line.last_mut() {
+                Some(piece) if piece.underline == joined => piece.text.push(' '),
+                _ => line.push(Piece { x, text: " ".to_string(), underline: joined }),
+            }
+            x += metrics.width(" ");
+        }
+
+        // Words wider than the line are split wherever they overflow
+        for c in text.chars() {
+            let char_width = metrics.char_width(c);
+            if x + char_width > right && !line.is_empty() && width > TEXT_WIDTH - left {
+                lines.push(std::mem::take(&mut line));
+                x = MARGIN + left;
+            }
+            match line.last_mut() {
+                Some(piece) if piece.underline == underline => piece.text.push(c),
+                _ => line.push(Piece { x, text: c.to_string(), underline }),
+            }
+            x += char_width;
+        }
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+fn center(lines: &mut [Vec<Piece>], metrics: &Metrics) {
+    for line in lines.iter_mut() {
+        let (Some(first), Some(last)) = (line.first(), line.last()) else { continue };
+        let width = last.x + metrics.width(&last.text) - first.x;
+        let shift = MARGIN + (TEXT_WIDTH - width) / 2.0 - first.x;
+        for piece in line.iter_mut() {
+            piece.x += shift;
+        }
+    }
+}
+
+fn mm(points: f32) -> Mm {
+    Mm(points * 25.4 / 72.0)
+}
+
+// 逐頁輸出
+struct ManuscriptWriter {
+    doc: PdfDocumentReference,
+    font: IndirectFontRef,
+    metrics: Metrics,
+    page: PdfPageIndex,
+    layer: PdfLayerReference,
+    header: String,
+    page_number: usize,
+    y: f32,
+}
+
+impl ManuscriptWriter {
+    fn draw(&self, pieces: &[Piece], y: f32) {
+        for piece in pieces {
+            self.layer.use_text(piece.text.as_str(), FONT_SIZE, mm(piece.x), mm(y), &self.font);
+            if piece.underline {
+                let end = piece.x + self.metrics.width(piece.text.trim_end());
+                self.layer.add_line(Line {
+                    points: vec![(Point::new(mm(piece.x), mm(y - 2.0)), false), (Point::new(mm(end), mm(y - 2.0)), false)],
+                    is_closed: false,
+                });
+            }
+        }
+    }
+
+    fn draw_text(&self, text: &str, x: f32, y: f32) {
+        self.draw(&[Piece { x, text: text.to_string(), underline: false }], y);
+    }
+
+    // 新頁面與頁首 (姓 / 書名 / 頁碼)
+    fn new_page(&mut self) {
+        let (page, layer) = self.doc.add_page(mm(PAGE_WIDTH), mm(PAGE_HEIGHT), "Text");
+        self.page = page;
+        self.layer = self.doc.get_page(page).get_layer(layer);
+        self.layer.set_outline_thickness(0.5);
+        self.page_number += 1;
+        let header = format!("{}{}", self.header, self.page_number);
+        let x = MARGIN + TEXT_WIDTH - self.metrics.width(&header);
+        self.draw_text(&header, x, HEADER_BASELINE);
+        self.y = FIRST_BASELINE;
+    }
+
+    fn line(&mut self, pieces: &[Piece]) {
+        if self.y < MARGIN {
+            self.new_page();
+        }
+        self.draw(pieces, self.y);
+        self.y -= LEADING;
+    }
+
+    fn blank(&mut self) {
+        // A blank line at the top of a page is dropped
+        if self.y < FIRST_BASELINE && self.y >= MARGIN {
+            self.y -= LEADING;
+        }
+    }
+
+    fn block(&mut self, block: &Block) {
+        match block {
+            Block::Paragraph { spans, first_indent, left } => {
+                let indent = if *first_indent { INDENT } else { 0.0 };
+                for line in layout(spans, &self.metrics, *left, indent) {
+                    self.line(&line);
+                }
+            },
+            Block::Centered(text) => {
+                let mut lines = layout(&[Span { text: text.clone(), underline: false }], &self.metrics, 0.0, 0.0);
+                center(&mut lines, &self.metrics);
+                for line in lines {
+                    self.line(&line);
+                }
+            },
+            Block::Blank => self.blank(),
+        }
+    }
+
+    fn chapter(&mut self, chapter: &Chapter) {
+        self.new_page();
+        self.doc.add_bookmark(chapter_title(chapter), self.page);
+        self.y = CHAPTER_BASELINE;
+        self.block(&Block::Centered(format!("Chapter {}", chapter.chapter_number)));
+        if !chapter.title.trim().is_empty() {
+            self.block(&Block::Centered(chapter.title.trim().to_string()));
+        }
+        self.blank();
+        for block in markdown_blocks(strip_title_heading(&chapter.content, &chapter.title)) {
+            self.block(&block);
+        }
+    }
+}
+
+// 頁首用的書名關鍵字 (最多三個英文字，或前八個中日韓文字)
+fn header_title(title: &str) -> String {
+    if title.chars().any(is_cjk) {
+        return title.chars().filter(|c| !c.is_whitespace()).take(8).collect();
+    }
+    title.split_whitespace().take(3).collect::<Vec<_>>().join(" ").to_uppercase()
+}
+
+// 確認字型能顯示書中所有文字
+fn check_characters(metrics: &Metrics, texts: &[&str]) -> Result<(), AppError> {
+    let mut missing: Vec<char> = Vec::new();
+    for text in texts {
+        for c in text.chars() {
+            if !c.is_control() && !metrics.supports(c) && !missing.contains(&c) {
+                missing.push(c);
+            }
+        }
+    }
+    if missing.is_empty() {
+        return Ok(());
+    }
+    let sample: String = missing.iter().take(8).map(|c| c.to_string()).collect::<Vec<_>>().join(" ");
+    if metrics.face.is_none() {
+        Err(AppError::validation(format!(
+            "This book contains characters that Courier cannot display ({}). Choose a TrueType font that covers them.",
+            sample
+        )))
+    } else {
+        Err(AppError::validation(format!("The selected font cannot display some characters in this book ({})", sample)))
+    }
+}
+
+fn render_manuscript(project: &Project, chapters: &[Chapter], options: &PdfExportOptions, path: &Path) -> Result<usize, AppError> {
+    let font_data = match options.font_path.as_deref().filter(|p| !p.trim().is_empty()) {
+        Some(font_path) => Some(fs::read(font_path).map_err(|e| AppError::validation(format!("Cannot read font {}: {}", font_path, e)))?),
+        None => None,
+    };
+    let metrics = Metrics {
+        face: match &font_data {
+            Some(data) => Some(OwnedFace::from_vec(data.clone(), 0).map_err(|e| AppError::validation(format!("Unsupported font: {}", e)))?),
+            None => None,
+        },
+    };
+
+    let author = options.author.as_deref().map(str::trim).filter(|a| !a.is_empty());
+    let contact = options.contact.as_deref().unwrap_or("");
+    let mut texts = vec![project.title.as_str(), contact, author.unwrap_or("")];
+    for chapter in chapters {
+        texts.push(&chapter.title);
+        texts.push(&chapter.content);
+    }
+    check_characters(&metrics, &texts)?;
+
+    let words: usize = chapters.iter().map(|c| count_words(strip_title_heading(&c.content, &c.title))).sum();
+
+    let (doc, page, layer) = PdfDocument::new(project.title.as_str(), mm(PAGE_WIDTH), mm(PAGE_HEIGHT), "Text");
+    // Courier is one of the standard PDF fonts and is not embedded
+    let doc = doc.with_conformance(PdfConformance::Custom(CustomPdfConformance {
+        allows_default_fonts: true,
+        ..Default::default()
+    }));
+    let doc = match author {
+        Some(author) => doc.with_author(author),
+        None => doc,
+    };
+    let doc = match project.category.as_deref().filter(|c| !c.trim().is_empty()) {
+        Some(category) => doc.with_subject(category),
+        None => doc,
+    };
+    let doc = doc.with_keywords(markdown_export::split_keywords(&project.keywords));
+    let font = match font_data {
+        Some(data) => doc.add_external_font(Cursor::new(data)),
+        None => doc.add_builtin_font(BuiltinFont::Courier),
+    }.map_err(|e| AppError::Internal(format!("Failed to load font: {}", e)))?;
+
+    let surname = author.and_then(|a| a.split_whitespace().last()).map(|s| format!("{} / ", s)).unwrap_or_default();
+    let title_layer = doc.get_page(page).get_layer(layer);
+    let mut writer = ManuscriptWriter {
+        header: format!("{}{} / ", surname, header_title(&project.title)),
+        doc,
+        font,
+        metrics,
+        page,
+        layer: title_layer,
+        page_number: 0,
+        y: FIRST_BASELINE,
+    };
+
+    // 書名頁：左上聯絡資訊，右上字數，書名與作者置中 (不編頁碼)
+    let mut y = FIRST_BASELINE;
+    for line in author.into_iter().chain(contact.lines().map(str::trim).filter(|l| !l.is_empty())) {
+        writer.draw_text(line, MARGIN, y);
+        y -= CONTACT_LEADING;
+    }
+    let mut right_lines = vec![rounded_word_count(words)];
+    if let Some(category) = project.category.as_deref().map(str::trim).filter(|c| !c.is_empty()) {
+        right_lines.push(category.to_string());
+    }
+    for (index, text) in right_lines.iter().enumerate() {
+        let x = MARGIN + TEXT_WIDTH - writer.metrics.width(text);
+        writer.draw_text(text, x, FIRST_BASELINE - CONTACT_LEADING * index as f32);
+    }
+    writer.y = PAGE_HEIGHT / 2.0 + LEADING;
+    writer.block(&Block::Centered(project.title.trim().to_string()));
+    if let Some(author) = author {
+        writer.blank();
+        writer.block(&Block::Centered(format!("by {}", author)));
+    }
+
+    for chapter in chapters {
+        writer.chapter(chapter);
+    }
+    writer.blank();
+    writer.block(&Block::Centered("END".to_string()));
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let file = fs::File::create(path)?;
+    if let Err(e) = writer.doc.save(&mut BufWriter::new(file)) {
+        let _ = fs::remove_file(path);
+        return Err(AppError::Internal(format!("Failed to write PDF: {}", e)));
+    }
+    Ok(words)
+}
+
+// 匯出書籍專案為標準稿件格式的 PDF，回傳檔案路徑
+#[tauri::command]
+pub async fn export_project_pdf(project_id: i64, options: Option<PdfExportOptions>, state: tauri::State<'_, SqliteState>) -> Result<String, AppError> {
+    let pool = state.inner().clone();
+    let options = options.unwrap_or_default();
+    state.run(move |conn| {
+        let project = db::load_project(conn, project_id)?;
+        if project.type_ != "book" {
+            return Err(AppError::validation("Manuscript PDF export is only available for book projects"));
+        }
+        let chapters = db::load_chapters(conn, project_id)?;
+        if chapters.is_empty() {
+            return Err(AppError::validation("This book has no chapters to export"));
+        }
+
+        let path = match options.output_path.as_deref().filter(|p| !p.trim().is_empty()) {
+            Some(path) => PathBuf::from(path),
+            None => default_export_path(&pool, "manuscript_", &project.title, &format!("book-{}", project_id), ".pdf")?,
+        };
+        let words = render_manuscript(&project, &chapters, &options, &path)?;
+        println!("Exported manuscript of project {} ({} words) to {:?}", project_id, words, path);
+        Ok(path.to_string_lossy().to_string())
+    }).await
+}
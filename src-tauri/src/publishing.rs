@@ -0,0 +1,1100 @@
+use async_trait::async_trait;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{Local, Utc};
+use hmac::{Hmac, Mac};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::collections::HashSet;
+use std::time::Duration;
+
+use crate::SqliteState;
+use crate::db::{self, Project};
+use crate::error::AppError;
+use crate::markdown_export::{excerpt, markdown_to_xhtml, slugify, split_keywords, strip_title_heading};
+use crate::secrets;
+use crate::wordpress;
+
+const SETTING_PREFIX: &str = "publishing.project";
+pub(crate) const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_DEVTO_API_URL: &str = "https://dev.to/api";
+const DEFAULT_HASHNODE_API_URL: &str = "https://gql.hashnode.com";
+const DEVTO_MAX_TAGS: usize = 4;
+const HASHNODE_MAX_TAGS: usize = 5;
+const GHOST_TOKEN_LIFETIME_SECS: i64 = 300;
+// Hashnode 草稿與文章的 ID 不同，草稿以此前綴記錄
+const HASHNODE_DRAFT_PREFIX: &str = "draft:";
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum PlatformKind {
+    WordPress,
+    Ghost,
+    DevTo,
+    Hashnode,
+}
+
+impl PlatformKind {
+    pub fn key(&self) -> &'static str {
+        match self {
+            PlatformKind::WordPress => wordpress::PLATFORM,
+            PlatformKind::Ghost => "ghost",
+            PlatformKind::DevTo => "devto",
+            PlatformKind::Hashnode => "hashnode",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PlatformKind::WordPress => "WordPress",
+            PlatformKind::Ghost => "Ghost",
+            PlatformKind::DevTo => "Dev.to",
+            PlatformKind::Hashnode => "Hashnode",
+        }
+    }
+
+    // 專案設定中的平台名稱是自由輸入，例如 "Dev.to"、"dev to"、"WordPress.com"
+    pub fn parse(name: &str) -> Option<PlatformKind> {
+        let normalized: String = name.chars()
+            .filter(|c| c.is_alphanumeric())
+            .flat_map(char::to_lowercase)
+            .collect();
+        match normalized.as_str() {
+            n if n.starts_with("wordpress") => Some(PlatformKind::WordPress),
+            "ghost" | "ghostorg" | "ghostio" => Some(PlatformKind::Ghost),
+            "devto" | "dev" | "forem" => Some(PlatformKind::DevTo),
+            "hashnode" | "hashnodedev" | "hashnodecom" => Some(PlatformKind::Hashnode),
+            _ => None,
+        }
+    }
+}
+
+// 專案的主要平台：publishing_platform 為 "custom" 時改看 custom_platform
+pub(crate) fn project_platform(project: &Project) -> Option<PlatformKind> {
+    project.publishing_platform.as_deref().and_then(PlatformKind::parse)
+        .or_else(|| project.custom_platform.as_deref().and_then(PlatformKind::parse))
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PostStatus {
+    #[default]
+    Draft,
+    Publish,
+}
+
+impl PostStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PostStatus::Draft => "draft",
+            PostStatus::Publish => "publish",
+        }
+    }
+}
+
+// 每個專案、每個平台各自的設定；api_key 另存為加密的 secret，不會回傳給前端
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct PlatformConfig {
+    // Ghost 的網站網址；Dev.to 與 Hashnode 未設定時使用官方 API，WordPress 未設定時使用專案的 wordpress_url
+    #[serde(default)]
+    pub api_url: Option<String>,
+    // Ghost Admin API key (id:secret)、Dev.to API key 或 Hashnode personal access token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_key: Option<String>,
+    // Hashnode 的 publication ID
+    #[serde(default)]
+    pub publication_id: Option<String>,
+    // 呼叫時未指定發佈狀態時使用
+    #[serde(default)]
+    pub default_status: Option<PostStatus>,
+}
+
+pub(crate) fn project_config_prefix(project_id: i64) -> String {
+    format!("{}.{}.", SETTING_PREFIX, project_id)
+}
+
+// 所有專案發佈設定鍵的共同前綴，後接專案 ID
+pub(crate) fn project_settings_prefix() -> String {
+    format!("{}.", SETTING_PREFIX)
+}
+
+fn config_key(project_id: i64, kind: PlatformKind) -> String {
+    format!("{}{}", project_config_prefix(project_id), kind.key())
+}
+
+fn load_config(conn: &Connection, project_id: i64, kind: PlatformKind) -> Result<Option<PlatformConfig>, AppError> {
+    let value: Option<String> = conn.query_row(
+        "SELECT value FROM settings WHERE key = ?1",
+        [config_key(project_id, kind)],
+        |row| row.get(0),
+    ).optional()?;
+    match value {
+        Some(value) => Ok(Some(serde_json::from_str(&value)?)),
+        None => Ok(None),
+    }
+}
+
+fn load_api_key(conn: &Connection, project_id: i64, kind: PlatformKind) -> Result<String, AppError> {
+    secrets::get_secret(conn, &secrets::provider_secret_name(&config_key(project_id, kind)))?
+        .filter(|key| !key.trim().is_empty())
+        .ok_or_else(|| AppError::ApiKeyMissing { provider: kind.label().to_string() })
+}
+
+// 平台網址檢查；去掉結尾的斜線
+// Credentials go out with every request, so plain http is only allowed for a site on this machine
+pub(crate) fn http_url(url: &str, label: &str) -> Result<String, AppError> {
+    let url = url.trim().trim_end_matches('/');
+    let parsed = reqwest::Url::parse(url)
+        .map_err(|e| AppError::validation(format!("The {} URL is not valid: {}", label, e)))?;
+    let local = matches!(parsed.host_str(), Some("localhost" | "127.0.0.1" | "[::1]"));
+    match parsed.scheme() {
+        "https" => Ok(url.to_string()),
+        "http" if local => Ok(url.to_string()),
+        "http" => Err(AppError::validation(format!("The {} URL must use https:// (http:// is only allowed for localhost)", label))),
+        _ => Err(AppError::validation(format!("The {} URL must start with https://", label))),
+    }
+}
+
+pub(crate) fn http_client() -> Result<reqwest::Client, AppError> {
+    reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| AppError::Internal(format!("Failed to create HTTP client: {}", e)))
+}
+
+// 回傳 (狀態碼, Retry-After, 內容)
+pub(crate) async fn send(label: &str, request: reqwest::RequestBuilder) -> Result<(u16, Option<u64>, String), AppError> {
+    let response = request.send().await.map_err(|e| {
+        println!("{} request error: {}", label, e);
+        AppError::Upstream { message: format!("Failed to reach {}: {}", label, e.without_url()), status: None }
+    })?;
+    let status = response.status().as_u16();
+    let retry_after = response.headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<u64>().ok());
+    let body = response.text().await.map_err(|e| AppError::Upstream {
+        message: format!("Failed to read the {} response: {}", label, e),
+        status: Some(status),
+    })?;
+    println!("{} response status: {}", label, status);
+    Ok((status, retry_after, body))
+}
+
+pub(crate) fn parse_json(label: &str, body: &str) -> Result<Value, AppError> {
+    serde_json::from_str(body).map_err(|e| AppError::Parse(format!("Failed to parse the {} response: {}", label, e)))
+}
+
+// 各平台的錯誤格式：WordPress {message}、Ghost {errors: [{message, context}]}、Dev.to {error}
+pub(crate) fn http_error(label: &str, status: u16, retry_after: Option<u64>, body: &str) -> AppError {
+    println!("{} error response ({}): {}", label, status, body);
+    let value = serde_json::from_str::<Value>(body).unwrap_or(Value::Null);
+    let message = value.get("message").and_then(|m| m.as_str()).map(str::to_string)
+        .or_else(|| value.pointer("/errors/0").map(|e| {
+            let message = e.get("message").and_then(|m| m.as_str()).unwrap_or_default();
+            match e.get("context").and_then(|c| c.as_str()) {
+                Some(context) if !context.is_empty() => format!("{} {}", message, context),
+                _ => message.to_string(),
+            }
+        }))
+        .or_else(|| value.get("error").and_then(|m| m.as_str()).map(str::to_string))
+        .unwrap_or_else(|| body.chars().take(300).collect());
+    match status {
+        429 => AppError::RateLimited { message: format!("{} rate limit exceeded: {}", label, message), retry_after, quota_exhausted: false },
+        401 | 403 => AppError::Upstream { message: format!("{} rejected the credentials: {}", label, message), status: Some(status) },
+        _ => AppError::Upstream { message: format!("{} returned {}: {}", label, status, message), status: Some(status) },
+    }
+}
+
+// 要發佈的內容，由文章與專案組成
+pub struct PublishRequest {
+    pub title: String,
+    pub markdown: String,
+    pub html: String,
+    pub excerpt: String,
+    pub tags: Vec<String>,
+    pub categories: Vec<String>,
+    pub status: PostStatus,
+    // 轉貼到其他平台時指向原文
+    pub canonical_url: Option<String>,
+}
+
+pub struct RemotePost {
+    pub id: String,
+    pub url: String,
+    pub status: String,
+    pub created: bool,
+}
+
+#[async_trait]
+pub trait Publisher: Send + Sync {
+    fn platform(&self) -> PlatformKind;
+
+    // 建立文章，或在 remote_id 有值時更新同一篇；遠端文章已刪除時重新建立
+    async fn publish(&self, post: &PublishRequest, remote_id: Option<&str>) -> Result<RemotePost, AppError>;
+}
+
+pub(crate) fn build_publisher(conn: &Connection, project: &Project, kind: PlatformKind) -> Result<Box<dyn Publisher>, AppError> {
+    let project_id = project.id.unwrap_or_default();
+    let config = load_config(conn, project_id, kind)?.unwrap_or_default();
+    let api_url = config.api_url.as_deref().map(str::trim).filter(|u| !u.is_empty());
+
+    let publisher: Box<dyn Publisher> = match kind {
+        PlatformKind::WordPress => {
+            let site = api_url.map(str::to_string).or_else(|| project.wordpress_url.clone());
+            Box::new(wordpress::WordPressPublisher::new(conn, project_id, &site)?)
+        },
+        PlatformKind::Ghost => {
+            let url = api_url.ok_or_else(|| AppError::validation("Set the Ghost site URL in the publishing settings"))?;
+            Box::new(GhostPublisher::new(http_url(url, "Ghost")?, &load_api_key(conn, project_id, kind)?)?)
+        },
+        PlatformKind::DevTo => {
+            let base = http_url(api_url.unwrap_or(DEFAULT_DEVTO_API_URL), "Dev.to")?;
+            Box::new(DevToPublisher { base, api_key: load_api_key(conn, project_id, kind)?, client: http_client()? })
+        },
+        PlatformKind::Hashnode => {
+            let endpoint = http_url(api_url.unwrap_or(DEFAULT_HASHNODE_API_URL), "Hashnode")?;
+            let publication_id = config.publication_id.as_deref().map(str::trim).filter(|id| !id.is_empty())
+                .ok_or_else(|| AppError::validation("Set the Hashnode publication ID in the publishing settings"))?
+                .to_string();
+            Box::new(HashnodePublisher { endpoint, token: load_api_key(conn, project_id, kind)?, publication_id, client: http_client()? })
+        },
+    };
+    Ok(publisher)
+}
+
+// 分類在前 (Ghost 的第一個標籤是主要標籤)，忽略大小寫去除重複
+fn merged_tags(post: &PublishRequest) -> Vec<String> {
+    let mut seen = HashSet::new();
+    post.categories.iter().chain(post.tags.iter())
+        .filter(|name| seen.insert(name.trim().to_lowercase()))
+        .cloned()
+        .collect()
+}
+
+struct GhostPublisher {
+    base: String,
+    key_id: String,
+    secret: Vec<u8>,
+    client: reqwest::Client,
+}
+
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len()).step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+impl GhostPublisher {
+    fn new(base: String, admin_key: &str) -> Result<Self, AppError> {
+        // Admin API key 格式為 "{id}:{hex secret}"
+        let (key_id, secret) = admin_key.trim().split_once(':')
+            .and_then(|(id, secret)| Some((id.to_string(), decode_hex(secret)?)))
+            .filter(|(id, secret)| !id.is_empty() && !secret.is_empty())
+            .ok_or_else(|| AppError::validation("The Ghost Admin API key must look like {id}:{secret}"))?;
+        let base = base.trim_end_matches("/ghost/api/admin").trim_end_matches("/ghost").to_string();
+        Ok(GhostPublisher { base, key_id, secret, client: http_client()? })
+    }
+
+    // 每次請求產生短期的 HS256 JWT
+    fn token(&self) -> Result<String, AppError> {
+        let header = json!({ "alg": "HS256", "typ": "JWT", "kid": self.key_id });
+        let iat = Utc::now().timestamp();
+        let payload = json!({ "iat": iat, "exp": iat + GHOST_TOKEN_LIFETIME_SECS, "aud": "/admin/" });
+        let signing_input = format!("{}.{}", URL_SAFE_NO_PAD.encode(header.to_string()), URL_SAFE_NO_PAD.encode(payload.to_string()));
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.secret)
+            .map_err(|e| AppError::Internal(format!("Invalid Ghost Admin API key: {}", e)))?;
+        mac.update(signing_input.as_bytes());
+        Ok(format!("{}.{}", signing_input, URL_SAFE_NO_PAD.encode(mac.finalize().into_bytes())))
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> Result<reqwest::RequestBuilder, AppError> {
+        Ok(self.client.request(method, format!("{}/ghost/api/admin/{}", self.base, path))
+            .header(reqwest::header::AUTHORIZATION, format!("Ghost {}", self.token()?))
+            .header("Accept-Version", "v5.0"))
+    }
+
+    fn remote_post(response: &Value, created: bool) -> Result<RemotePost, AppError> {
+        let post = response.pointer("/posts/0")
+            .ok_or_else(|| AppError::Parse("Ghost response has no post".to_string()))?;
+        let id = post.get("id").and_then(|id| id.as_str())
+            .ok_or_else(|| AppError::Parse("Ghost response has no post id".to_string()))?;
+        Ok(RemotePost {
+            id: id.to_string(),
+            url: post.get("url").and_then(|u| u.as_str()).unwrap_or_default().to_string(),
+            status: post.get("status").and_then(|s| s.as_str()).unwrap_or_default().to_string(),
+            created,
+        })
+    }
+}
+
+#[async_trait]
+impl Publisher for GhostPublisher {
+    fn platform(&self) -> PlatformKind {
+        PlatformKind::Ghost
+    }
+
+    async fn publish(&self, post: &PublishRequest, remote_id: Option<&str>) -> Result<RemotePost, AppError> {
+        let label = self.platform().label();
+        let tags: Vec<Value> = merged_tags(post).into_iter().map(|name| json!({ "name": name })).collect();
+        let mut body = json!({
+            "title": post.title,
+            "html": post.html,
+            "status": if post.status == PostStatus::Publish { "published" } else { "draft" },
+            "tags": tags,
+            "custom_excerpt": post.excerpt.chars().take(300).collect::<String>(),
+        });
+        if let Some(canonical_url) = &post.canonical_url {
+            body["canonical_url"] = json!(canonical_url);
+        }
+
+        if let Some(id) = remote_id {
+            // Ghost 需要目前的 updated_at 以避免覆寫其他編輯
+            let (status, retry_after, response) = send(label, self.request(reqwest::Method::GET, &format!("posts/{}/", id))?).await?;
+            match status {
+                200..=299 => {
+                    let current = parse_json(label, &response)?;
+                    body["updated_at"] = current.pointer("/posts/0/updated_at").cloned().unwrap_or(Value::Null);
+                    let (status, retry_after, response) = send(label,
+                        self.request(reqwest::Method::PUT, &format!("posts/{}/?source=html", id))?.json(&json!({ "posts": [body] }))
+                    ).await?;
+                    if !(200..300).contains(&status) {
+                        return Err(http_error(label, status, retry_after, &response));
+                    }
+                    return Self::remote_post(&parse_json(label, &response)?, false);
+                },
+                404 => println!("Ghost post {} no longer exists; creating a new post", id),
+                _ => return Err(http_error(label, status, retry_after, &response)),
+            }
+        }
+
+        let (status, retry_after, response) = send(label,
+            self.request(reqwest::Method::POST, "posts/?source=html")?.json(&json!({ "posts": [body] }))
+        ).await?;
+        if !(200..300).contains(&status) {
+            return Err(http_error(label, status, retry_after, &response));
+        }
+        Self::remote_post(&parse_json(label, &response)?, true)
+    }
+}
+
+struct DevToPublisher {
+    base: String,
+    api_key: String,
+    client: reqwest::Client,
+}
+
+// Dev.to 最多四個標籤，只接受小寫英數字
+fn devto_tags(post: &PublishRequest) -> Vec<String> {
+    let mut seen = HashSet::new();
+    merged_tags(post).iter()
+        .map(|name| name.chars().filter(|c| c.is_ascii_alphanumeric()).collect::<String>().to_lowercase())
+        .filter(|tag| !tag.is_empty() && seen.insert(tag.clone()))
+        .take(DEVTO_MAX_TAGS)
+        .collect()
+}
+
+impl DevToPublisher {
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        self.client.request(method, format!("{}/{}", self.base, path))
+            .header("api-key", &self.api_key)
+            .header(reqwest::header::ACCEPT, "application/vnd.forem.api-v1+json")
+    }
+
+    fn remote_post(response: &Value, created: bool) -> Result<RemotePost, AppError> {
+        let id = response.get("id").and_then(|id| id.as_i64())
+            .ok_or_else(|| AppError::Parse("Dev.to response has no article id".to_string()))?;
+        let published = response.get("published").and_then(|p| p.as_bool()).unwrap_or(false);
+        Ok(RemotePost {
+            id: id.to_string(),
+            url: response.get("url").and_then(|u| u.as_str()).unwrap_or_default().to_string(),
+            status: if published { "published" } else { "draft" }.to_string(),
+            created,
+        })
+    }
+}
+
+#[async_trait]
+impl Publisher for DevToPublisher {
+    fn platform(&self) -> PlatformKind {
+        PlatformKind::DevTo
+    }
+
+    async fn publish(&self, post: &PublishRequest, remote_id: Option<&str>) -> Result<RemotePost, AppError> {
+        let label = self.platform().label();
+        let mut article = json!({
+            "title": post.title,
+            "body_markdown": post.markdown,
+            "published": post.status == PostStatus::Publish,
+            "tags": devto_tags(post),
+            "description": post.excerpt,
+        });
+        if let Some(canonical_url) = &post.canonical_url {
+            article["canonical_url"] = json!(canonical_url);
+        }
+        let body = json!({ "article": article });
+
+        if let Some(id) = remote_id {
+            let (status, retry_after, response) = send(label,
+                self.request(reqwest::Method::PUT, &format!("articles/{}", id)).json(&body)
+            ).await?;
+            match status {
+                200..=299 => return Self::remote_post(&parse_json(label, &response)?, false),
+                404 => println!("Dev.to article {} no longer exists; creating a new article", id),
+                _ => return Err(http_error(label, status, retry_after, &response)),
+            }
+        }
+
+        let (status, retry_after, response) = send(label, self.request(reqwest::Method::POST, "articles").json(&body)).await?;
+        if !(200..300).contains(&status) {
+            return Err(http_error(label, status, retry_after, &response));
+        }
+        Self::remote_post(&parse_json(label, &response)?, true)
+    }
+}
+
+const HASHNODE_CREATE_DRAFT: &str = "mutation CreateDraft($input: CreateDraftInput!) { createDraft(input: $input) { draft { id } } }";
+const HASHNODE_UPDATE_DRAFT: &str = "mutation UpdateDraft($input: UpdateDraftInput!) { updateDraft(input: $input) { draft { id } } }";
+const HASHNODE_PUBLISH_DRAFT: &str = "mutation PublishDraft($input: PublishDraftInput!) { publishDraft(input: $input) { post { id url } } }";
+const HASHNODE_PUBLISH_POST: &str = "mutation PublishPost($input: PublishPostInput!) { publishPost(input: $input) { post { id url } } }";
+const HASHNODE_UPDATE_POST: &str = "mutation UpdatePost($input: UpdatePostInput!) { updatePost(input: $input) { post { id url } } }";
+
+struct HashnodePublisher {
+    endpoint: String,
+    token: String,
+    publication_id: String,
+    client: reqwest::Client,
+}
+
+impl HashnodePublisher {
+    // GraphQL 錯誤也可能以 200 回傳；NOT_FOUND 轉為 AppError::NotFound 以便重新建立
+    async fn call(&self, query: &str, input: Value) -> Result<Value, AppError> {
+        let label = self.platform().label();
+        let request = self.client.post(&self.endpoint)
+            .header(reqwest::header::AUTHORIZATION, &self.token)
+            .json(&json!({ "query": query, "variables": { "input": input } }));
+        let (status, retry_after, body) = send(label, request).await?;
+        let value = serde_json::from_str::<Value>(&body).unwrap_or(Value::Null);
+        if let Some(error) = value.pointer("/errors/0") {
+            println!("Hashnode GraphQL error: {}", body);
+            let message = error.get("message").and_then(|m| m.as_str()).unwrap_or("Unknown error").to_string();
+            return Err(match error.pointer("/extensions/code").and_then(|c| c.as_str()) {
+                Some("NOT_FOUND") => AppError::NotFound(message),
+                Some("UNAUTHENTICATED") | Some("FORBIDDEN") => AppError::Upstream { message: format!("Hashnode rejected the token: {}", message), status: Some(status) },
+                _ if status == 429 => AppError::RateLimited { message: format!("Hashnode rate limit exceeded: {}", message), retry_after, quota_exhausted: false },
+                _ => AppError::Upstream { message: format!("Hashnode returned an error: {}", message), status: Some(status) },
+            });
+        }
+        if !(200..300).contains(&status) {
+            return Err(http_error(label, status, retry_after, &body));
+        }
+        value.get("data").cloned().filter(|data| !data.is_null())
+            .ok_or_else(|| AppError::Parse("Hashnode response has no data".to_string()))
+    }
+
+    fn input(&self, post: &PublishRequest) -> Value {
+        let mut seen = HashSet::new();
+        let tags: Vec<Value> = merged_tags(post).into_iter()
+            .map(|name| (slugify(&name), name))
+            .filter(|(slug, _)| !slug.is_empty() && seen.insert(slug.clone()))
+            .take(HASHNODE_MAX_TAGS)
+            .map(|(slug, name)| json!({ "name": name, "slug": slug }))
+            .collect();
+        let mut input = json!({
+            "title": post.title,
+            "contentMarkdown": post.markdown,
+            "tags": tags,
+        });
+        if let Some(canonical_url) = &post.canonical_url {
+            input["originalArticleURL"] = json!(canonical_url);
+        }
+        input
+    }
+
+    fn published(data: &Value, field: &str, created: bool) -> Result<RemotePost, AppError> {
+        let post = data.pointer(&format!("/{}/post", field))
+            .ok_or_else(|| AppError::Parse("Hashnode response has no post".to_string()))?;
+        Ok(RemotePost {
+            id: post.get("id").and_then(|id| id.as_str()).unwrap_or_default().to_string(),
+            url: post.get("url").and_then(|u| u.as_str()).unwrap_or_default().to_string(),
+            status: "published".to_string(),
+            created,
+        })
+    }
+
+    fn draft(data: &Value, field: &str, created: bool) -> Result<RemotePost, AppError> {
+        let id = data.pointer(&format!("/{}/draft/id", field)).and_then(|id| id.as_str())
+            .ok_or_else(|| AppError::Parse("Hashnode response has no draft id".to_string()))?;
+        Ok(RemotePost {
+            id: format!("{}{}", HASHNODE_DRAFT_PREFIX, id),
+            url: format!("https://hashnode.com/draft/{}", id),
+            status: "draft".to_string(),
+            created,
+        })
+    }
+
+    async fn create(&self, post: &PublishRequest) -> Result<RemotePost, AppError> {
+        let mut input = self.input(post);
+        input["publicationId"] = json!(self.publication_id);
+        if post.status == PostStatus::Publish {
+            Self::published(&self.call(HASHNODE_PUBLISH_POST, input).await?, "publishPost", true)
+        } else {
+            Self::draft(&self.call(HASHNODE_CREATE_DRAFT, input).await?, "createDraft", true)
+        }
+    }
+
+    async fn update(&self, post: &PublishRequest, remote_id: &str) -> Result<RemotePost, AppError> {
+        let mut input = self.input(post);
+        match remote_id.strip_prefix(HASHNODE_DRAFT_PREFIX) {
+            Some(draft_id) => {
+                input["id"] = json!(draft_id);
+                let data = self.call(HASHNODE_UPDATE_DRAFT, input).await?;
+                if post.status == PostStatus::Publish {
+                    let data = self.call(HASHNODE_PUBLISH_DRAFT, json!({ "draftId": draft_id })).await?;
+                    return Self::published(&data, "publishDraft", false);
+                }
+                Self::draft(&data, "updateDraft", false)
+            },
+            // 已公開的文章無法改回草稿，只更新內容
+            None => {
+                input["id"] = json!(remote_id);
+                Self::published(&self.call(HASHNODE_UPDATE_POST, input).await?, "updatePost", false)
+            },
+        }
+    }
+}
+
+#[async_trait]
+impl Publisher for HashnodePublisher {
+    fn platform(&self) -> PlatformKind {
+        PlatformKind::Hashnode
+    }
+
+    async fn publish(&self, post: &PublishRequest, remote_id: Option<&str>) -> Result<RemotePost, AppError> {
+        if let Some(id) = remote_id {
+            match self.update(post, id).await {
+                Err(AppError::NotFound(message)) => println!("Hashnode post {} no longer exists ({}); creating a new post", id, message),
+                result => return result,
+            }
+        }
+        self.create(post).await
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PublishResult {
+    pub log_id: i64,
+    pub platform: PlatformKind,
+    pub remote_id: String,
+    pub url: String,
+    pub status: String,
+    // false 表示更新了先前發佈的文章
+    pub created: bool,
+    // 轉貼時指向原文的網址
+    pub canonical_url: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PublishLogEntry {
+    pub id: i64,
+    pub blog_id: i64,
+    pub project_id: i64,
+    pub platform: String,
+    pub action: String,
+    pub status: String,
+    pub post_status: Option<String>,
+    pub remote_id: Option<String>,
+    pub remote_url: Option<String>,
+    pub canonical_url: Option<String>,
+    pub error: Option<String>,
+    pub created_at: String,
+}
+
+// 最近一次成功發佈到同一平台的遠端 ID；沒有紀錄時沿用文章上的遠端 ID
+fn previous_remote_id(conn: &Connection, blog: &db::Blog, kind: PlatformKind) -> Result<Option<String>, AppError> {
+    let logged: Option<String> = conn.query_row(
+        "SELECT remote_id FROM publish_log
+         WHERE blog_id = ?1 AND platform = ?2 AND status = 'success' AND remote_id IS NOT NULL
+         ORDER BY id DESC LIMIT 1",
+        params![blog.id, kind.key()],
+        |row| row.get(0),
+    ).optional()?;
+    Ok(logged.or_else(|| {
+        if blog.remote_platform.as_deref() == Some(kind.key()) { blog.remote_post_id.clone() } else { None }
+    }))
+}
+
+// 發佈文章並記錄結果；WordPress 的命令也走這裡
+pub(crate) async fn publish_blog_to(state: &SqliteState, blog_id: i64, platform: Option<PlatformKind>, status: Option<PostStatus>) -> Result<PublishResult, AppError> {
+    let (blog, project, kind, publisher, default_status, remote_id, is_original) = state.run(move |conn| {
+        let blog = db::load_blog(conn, blog_id)?;
+        let project = db::load_project(conn, blog.project_id)?;
+        let primary = project_platform(&project);
+        let kind = platform.or(primary).ok_or_else(|| AppError::validation(
+            "Choose a platform or set the project's publishing platform to WordPress, Ghost, Dev.to or Hashnode"
+        ))?;
+        let publisher = build_publisher(conn, &project, kind)?;
+        let default_status = load_config(conn, blog.project_id, kind)?.and_then(|c| c.default_status);
+        let remote_id = previous_remote_id(conn, &blog, kind)?;
+        // 主要平台上的文章是原文；沒有主要平台時，第一個發佈的平台就是原文
+        let is_original = match primary {
+            Some(primary) => primary == kind,
+            None => blog.remote_platform.as_deref().is_none_or(|p| p == kind.key()),
+        };
+        Ok::<_, AppError>((blog, project, kind, publisher, default_status, remote_id, is_original))
+    }).await?;
+
+    // 轉貼時以原文網址作為 canonical URL
+    let canonical_url = if is_original {
+        None
+    } else {
+        blog.remote_url.clone()
+            .filter(|url| !url.is_empty() && blog.remote_platform.as_deref() != Some(kind.key()))
+    };
+
+    let status = status.or(default_status).unwrap_or_default();
+    let markdown = strip_title_heading(&blog.content, &blog.title).trim().to_string();
+    let post = PublishRequest {
+        title: blog.title.clone(),
+        html: markdown_to_xhtml(&markdown, 0, false),
+        excerpt: excerpt(&markdown),
+        markdown,
+        tags: split_keywords(&blog.keywords),
+        categories: split_keywords(&project.category),
+        status,
+        canonical_url: canonical_url.clone(),
+    };
+
+    println!("Publishing blog {} to {} as {} (remote id: {:?})", blog_id, kind.label(), status.as_str(), remote_id);
+    let result = publisher.publish(&post, remote_id.as_deref()).await;
+
+    let action = match &result {
+        Ok(remote) => if remote.created { "create" } else { "update" },
+        Err(_) => if remote_id.is_some() { "update" } else { "create" },
+    };
+    let (log_status, logged_id, logged_url, error) = match &result {
+        Ok(remote) => ("success", Some(remote.id.clone()), Some(remote.url.clone()), None),
+        Err(e) => ("failed", remote_id.clone(), None, Some(e.to_string())),
+    };
+    let project_id = blog.project_id;
+    let update_blog = result.is_ok() && is_original;
+    let logged_canonical = canonical_url.clone();
+    let log_id = state.run(move |conn| {
+        let now = Local::now().to_rfc3339();
+        conn.execute(
+            "INSERT INTO publish_log (blog_id, project_id, platform, action, status, post_status, remote_id, remote_url, canonical_url, error, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![blog_id, project_id, kind.key(), action, log_status, status.as_str(), logged_id, logged_url, logged_canonical, error, now],
+        )?;
+        let log_id = conn.last_insert_rowid();
+        // 文章上的遠端網址只記錄原文，轉貼的網址留在紀錄中
+        if update_blog {
+            conn.execute(
+                "UPDATE blogs SET remote_platform = ?1, remote_post_id = ?2, remote_url = ?3, published_at = ?4 WHERE id = ?5",
+                params![kind.key(), logged_id, logged_url, now, blog_id],
+            )?;
+        }
+        Ok::<_, AppError>(log_id)
+    }).await?;
+
+    let remote = result?;
+    println!("{} {} post {} for blog {}: {}", if remote.created { "Created" } else { "Updated" }, kind.label(), remote.id, blog_id, remote.url);
+    Ok(PublishResult {
+        log_id,
+        platform: kind,
+        remote_id: remote.id,
+        url: remote.url,
+        status: remote.status,
+        created: remote.created,
+        canonical_url,
+    })
+}
+
+// 發佈到指定平台 (未指定時使用專案的主要平台)；再次發佈時更新同一篇文章
+#[tauri::command]
+pub async fn publish_blog(blog_id: i64, platform: Option<PlatformKind>, status: Option<PostStatus>, state: tauri::State<'_, SqliteState>) -> Result<PublishResult, AppError> {
+    publish_blog_to(&state, blog_id, platform, status).await
+}
+
+// 取得平台設定 (不回傳 API 金鑰)
+#[tauri::command]
+pub async fn get_publishing_config(project_id: i64, platform: PlatformKind, state: tauri::State<'_, SqliteState>) -> Result<Option<PlatformConfig>, AppError> {
+    state.run(move |conn| {
+        let config = load_config(conn, project_id, platform)?;
+        Ok(config.map(|config| PlatformConfig { api_key: None, ..config }))
+    }).await
+}
+
+// 保存平台設定；api_key 為空時保留原本的金鑰
+#[tauri::command]
+pub async fn set_publishing_config(project_id: i64, platform: PlatformKind, config: PlatformConfig, state: tauri::State<'_, SqliteState>) -> Result<bool, AppError> {
+    if let Some(url) = config.api_url.as_deref().map(str::trim).filter(|u| !u.is_empty()) {
+        http_url(url, platform.label())?;
+    }
+    state.run(move |conn| {
+        db::load_project(conn, project_id)?;
+        let key = config_key(project_id, platform);
+        if let Some(api_key) = config.api_key.as_deref().map(str::trim).filter(|k| !k.is_empty()) {
+            if platform == PlatformKind::Ghost {
+                GhostPublisher::new(String::new(), api_key)?;
+            }
+            secrets::set_secret(conn, &secrets::provider_secret_name(&key), api_key)?;
+        }
+        let value = serde_json::to_string(&PlatformConfig { api_key: None, ..config })?;
+        let now = Local::now().to_rfc3339();
+        conn.execute(
+            "INSERT INTO settings (key, value, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?3)
+             ON CONFLICT(key) DO UPDATE SET value = ?2, updated_at = ?3",
+            params![key, value, now],
+        )?;
+        println!("Saved {} publishing settings for project {}", platform.label(), project_id);
+        Ok(true)
+    }).await
+}
+
+#[tauri::command]
+pub async fn delete_publishing_config(project_id: i64, platform: PlatformKind, state: tauri::State<'_, SqliteState>) -> Result<bool, AppError> {
+    state.run(move |conn| {
+        let key = config_key(project_id, platform);
+        secrets::delete_secret(conn, &secrets::provider_secret_name(&key))?;
+        let deleted = conn.execute("DELETE FROM settings WHERE key = ?1", [key])?;
+        Ok(deleted > 0)
+    }).await
+}
+
+// 文章或專案的發佈紀錄，最新的在前
+#[tauri::command]
+pub async fn get_publish_log(blog_id: Option<i64>, project_id: Option<i64>, state: tauri::State<'_, SqliteState>) -> Result<Vec<PublishLogEntry>, AppError> {
+    if blog_id.is_none() && project_id.is_none() {
+        return Err(AppError::validation("A blog or project is required"));
+    }
+    state.run(move |conn| {
+        let mut stmt = conn.prepare(
+            "SELECT id, blog_id, project_id, platform, action, status, post_status, remote_id, remote_url, canonical_url, error, created_at
+             FROM publish_log
+             WHERE (?1 IS NULL OR blog_id = ?1) AND (?2 IS NULL OR project_id = ?2)
+             ORDER BY id DESC"
+        )?;
+        let entries = stmt.query_map(params![blog_id, project_id], |row| {
+            Ok(PublishLogEntry {
+                id: row.get(0)?,
+                blog_id: row.get(1)?,
+                project_id: row.get(2)?,
+                platform: row.get(3)?,
+                action: row.get(4)?,
+                status: row.get(5)?,
+                post_status: row.get(6)?,
+                remote_id: row.get(7)?,
+                remote_url: row.get(8)?,
+                canonical_url: row.get(9)?,
+                error: row.get(10)?,
+                created_at: row.get(11)?,
+            })
+        })?.collect::<Result<Vec<_>, _>>()?;
+        Ok(entries)
+    }).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{MockResponse, MockServer};
+
+    // Ghost Admin API key: id 為 "key1"，secret 為 16 進位
+    const GHOST_KEY: &str = "key1:00112233445566778899aabbccddeeff";
+
+    fn post(status: PostStatus) -> PublishRequest {
+        PublishRequest {
+            title: "Hello World".to_string(),
+            markdown: "Some **text**".to_string(),
+            html: "<p>Some <strong>text</strong></p>".to_string(),
+            excerpt: "Some text".to_string(),
+            tags: vec!["Rust".to_string(), "Tauri".to_string(), "Web Dev".to_string(), "C++".to_string(), "rust".to_string(), "Async".to_string()],
+            categories: vec!["Programming".to_string()],
+            status,
+            canonical_url: Some("https://example.com/hello".to_string()),
+        }
+    }
+
+    fn ghost(server: &MockServer) -> GhostPublisher {
+        GhostPublisher::new(http_url(&server.url, "Ghost").unwrap(), GHOST_KEY).unwrap()
+    }
+
+    fn devto(server: &MockServer) -> DevToPublisher {
+        DevToPublisher { base: server.url.clone(), api_key: "devto-key".to_string(), client: http_client().unwrap() }
+    }
+
+    fn hashnode(server: &MockServer) -> HashnodePublisher {
+        HashnodePublisher {
+            endpoint: server.url.clone(),
+            token: "hashnode-token".to_string(),
+            publication_id: "pub1".to_string(),
+            client: http_client().unwrap(),
+        }
+    }
+
+    fn decode_segment(segment: &str) -> Value {
+        serde_json::from_slice(&URL_SAFE_NO_PAD.decode(segment).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn http_url_requires_https_except_for_localhost() {
+        assert_eq!(http_url(" https://example.com/ghost/ ", "Ghost").unwrap(), "https://example.com/ghost");
+        assert!(http_url("http://localhost:2368", "Ghost").is_ok());
+        assert!(http_url("http://127.0.0.1:2368", "Ghost").is_ok());
+        assert!(http_url("http://[::1]:2368", "Ghost").is_ok());
+        for url in ["http://example.com", "ftp://example.com", "example.com"] {
+            assert!(matches!(http_url(url, "Ghost"), Err(AppError::Validation(_))), "{}", url);
+        }
+    }
+
+    #[test]
+    fn ghost_rejects_malformed_admin_keys() {
+        for key in ["", "key1", "key1:", ":0011", "key1:xyz", "key1:123"] {
+            assert!(matches!(GhostPublisher::new(String::new(), key), Err(AppError::Validation(_))), "{}", key);
+        }
+    }
+
+    #[tokio::test]
+    async fn ghost_creates_a_post_with_a_signed_token() {
+        let server = MockServer::start(vec![
+            MockResponse::json(201, json!({ "posts": [{ "id": "abc", "url": "https://blog.example.com/hello/", "status": "draft" }] })),
+        ]);
+        let remote = ghost(&server).publish(&post(PostStatus::Draft), None).await.unwrap();
+        assert!(remote.created);
+        assert_eq!(remote.id, "abc");
+        assert_eq!(remote.url, "https://blog.example.com/hello/");
+        assert_eq!(remote.status, "draft");
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(requests[0].path, "/ghost/api/admin/posts/?source=html");
+        assert_eq!(requests[0].header("accept-version"), Some("v5.0"));
+
+        // Authorization: Ghost {header}.{payload}.{signature}
+        let token = requests[0].header("authorization").unwrap().strip_prefix("Ghost ").unwrap();
+        let segments: Vec<&str> = token.split('.').collect();
+        assert_eq!(segments.len(), 3);
+        let header = decode_segment(segments[0]);
+        assert_eq!(header["alg"], "HS256");
+        assert_eq!(header["kid"], "key1");
+        let payload = decode_segment(segments[1]);
+        assert_eq!(payload["aud"], "/admin/");
+        assert_eq!(payload["exp"].as_i64().unwrap() - payload["iat"].as_i64().unwrap(), GHOST_TOKEN_LIFETIME_SECS);
+        let mut mac = Hmac::<Sha256>::new_from_slice(&decode_hex("00112233445566778899aabbccddeeff").unwrap()).unwrap();
+        mac.update(format!("{}.{}", segments[0], segments[1]).as_bytes());
+        mac.verify_slice(&URL_SAFE_NO_PAD.decode(segments[2]).unwrap()).unwrap();
+
+        let body = requests[0].json();
+        assert_eq!(body["posts"][0]["status"], "draft");
+        assert_eq!(body["posts"][0]["canonical_url"], "https://example.com/hello");
+        // Categories come first and duplicate tags are dropped
+        let tags: Vec<&str> = body["posts"][0]["tags"].as_array().unwrap().iter().map(|t| t["name"].as_str().unwrap()).collect();
+        assert_eq!(tags, vec!["Programming", "Rust", "Tauri", "Web Dev", "C++", "Async"]);
+    }
+
+    #[tokio::test]
+    async fn ghost_updates_a_post_with_its_updated_at() {
+        let server = MockServer::start(vec![
+            MockResponse::json(200, json!({ "posts": [{ "id": "abc", "updated_at": "2024-05-01T10:00:00.000Z" }] })),
+            MockResponse::json(200, json!({ "posts": [{ "id": "abc", "url": "https://blog.example.com/hello/", "status": "published" }] })),
+        ]);
+        let remote = ghost(&server).publish(&post(PostStatus::Publish), Some("abc")).await.unwrap();
+        assert!(!remote.created);
+        assert_eq!(remote.status, "published");
+
+        let requests = server.requests();
+        assert_eq!(requests[0].method, "GET");
+        assert_eq!(requests[0].path, "/ghost/api/admin/posts/abc/");
+        assert_eq!(requests[1].method, "PUT");
+        assert_eq!(requests[1].path, "/ghost/api/admin/posts/abc/?source=html");
+        let body = requests[1].json();
+        assert_eq!(body["posts"][0]["updated_at"], "2024-05-01T10:00:00.000Z");
+        assert_eq!(body["posts"][0]["status"], "published");
+    }
+
+    #[tokio::test]
+    async fn ghost_recreates_a_deleted_post() {
+        let server = MockServer::start(vec![
+            MockResponse::json(404, json!({ "errors": [{ "message": "Resource not found" }] })),
+            MockResponse::json(201, json!({ "posts": [{ "id": "def", "url": "", "status": "draft" }] })),
+        ]);
+        let remote = ghost(&server).publish(&post(PostStatus::Draft), Some("abc")).await.unwrap();
+        assert!(remote.created);
+        assert_eq!(remote.id, "def");
+        assert_eq!(server.requests()[1].path, "/ghost/api/admin/posts/?source=html");
+    }
+
+    #[tokio::test]
+    async fn ghost_maps_errors() {
+        let server = MockServer::start(vec![
+            MockResponse::json(401, json!({ "errors": [{ "message": "Invalid token:", "context": "Token expired" }] })),
+            MockResponse::json(422, json!({ "errors": [{ "message": "Validation error", "context": "Title is too long" }] })),
+        ]);
+        let ghost = ghost(&server);
+        match ghost.publish(&post(PostStatus::Draft), Some("abc")).await {
+            Err(AppError::Upstream { message, status: Some(401) }) => {
+                assert!(message.contains("Ghost rejected the credentials"));
+                assert!(message.contains("Invalid token: Token expired"));
+            },
+            other => panic!("unexpected result: {:?}", other.map(|r| r.id)),
+        }
+        match ghost.publish(&post(PostStatus::Draft), None).await {
+            Err(AppError::Upstream { message, status: Some(422) }) => assert!(message.contains("Validation error Title is too long")),
+            other => panic!("unexpected result: {:?}", other.map(|r| r.id)),
+        }
+    }
+
+    #[tokio::test]
+    async fn devto_creates_an_article_with_at_most_four_tags() {
+        let server = MockServer::start(vec![
+            MockResponse::json(201, json!({ "id": 42, "url": "https://dev.to/me/hello-world", "published": true })),
+        ]);
+        let remote = devto(&server).publish(&post(PostStatus::Publish), None).await.unwrap();
+        assert!(remote.created);
+        assert_eq!(remote.id, "42");
+        assert_eq!(remote.status, "published");
+
+        let requests = server.requests();
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(requests[0].path, "/articles");
+        assert_eq!(requests[0].header("api-key"), Some("devto-key"));
+        let article = &requests[0].json()["article"];
+        assert_eq!(article["published"], true);
+        assert_eq!(article["body_markdown"], "Some **text**");
+        assert_eq!(article["canonical_url"], "https://example.com/hello");
+        assert_eq!(article["tags"], json!(["programming", "rust", "tauri", "webdev"]));
+    }
+
+    #[tokio::test]
+    async fn devto_updates_an_article() {
+        let server = MockServer::start(vec![
+            MockResponse::json(200, json!({ "id": 42, "url": "https://dev.to/me/hello-world", "published": false })),
+        ]);
+        let remote = devto(&server).publish(&post(PostStatus::Draft), Some("42")).await.unwrap();
+        assert!(!remote.created);
+        assert_eq!(remote.status, "draft");
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, "PUT");
+        assert_eq!(requests[0].path, "/articles/42");
+        assert_eq!(requests[0].json()["article"]["published"], false);
+    }
+
+    #[tokio::test]
+    async fn devto_recreates_a_deleted_article() {
+        let server = MockServer::start(vec![
+            MockResponse::json(404, json!({ "error": "not found", "status": 404 })),
+            MockResponse::json(201, json!({ "id": 43, "url": "https://dev.to/me/hello-world-2", "published": false })),
+        ]);
+        let remote = devto(&server).publish(&post(PostStatus::Draft), Some("42")).await.unwrap();
+        assert!(remote.created);
+        assert_eq!(remote.id, "43");
+        let paths: Vec<String> = server.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(paths, vec!["/articles/42", "/articles"]);
+    }
+
+    #[tokio::test]
+    async fn devto_maps_errors() {
+        let server = MockServer::start(vec![
+            MockResponse::json(422, json!({ "error": "Title can't be blank", "status": 422 })),
+            MockResponse::json(429, json!({ "error": "Rate limit reached", "status": 429 })).header("Retry-After", "12"),
+        ]);
+        let devto = devto(&server);
+        match devto.publish(&post(PostStatus::Draft), None).await {
+            Err(AppError::Upstream { message, status: Some(422) }) => assert!(message.contains("Title can't be blank")),
+            other => panic!("unexpected result: {:?}", other.map(|r| r.id)),
+        }
+        assert!(matches!(
+            devto.publish(&post(PostStatus::Draft), None).await,
+            Err(AppError::RateLimited { retry_after: Some(12), quota_exhausted: false, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn hashnode_creates_a_draft() {
+        let server = MockServer::start(vec![
+            MockResponse::json(200, json!({ "data": { "createDraft": { "draft": { "id": "d1" } } } })),
+        ]);
+        let remote = hashnode(&server).publish(&post(PostStatus::Draft), None).await.unwrap();
+        assert!(remote.created);
+        assert_eq!(remote.id, "draft:d1");
+        assert_eq!(remote.status, "draft");
+
+        let requests = server.requests();
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(requests[0].header("authorization"), Some("hashnode-token"));
+        let body = requests[0].json();
+        assert_eq!(body["query"], HASHNODE_CREATE_DRAFT);
+        let input = &body["variables"]["input"];
+        assert_eq!(input["publicationId"], "pub1");
+        assert_eq!(input["originalArticleURL"], "https://example.com/hello");
+        assert_eq!(input["tags"].as_array().unwrap().len(), HASHNODE_MAX_TAGS);
+        assert_eq!(input["tags"][0], json!({ "name": "Programming", "slug": "programming" }));
+    }
+
+    #[tokio::test]
+    async fn hashnode_publishes_an_updated_draft() {
+        let server = MockServer::start(vec![
+            MockResponse::json(200, json!({ "data": { "updateDraft": { "draft": { "id": "d1" } } } })),
+            MockResponse::json(200, json!({ "data": { "publishDraft": { "post": { "id": "p1", "url": "https://me.hashnode.dev/hello-world" } } } })),
+        ]);
+        let remote = hashnode(&server).publish(&post(PostStatus::Publish), Some("draft:d1")).await.unwrap();
+        assert!(!remote.created);
+        assert_eq!(remote.id, "p1");
+        assert_eq!(remote.status, "published");
+
+        let requests = server.requests();
+        assert_eq!(requests[0].json()["query"], HASHNODE_UPDATE_DRAFT);
+        assert_eq!(requests[0].json()["variables"]["input"]["id"], "d1");
+        assert_eq!(requests[1].json()["query"], HASHNODE_PUBLISH_DRAFT);
+        assert_eq!(requests[1].json()["variables"]["input"], json!({ "draftId": "d1" }));
+    }
+
+    #[tokio::test]
+    async fn hashnode_recreates_a_missing_post() {
+        let server = MockServer::start(vec![
+            MockResponse::json(200, json!({ "data": null, "errors": [{ "message": "Post not found", "extensions": { "code": "NOT_FOUND" } }] })),
+            MockResponse::json(200, json!({ "data": { "publishPost": { "post": { "id": "p2", "url": "https://me.hashnode.dev/hello-world-1" } } } })),
+        ]);
+        let remote = hashnode(&server).publish(&post(PostStatus::Publish), Some("p1")).await.unwrap();
+        assert!(remote.created);
+        assert_eq!(remote.id, "p2");
+
+        let requests = server.requests();
+        assert_eq!(requests[0].json()["query"], HASHNODE_UPDATE_POST);
+        assert_eq!(requests[1].json()["query"], HASHNODE_PUBLISH_POST);
+    }
+
+    #[tokio::test]
+    async fn hashnode_maps_graphql_errors() {
+        let server = MockServer::start(vec![
+            MockResponse::json(200, json!({ "errors": [{ "message": "Invalid token", "extensions": { "code": "UNAUTHENTICATED" } }] })),
+            MockResponse::json(400, json!({ "errors": [{ "message": "Tag limit exceeded", "extensions": { "code": "BAD_USER_INPUT" } }] })),
+            MockResponse::json(429, json!({ "errors": [{ "message": "Too many requests" }] })).header("Retry-After", "60"),
+            MockResponse::json(200, json!({ "data": null })),
+        ]);
+        let hashnode = hashnode(&server);
+        match hashnode.publish(&post(PostStatus::Draft), None).await {
+            Err(AppError::Upstream { message, status: Some(200) }) => assert!(message.contains("Hashnode rejected the token: Invalid token")),
+            other => panic!("unexpected result: {:?}", other.map(|r| r.id)),
+        }
+        match hashnode.publish(&post(PostStatus::Draft), None).await {
+            Err(AppError::Upstream { message, status: Some(400) }) => assert!(message.contains("Tag limit exceeded")),
+            other => panic!("unexpected result: {:?}", other.map(|r| r.id)),
+        }
+        assert!(matches!(
+            hashnode.publish(&post(PostStatus::Draft), None).await,
+            Err(AppError::RateLimited { retry_after: Some(60), .. })
+        ));
+        assert!(matches!(hashnode.publish(&post(PostStatus::Draft), None).await, Err(AppError::Parse(_))));
+    }
+}
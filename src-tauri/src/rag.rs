@@ -0,0 +1,508 @@
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use chrono::Local;
+
+use crate::SqliteState;
+use crate::ai_agent::AgentType;
+use crate::embeddings::{self, QueryEmbedding};
+use crate::error::AppError;
+use crate::export_common::is_cjk;
+
+// 每個片段的目標長度 (字元)
+const CHUNK_TARGET_CHARS: usize = 800;
+// 預設檢索數量
+const DEFAULT_TOP_K: usize = 5;
+// Upper bound on query terms so a long selection does not turn into hundreds of lookups
+const MAX_QUERY_TERMS: usize = 64;
+
+// BM25 參數
+const BM25_K1: f64 = 1.2;
+const BM25_B: f64 = 0.75;
+// 排名融合的平滑常數；越大時兩份排名的前段差距越小
+const RRF_K: f64 = 60.0;
+
+// 設定鍵
+const SETTING_INCLUDE_OTHER_PROJECTS: &str = "rag_include_other_projects";
+const SETTING_TOP_K: &str = "rag_top_k";
+
+pub const SOURCE_BLOG: &str = "blog";
+pub const SOURCE_CHAPTER: &str = "chapter";
+
+const STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "but", "by", "for", "from", "has", "have",
+    "he", "her", "his", "i", "in", "is", "it", "its", "of", "on", "or", "our", "she",
+    "that", "the", "their", "them", "they", "this", "to", "was", "we", "were", "what",
+    "when", "which", "who", "will", "with", "you", "your",
+];
+
+// 檢索到的段落
+#[derive(Debug, Clone, Serialize)]
+pub struct RetrievedPassage {
+    pub chunk_id: i64,
+    pub source_type: String,
+    pub source_id: i64,
+    pub project_id: i64,
+    pub content: String,
+    pub relevance_score: f64,
+}
+
+// 片段內容的雜湊 (FNV-1a)，用於判斷內容是否變更
+// Must stay stable across builds because stored embeddings are keyed by it
+pub fn content_hash(text: &str) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in text.as_bytes() {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{:016x}", hash)
+}
+
+// 分詞：拉丁文字以單字為單位，CJK 文字使用字元雙連詞 (bigram)
+pub fn tokenize(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    let mut cjk_run: Vec<char> = Vec::new();
+
+    fn flush_word(word: &mut String, tokens: &mut Vec<String>) {
+        if word.chars().count() >= 2 && !STOP_WORDS.contains(&word.as_str()) {
+            tokens.push(word.clone());
+        }
+        word.clear();
+    }
+
+    fn flush_cjk(run: &mut Vec<char>, tokens: &mut Vec<String>) {
+        match run.len() {
+            0 => {},
+            1 => tokens.push(run[0].to_string()),
+            _ => {
+                for pair in run.windows(2) {
+                    tokens.push(pair.iter().collect());
+                }
+            }
+        }
+        run.clear();
+    }
+
+    for c in text.chars() {
+        // is_cjk also covers full-width punctuation, which separates runs
+        if is_cjk(c) && c.is_alphanumeric() {
+            flush_word(&mut word, &mut tokens);
+            cjk_run.push(c);
+        } else if c.is_alphanumeric() {
+            flush_cjk(&mut cjk_run, &mut tokens);
+            word.extend(c.to_lowercase());
+        } else {
+            flush_word(&mut word, &mut tokens);
+            flush_cjk(&mut cjk_run, &mut tokens);
+        }
+    }
+    flush_word(&mut word, &mut tokens);
+    flush_cjk(&mut cjk_run, &mut tokens);
+
+    tokens
+}
+
+// Split an over-long paragraph on sentence boundaries
+fn split_sentences(paragraph: &str) -> Vec<String> {
+    let mut sentences = Vec::new();
+    let mut current = String::new();
+    for c in paragraph.chars() {
+        current.push(c);
+        if matches!(c, '.' | '!' | '?' | '。' | '！' | '？') && current.chars().count() >= 40 {
+            sentences.push(current.trim().to_string());
+            current.clear();
+        }
+    }
+    if !current.trim().is_empty() {
+        sentences.push(current.trim().to_string());
+    }
+    sentences
+}
+
+// 將 Markdown 內容切成片段：以段落為單位打包到目標長度
+pub fn chunk_content(title: &str, content: &str) -> Vec<String> {
+    let mut pieces: Vec<String> = Vec::new();
+    for paragraph in content.split("\n\n").map(|p| p.trim()).filter(|p| !p.is_empty()) {
+        if paragraph.chars().count() > CHUNK_TARGET_CHARS {
+            pieces.extend(split_sentences(paragraph));
+        } else {
+            pieces.push(paragraph.to_string());
+        }
+    }
+
+    let mut chunks: Vec<String> = Vec::new();
+    let mut current = String::new();
+    for piece in pieces {
+        if !current.is_empty() && current.chars().count() + piece.chars().count() > CHUNK_TARGET_CHARS {
+            chunks.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push_str("\n\n");
+        }
+        current.push_str(&piece);
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+
+    // A title-only document still gets one chunk so it can be found
+    if chunks.is_empty() && !title.trim().is_empty() {
+        chunks.push(title.trim().to_string());
+    }
+    chunks
+}
+
+// 重新索引一個部落格或章節：刪除舊片段並寫入新片段與詞頻
+pub fn index_source(conn: &Connection, source_type: &str, source_id: i64, project_id: i64, title: &str, content: &str) -> Result<usize, AppError> {
+    let now = Local::now().to_rfc3339();
+    let chunks = chunk_content(title, content);
+
+    conn.execute_batch("SAVEPOINT rag_index")?;
+    let result = (|| -> Result<(), AppError> {
+        remove_source(conn, source_type, source_id)?;
+
+        for (index, chunk) in chunks.iter().enumerate() {
+            // The title is indexed with every chunk so passages match on the article topic
+            let tokens = tokenize(&format!("{}\n{}", title, chunk));
+            let mut frequencies: HashMap<&str, i64> = HashMap::new();
+            for token in &tokens {
+                *frequencies.entry(token.as_str()).or_insert(0) += 1;
+            }
+
+            conn.execute(
+                "INSERT INTO content_chunks (source_type, source_id, project_id, chunk_index, content, content_hash, term_count, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![source_type, source_id, project_id, index as i64, chunk, content_hash(chunk), tokens.len() as i64, now],
+            )?;
+            let chunk_id = conn.last_insert_rowid();
+
+            let mut stmt = conn.prepare_cached(
+                "INSERT INTO chunk_terms (chunk_id, term, frequency) VALUES (?1, ?2, ?3)"
+            )?;
+            for (term, frequency) in frequencies {
+                stmt.execute(params![chunk_id, term, frequency])?;
+            }
+        }
+        Ok(())
+    })();
+
+    match result {
+        Ok(()) => {
+            conn.execute_batch("RELEASE rag_index")?;
+            Ok(chunks.len())
+        },
+        Err(e) => {
+            let _ = conn.execute_batch("ROLLBACK TO rag_index; RELEASE rag_index");
+            Err(e)
+        }
+    }
+}
+
+// 移除一個來源的所有片段
+pub fn remove_source(conn: &Connection, source_type: &str, source_id: i64) -> Result<(), AppError> {
+    conn.execute(
+        "DELETE FROM chunk_terms WHERE chunk_id IN (SELECT id FROM content_chunks WHERE source_type = ?1 AND source_id = ?2)",
+        params![source_type, source_id],
+    )?;
+    conn.execute(
+        "DELETE FROM content_chunks WHERE source_type = ?1 AND source_id = ?2",
+        params![source_type, source_id],
+    )?;
+    Ok(())
+}
+
+// 移除一個專案的所有片段
+pub fn remove_project(conn: &Connection, project_id: i64) -> Result<(), AppError> {
+    conn.execute(
+        "DELETE FROM chunk_terms WHERE chunk_id IN (SELECT id FROM content_chunks WHERE project_id = ?1)",
+        [project_id],
+    )?;
+    conn.execute("DELETE FROM content_chunks WHERE project_id = ?1", [project_id])?;
+    Ok(())
+}
+
+// 索引時記錄錯誤但不讓儲存失敗
+pub fn index_source_logged(conn: &Connection, source_type: &str, source_id: i64, project_id: i64, title: &str, content: &str) {
+    match index_source(conn, source_type, source_id, project_id, title, content) {
+        Ok(count) => println!("Indexed {} {} into {} chunks", source_type, source_id, count),
+        Err(e) => println!("Failed to index {} {}: {}", source_type, source_id, e),
+    }
+}
+
+// 為尚未建立索引的部落格與章節補建索引
+pub fn index_missing_sources(conn: &Connection) -> Result<usize, AppError> {
+    let mut sources: Vec<(String, i64, i64, String, String)> = Vec::new();
+    for (source_type, table) in [(SOURCE_BLOG, "blogs"), (SOURCE_CHAPTER, "chapters")] {
+        let sql = format!(
+            "SELECT t.id, t.project_id, t.title, COALESCE(t.content, '') FROM {} t
+             WHERE NOT EXISTS (SELECT 1 FROM content_chunks c WHERE c.source_type = ?1 AND c.source_id = t.id)",
+            table
+        );
+        let mut stmt = match conn.prepare(&sql) {
+            Ok(stmt) => stmt,
+            // Table may not exist yet on a fresh database
+            Err(_) => continue,
+        };
+        let rows = stmt.query_map([source_type], |row| {
+            Ok((source_type.to_string(), row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get::<_, String>(2)?, row.get::<_, String>(3)?))
+        })?;
+        for row in rows {
+            sources.push(row?);
+        }
+    }
+
+    for (source_type, source_id, project_id, title, content) in &sources {
+        index_source(conn, source_type, *source_id, *project_id, title, content)?;
+    }
+    if !sources.is_empty() {
+        println!("Indexed {} previously unindexed sources", sources.len());
+    }
+    Ok(sources.len())
+}
+
+// 以 BM25 為片段評分，依分數由高到低排列
+fn bm25_ranking(conn: &Connection, project_id: Option<i64>, query: &str) -> Result<Vec<(i64, f64)>, AppError> {
+    let mut seen = HashSet::new();
+    let terms: Vec<String> = tokenize(query)
+        .into_iter()
+        .filter(|t| seen.insert(t.clone()))
+        .take(MAX_QUERY_TERMS)
+        .collect();
+    if terms.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // 檢索範圍：指定專案或全部專案
+    let scope = match project_id {
+        Some(_) => "c.project_id = ?1",
+        None => "?1 IS NULL",
+    };
+
+    let (chunk_count, avg_len): (i64, f64) = conn.query_row(
+        &format!("SELECT COUNT(*), COALESCE(AVG(term_count), 0) FROM content_chunks c WHERE {}", scope),
+        params![project_id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    if chunk_count == 0 {
+        return Ok(Vec::new());
+    }
+    let avg_len = avg_len.max(1.0);
+
+    let mut scores: HashMap<i64, f64> = HashMap::new();
+    let mut stmt = conn.prepare(&format!(
+        "SELECT t.chunk_id, t.frequency, c.term_count FROM chunk_terms t
+         JOIN content_chunks c ON c.id = t.chunk_id
+         WHERE t.term = ?2 AND {}",
+        scope
+    ))?;
+
+    for term in &terms {
+        let postings = stmt.query_map(params![project_id, term], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get::<_, i64>(2)?))
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+
+        if postings.is_empty() {
+            continue;
+        }
+        let df = postings.len() as f64;
+        let idf = (1.0 + (chunk_count as f64 - df + 0.5) / (df + 0.5)).ln();
+
+        for (chunk_id, frequency, term_count) in postings {
+            let tf = frequency as f64;
+            let norm = BM25_K1 * (1.0 - BM25_B + BM25_B * term_count as f64 / avg_len);
+            *scores.entry(chunk_id).or_insert(0.0) += idf * tf * (BM25_K1 + 1.0) / (tf + norm);
+        }
+    }
+
+    let mut ranked: Vec<(i64, f64)> = scores.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+    Ok(ranked)
+}
+
+// 以倒數排名融合 (RRF) 合併詞彙與向量兩份排名
+// BM25 and cosine scores are on different scales, so only the rank positions are combined
+fn fuse_rankings(lexical: &[(i64, f64)], semantic: &[(i64, f64)]) -> Vec<(i64, f64)> {
+    let mut scores: HashMap<i64, f64> = HashMap::new();
+    for ranking in [lexical, semantic] {
+        for (rank, (chunk_id, _)) in ranking.iter().enumerate() {
+            *scores.entry(*chunk_id).or_insert(0.0) += 1.0 / (RRF_K + rank as f64 + 1.0);
+        }
+    }
+    let mut fused: Vec<(i64, f64)> = scores.into_iter().collect();
+    fused.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal).then(a.0.cmp(&b.0)));
+    fused
+}
+
+// 依排名載入片段內容，略過排除的來源與已刪除的片段
+pub(crate) fn load_passages(
+    conn: &Connection,
+    ranked: &[(i64, f64)],
+    exclude: Option<(&str, i64)>,
+    limit: usize,
+) -> Result<Vec<RetrievedPassage>, AppError> {
+    let mut passages = Vec::new();
+    let mut chunk_stmt = conn.prepare(
+        "SELECT source_type, source_id, project_id, content FROM content_chunks WHERE id = ?1"
+    )?;
+    for &(chunk_id, score) in ranked {
+        if passages.len() >= limit {
+            break;
+        }
+        let row = chunk_stmt.query_row([chunk_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?, row.get::<_, i64>(2)?, row.get::<_, String>(3)?))
+        }).optional()?;
+        let Some((source_type, source_id, chunk_project_id, content)) = row else { continue };
+        if exclude == Some((source_type.as_str(), source_id)) {
+            continue;
+        }
+        passages.push(RetrievedPassage {
+            chunk_id,
+            source_type,
+            source_id,
+            project_id: chunk_project_id,
+            content,
+            relevance_score: score,
+        });
+    }
+    Ok(passages)
+}
+
+// 檢索最相關的片段：BM25，有查詢向量與已嵌入的片段時再融合向量相似度
+pub fn retrieve(
+    conn: &Connection,
+    project_id: Option<i64>,
+    query: &str,
+    query_embedding: Option<&QueryEmbedding>,
+    exclude: Option<(&str, i64)>,
+    limit: usize,
+) -> Result<Vec<RetrievedPassage>, AppError> {
+    if limit == 0 {
+        return Ok(Vec::new());
+    }
+    let lexical = bm25_ranking(conn, project_id, query)?;
+    let semantic = match query_embedding {
+        Some(query_embedding) => embeddings::chunk_similarities(conn, query_embedding, project_id)?,
+        None => Vec::new(),
+    };
+    let ranked = if semantic.is_empty() { lexical } else { fuse_rankings(&lexical, &semantic) };
+    load_passages(conn, &ranked, exclude, limit)
+}
+
+fn read_setting(conn: &Connection, key: &str) -> Option<String> {
+    conn.query_row("SELECT value FROM settings WHERE key = ?1", [key], |row| row.get::<_, String>(0))
+        .optional()
+        .ok()
+        .flatten()
+}
+
+// 組合檢索查詢：標題、使用者請求與選取文字；沒有選取時使用內容開頭
+pub fn build_query(title: &str, prompt: &str, selected_text: &str, content: &str) -> String {
+    let focus: String = if selected_text.trim().is_empty() {
+        content.chars().take(500).collect()
+    } else {
+        selected_text.to_string()
+    };
+    format!("{}\n{}\n{}", title, prompt, focus)
+}
+
+// 記錄檢索結果到 rag_retrievals
+fn record_retrievals(conn: &Connection, blog_id: i64, agent_type: &AgentType, passages: &[RetrievedPassage]) -> Result<(), AppError> {
+    let now = Local::now().to_rfc3339();
+    for passage in passages {
+        conn.execute(
+            "INSERT INTO rag_retrievals (blog_id, agent_type, source_type, source_id, relevance_score, content, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?7)",
+            params![
+                blog_id,
+                agent_type.to_string(),
+                passage.source_type,
+                passage.source_id,
+                passage.relevance_score,
+                passage.content,
+                now
+            ],
+        )?;
+    }
+    Ok(())
+}
+
+// 為部落格檢索相關段落並記錄
+pub fn retrieve_for_blog(
+    conn: &Connection,
+    blog_id: i64,
+    agent_type: &AgentType,
+    query: &str,
+    query_embedding: Option<&QueryEmbedding>,
+) -> Result<Vec<RetrievedPassage>, AppError> {
+    let project_id: Option<i64> = conn.query_row(
+        "SELECT project_id FROM blogs WHERE id = ?1",
+        [blog_id],
+        |row| row.get(0)
+    ).optional()?;
+
+    let include_other_projects = read_setting(conn, SETTING_INCLUDE_OTHER_PROJECTS)
+        .map(|v| v == "true")
+        .unwrap_or(false);
+    let top_k = read_setting(conn, SETTING_TOP_K)
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_TOP_K);
+
+    let scope = if include_other_projects { None } else { project_id };
+    if scope.is_none() && !include_other_projects {
+        // Blog is not attached to a project; nothing to search
+        return Ok(Vec::new());
+    }
+
+    let passages = retrieve(conn, scope, query, query_embedding, Some((SOURCE_BLOG, blog_id)), top_k)?;
+    record_retrievals(conn, blog_id, agent_type, &passages)?;
+    println!("Retrieved {} passages for blog {}", passages.len(), blog_id);
+    Ok(passages)
+}
+
+// 將檢索到的段落加入提示前方
+pub fn prepend_context(prompt: String, passages: &[RetrievedPassage]) -> String {
+    if passages.is_empty() {
+        return prompt;
+    }
+    let mut context = String::from(
+        "Reference material from the author's existing writing. Use it for consistency of facts, terminology and voice when relevant; ignore it otherwise.\n"
+    );
+    for (i, passage) in passages.iter().enumerate() {
+        context.push_str(&format!("\n[{}] ({} {})\n{}\n", i + 1, passage.source_type, passage.source_id, passage.content));
+    }
+    format!("{}\n---\n\n{}", context, prompt)
+}
+
+// 檢索並擴充提示；檢索失敗時回傳原始提示
+pub fn augment_prompt(
+    conn: &Connection,
+    blog_id: i64,
+    agent_type: &AgentType,
+    query: &str,
+    query_embedding: Option<&QueryEmbedding>,
+    prompt: String,
+) -> String {
+    match retrieve_for_blog(conn, blog_id, agent_type, query, query_embedding) {
+        Ok(passages) => prepend_context(prompt, &passages),
+        Err(e) => {
+            println!("RAG retrieval failed for blog {}: {}", blog_id, e);
+            prompt
+        }
+    }
+}
+
+// 重建全部檢索索引
+#[tauri::command]
+pub async fn rebuild_rag_index(state: tauri::State<'_, SqliteState>) -> Result<usize, AppError> {
+    state.run(|conn| {
+        // 失敗時保留原本的索引，避免提示失去檢索內容
+        let tx = conn.unchecked_transaction()?;
+        tx.execute("DELETE FROM chunk_terms", [])?;
+        tx.execute("DELETE FROM content_chunks", [])?;
+        let indexed = index_missing_sources(&tx)?;
+        tx.commit()?;
+        Ok(indexed)
+    }).await
+}
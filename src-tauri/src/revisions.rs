@@ -0,0 +1,285 @@
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::Serialize;
+use chrono::Local;
+use tauri::AppHandle;
+
+use crate::SqliteState;
+use crate::ai_agent::AgentType;
+use crate::error::AppError;
+use crate::db;
+use crate::diff::{self, DiffSegment};
+use crate::embeddings;
+use crate::rag;
+
+// 作者標記：使用者或 AgentType 名稱
+pub const AUTHOR_USER: &str = "user";
+
+// 版本記錄
+#[derive(Debug, Serialize)]
+pub struct Revision {
+    pub id: i64,
+    pub source_type: String,
+    pub source_id: i64,
+    pub project_id: i64,
+    pub title: String,
+    pub content: String,
+    pub keywords: Option<String>,
+    pub chapter_number: Option<i32>,
+    pub author: String,
+    pub restored_from: Option<i64>,
+    pub created_at: String,
+}
+
+// 版本列表項目 (不含內容)
+#[derive(Debug, Serialize)]
+pub struct RevisionSummary {
+    pub id: i64,
+    pub title: String,
+    pub author: String,
+    pub restored_from: Option<i64>,
+    pub word_count: usize,
+    pub created_at: String,
+}
+
+// 兩個版本的比較結果
+#[derive(Debug, Serialize)]
+pub struct RevisionDiff {
+    pub from_id: i64,
+    pub to_id: i64,
+    pub title_changed: bool,
+    pub segments: Vec<DiffSegment>,
+}
+
+// 將作者參數轉為儲存的字串
+pub fn author_name(author: &Option<AgentType>) -> String {
+    match author {
+        Some(agent_type) => agent_type.to_string(),
+        None => AUTHOR_USER.to_string(),
+    }
+}
+
+fn source_table(source_type: &str) -> Result<&'static str, AppError> {
+    match source_type {
+        rag::SOURCE_BLOG => Ok("blogs"),
+        rag::SOURCE_CHAPTER => Ok("chapters"),
+        _ => Err(AppError::validation(format!("Unknown revision source type: {}", source_type))),
+    }
+}
+
+// 目前的部落格或章節內容
+struct CurrentContent {
+    project_id: i64,
+    title: String,
+    content: String,
+    keywords: Option<String>,
+    chapter_number: Option<i32>,
+}
+
+fn read_current(conn: &Connection, source_type: &str, source_id: i64) -> Result<Option<CurrentContent>, AppError> {
+    let sql = match source_table(source_type)? {
+        "blogs" => "SELECT project_id, title, COALESCE(content, ''), keywords, NULL FROM blogs WHERE id = ?1",
+        _ => "SELECT project_id, title, COALESCE(content, ''), NULL, chapter_number FROM chapters WHERE id = ?1",
+    };
+    conn.query_row(sql, [source_id], |row| {
+        Ok(CurrentContent {
+            project_id: row.get(0)?,
+            title: row.get(1)?,
+            content: row.get(2)?,
+            keywords: row.get(3)?,
+            chapter_number: row.get(4)?,
+        })
+    }).optional().map_err(AppError::from)
+}
+
+// 保存目前內容為新版本；與最新版本相同時略過
+pub fn record_revision(conn: &Connection, source_type: &str, source_id: i64, author: &str, restored_from: Option<i64>) -> Result<Option<i64>, AppError> {
+    let Some(CurrentContent { project_id, title, content, keywords, chapter_number }) = read_current(conn, source_type, source_id)? else {
+        return Ok(None);
+    };
+
+    let latest: Option<(String, String)> = conn.query_row(
+        "SELECT title, content FROM revisions WHERE source_type = ?1 AND source_id = ?2 ORDER BY id DESC LIMIT 1",
+        params![source_type, source_id],
+        |row| Ok((row.get(0)?, row.get(1)?))
+    ).optional()?;
+    if restored_from.is_none() && latest == Some((title.clone(), content.clone())) {
+        return Ok(None);
+    }
+
+    conn.execute(
+        "INSERT INTO revisions (source_type, source_id, project_id, title, content, keywords, chapter_number, author, restored_from, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            source_type,
+            source_id,
+            project_id,
+            title,
+            content,
+            keywords,
+            chapter_number,
+            author,
+            restored_from,
+            Local::now().to_rfc3339()
+        ],
+    )?;
+
+    Ok(Some(conn.last_insert_rowid()))
+}
+
+// 第一次覆寫前保存原始內容，讓建立版本記錄以前的文字也能還原
+pub fn ensure_baseline(conn: &Connection, source_type: &str, source_id: i64) -> Result<(), AppError> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM revisions WHERE source_type = ?1 AND source_id = ?2",
+        params![source_type, source_id],
+        |row| row.get(0)
+    )?;
+    if count == 0 {
+        record_revision(conn, source_type, source_id, AUTHOR_USER, None)?;
+    }
+    Ok(())
+}
+
+// 記錄錯誤但不讓儲存失敗
+pub fn ensure_baseline_logged(conn: &Connection, source_type: &str, source_id: i64) {
+    if let Err(e) = ensure_baseline(conn, source_type, source_id) {
+        println!("Failed to save baseline revision for {} {}: {}", source_type, source_id, e);
+    }
+}
+
+pub fn record_revision_logged(conn: &Connection, source_type: &str, source_id: i64, author: &str) {
+    match record_revision(conn, source_type, source_id, author, None) {
+        Ok(Some(id)) => println!("Saved revision {} for {} {} by {}", id, source_type, source_id, author),
+        Ok(None) => {},
+        Err(e) => println!("Failed to save revision for {} {}: {}", source_type, source_id, e),
+    }
+}
+
+// 刪除來源的所有版本
+pub fn remove_revisions(conn: &Connection, source_type: &str, source_id: i64) -> Result<(), AppError> {
+    conn.execute(
+        "DELETE FROM revisions WHERE source_type = ?1 AND source_id = ?2",
+        params![source_type, source_id],
+    )?;
+    Ok(())
+}
+
+fn load_revision(conn: &Connection, id: i64) -> Result<Revision, AppError> {
+    conn.query_row(
+        "SELECT id, source_type, source_id, project_id, title, content, keywords, chapter_number, author, restored_from, created_at
+         FROM revisions WHERE id = ?1",
+        [id],
+        |row| {
+            Ok(Revision {
+                id: row.get(0)?,
+                source_type: row.get(1)?,
+                source_id: row.get(2)?,
+                project_id: row.get(3)?,
+                title: row.get(4)?,
+                content: row.get(5)?,
+                keywords: row.get(6)?,
+                chapter_number: row.get(7)?,
+                author: row.get(8)?,
+                restored_from: row.get(9)?,
+                created_at: row.get(10)?,
+            })
+        }
+    ).optional()?
+    .ok_or_else(|| AppError::not_found("Revision", id))
+}
+
+// 列出部落格或章節的版本 (最新在前)
+#[tauri::command]
+pub async fn list_revisions(source_type: String, source_id: i64, state: tauri::State<'_, SqliteState>) -> Result<Vec<RevisionSummary>, AppError> {
+    state.run(move |conn| {
+        source_table(&source_type)?;
+
+        let mut stmt = conn.prepare(
+            "SELECT id, title, author, restored_from, content, created_at FROM revisions
+             WHERE source_type = ?1 AND source_id = ?2
+             ORDER BY id DESC"
+        )?;
+
+        let rows = stmt.query_map(params![source_type, source_id], |row| {
+            let content: String = row.get(4)?;
+            Ok(RevisionSummary {
+                id: row.get(0)?,
+                title: row.get(1)?,
+                author: row.get(2)?,
+                restored_from: row.get(3)?,
+                word_count: content.split_whitespace().count(),
+                created_at: row.get(5)?,
+            })
+        })?;
+
+        let mut revisions = Vec::new();
+        for row in rows {
+            revisions.push(row?);
+        }
+        println!("Retrieved {} revisions for {} {}", revisions.len(), source_type, source_id);
+        Ok(revisions)
+    }).await
+}
+
+// 獲取單一版本
+#[tauri::command]
+pub async fn get_revision(id: i64, state: tauri::State<'_, SqliteState>) -> Result<Revision, AppError> {
+    state.run(move |conn| load_revision(conn, id)).await
+}
+
+// 比較兩個版本 (以行為單位)
+#[tauri::command]
+pub async fn diff_revisions(from_id: i64, to_id: i64, state: tauri::State<'_, SqliteState>) -> Result<RevisionDiff, AppError> {
+    state.run(move |conn| {
+        let from = load_revision(conn, from_id)?;
+        let to = load_revision(conn, to_id)?;
+
+        if from.source_type != to.source_type || from.source_id != to.source_id {
+            return Err(AppError::validation("Revisions belong to different documents"));
+        }
+
+        Ok(RevisionDiff {
+            from_id,
+            to_id,
+            title_changed: from.title != to.title,
+            segments: diff::diff_lines(&from.content, &to.content),
+        })
+    }).await
+}
+
+// 將版本還原為目前內容，並記錄為新版本
+#[tauri::command]
+pub async fn restore_revision(id: i64, app_handle: AppHandle, state: tauri::State<'_, SqliteState>) -> Result<i64, AppError> {
+    state.run(move |conn| {
+        let revision = load_revision(conn, id)?;
+        let table = source_table(&revision.source_type)?;
+        let now = Local::now().to_rfc3339();
+
+        // Keep whatever is there now so the restore itself can be undone
+        ensure_baseline(conn, &revision.source_type, revision.source_id)?;
+        record_revision(conn, &revision.source_type, revision.source_id, AUTHOR_USER, None)?;
+
+        let updated = match table {
+            "blogs" => conn.execute(
+                "UPDATE blogs SET title = ?1, content = ?2, keywords = ?3, updated_at = ?4 WHERE id = ?5",
+                params![revision.title, revision.content, revision.keywords, now, revision.source_id],
+            ),
+            _ => conn.execute(
+                "UPDATE chapters SET title = ?1, content = ?2, chapter_number = COALESCE(?3, chapter_number), updated_at = ?4 WHERE id = ?5",
+                params![revision.title, revision.content, revision.chapter_number, now, revision.source_id],
+            ),
+        }?;
+
+        if updated == 0 {
+            return Err(AppError::NotFound(format!("{} {} no longer exists", revision.source_type, revision.source_id)));
+        }
+
+        let new_id = record_revision(conn, &revision.source_type, revision.source_id, AUTHOR_USER, Some(id))?
+            .ok_or_else(|| AppError::Internal("Failed to record restored revision".to_string()))?;
+
+        db::reindex_source(conn, &revision.source_type, revision.source_id);
+        embeddings::schedule_embedding(&app_handle);
+
+        println!("Restored revision {} to {} {}", id, revision.source_type, revision.source_id);
+        Ok(new_id)
+    }).await
+}
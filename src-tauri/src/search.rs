@@ -0,0 +1,230 @@
+use rusqlite::{Connection, params};
+use serde::Serialize;
+
+use crate::SqliteState;
+use crate::error::AppError;
+
+const DEFAULT_SEARCH_LIMIT: usize = 50;
+// trigram 分詞器至少需要 3 個字元才能比對
+const MIN_FTS_TERM_CHARS: usize = 3;
+
+const HIGHLIGHT_START: &str = "<mark>";
+const HIGHLIGHT_END: &str = "</mark>";
+const SNIPPET_ELLIPSIS: &str = "…";
+const SNIPPET_TOKENS: i64 = 24;
+const FALLBACK_SNIPPET_CHARS: usize = 60;
+
+// 搜尋結果
+#[derive(Debug, Serialize)]
+pub struct SearchResult {
+    pub result_type: String,
+    pub id: i64,
+    pub project_id: i64,
+    pub project_title: String,
+    pub title: String,
+    pub snippet: String,
+    pub score: f64,
+}
+
+// 全文索引的表定義 (由 migrations v6 建立)：(FTS 表, 來源表, 欄位, bm25 欄位權重)
+// Titles weigh most, then keywords/description, then body text
+const FTS_TABLES: &[(&str, &str, &[&str], &str)] = &[
+    ("projects_fts", "projects", &["title", "description", "keywords"], "10.0, 2.0, 5.0"),
+    ("blogs_fts", "blogs", &["title", "content", "keywords"], "10.0, 1.0, 5.0"),
+    ("chapters_fts", "chapters", &["title", "content"], "10.0, 1.0"),
+];
+
+// 將使用者輸入轉為 FTS5 查詢：每個詞加上引號避免語法錯誤，詞之間為 AND
+fn build_match_query(terms: &[String]) -> String {
+    terms.iter()
+        .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn search_fts(
+    conn: &Connection,
+    match_query: &str,
+    project_id: Option<i64>,
+    limit: usize,
+) -> Result<Vec<SearchResult>, AppError> {
+    let mut results = Vec::new();
+
+    for (fts_table, source_table, _, weights) in FTS_TABLES {
+        let (result_type, project_column) = match *source_table {
+            "projects" => ("project", "s.id"),
+            "blogs" => ("blog", "s.project_id"),
+            _ => ("chapter", "s.project_id"),
+        };
+        let sql = format!(
+            "SELECT s.id, {project_column}, COALESCE(p.title, ''), s.title,
+                    snippet({fts}, -1, ?1, ?2, ?3, ?4),
+                    bm25({fts}, {weights})
+             FROM {fts}
+             JOIN {src} s ON s.id = {fts}.rowid
+             LEFT JOIN projects p ON p.id = {project_column}
+             WHERE {fts} MATCH ?5 AND (?6 IS NULL OR {project_column} = ?6)
+             ORDER BY bm25({fts}, {weights})
+             LIMIT ?7",
+            fts = fts_table,
+            src = source_table,
+            project_column = project_column,
+            weights = weights,
+        );
+
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(
+            params![HIGHLIGHT_START, HIGHLIGHT_END, SNIPPET_ELLIPSIS, SNIPPET_TOKENS, match_query, project_id, limit as i64],
+            |row| {
+                Ok(SearchResult {
+                    result_type: result_type.to_string(),
+                    id: row.get(0)?,
+                    project_id: row.get(1)?,
+                    project_title: row.get(2)?,
+                    title: row.get(3)?,
+                    snippet: row.get(4)?,
+                    // bm25() is lower for better matches; flip it so higher is better
+                    score: -row.get::<_, f64>(5)?,
+                })
+            }
+        )?;
+
+        for row in rows {
+            results.push(row?);
+        }
+    }
+
+    Ok(results)
+}
+
+// 在文字中找出第一個符合的詞並截取前後內容
+fn highlight_excerpt(text: &str, terms: &[String]) -> String {
+    let lower = text.to_lowercase();
+    let found = terms.iter()
+        .filter_map(|term| lower.find(&term.to_lowercase()).map(|pos| (pos, term.len())))
+        .min_by_key(|(pos, _)| *pos);
+
+    let Some((pos, len)) = found else {
+        return text.chars().take(FALLBACK_SNIPPET_CHARS * 2).collect();
+    };
+    // Lowercasing can shift byte offsets for some scripts; fall back to a plain excerpt if so
+    if !text.is_char_boundary(pos) || !text.is_char_boundary(pos + len) || pos + len > text.len() {
+        return text.chars().take(FALLBACK_SNIPPET_CHARS * 2).collect();
+    }
+
+    let before: String = text[..pos].chars().rev().take(FALLBACK_SNIPPET_CHARS).collect::<Vec<_>>().into_iter().rev().collect();
+    let after: String = text[pos + len..].chars().take(FALLBACK_SNIPPET_CHARS).collect();
+    format!(
+        "{}{}{}{}{}{}{}",
+        if before.len() < pos { SNIPPET_ELLIPSIS } else { "" },
+        before,
+        HIGHLIGHT_START,
+        &text[pos..pos + len],
+        HIGHLIGHT_END,
+        after,
+        if pos + len + after.len() < text.len() { SNIPPET_ELLIPSIS } else { "" },
+    )
+}
+
+// 查詢詞太短無法使用 trigram 索引時，改用 LIKE 比對
+fn search_like(
+    conn: &Connection,
+    terms: &[String],
+    project_id: Option<i64>,
+    limit: usize,
+) -> Result<Vec<SearchResult>, AppError> {
+    let mut results = Vec::new();
+
+    for (_, source_table, columns, _) in FTS_TABLES {
+        let (result_type, project_column) = match *source_table {
+            "projects" => ("project", "s.id"),
+            "blogs" => ("blog", "s.project_id"),
+            _ => ("chapter", "s.project_id"),
+        };
+        let body_column = columns[1];
+        let haystack = columns.iter().map(|c| format!("COALESCE(s.{}, '')", c)).collect::<Vec<_>>().join(" || ' ' || ");
+        let term_conditions = (0..terms.len())
+            .map(|i| format!("({}) LIKE ?{} ESCAPE '\\'", haystack, i + 3))
+            .collect::<Vec<_>>()
+            .join(" AND ");
+        let sql = format!(
+            "SELECT s.id, {project_column}, COALESCE(p.title, ''), s.title, COALESCE(s.{body}, '')
+             FROM {src} s
+             LEFT JOIN projects p ON p.id = {project_column}
+             WHERE (?1 IS NULL OR {project_column} = ?1) AND {conditions}
+             ORDER BY s.updated_at DESC
+             LIMIT ?2",
+            src = source_table,
+            project_column = project_column,
+            body = body_column,
+            conditions = term_conditions,
+        );
+
+        let mut values: Vec<Box<dyn rusqlite::ToSql>> = vec![Box::new(project_id), Box::new(limit as i64)];
+        for term in terms {
+            let escaped = term.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+            values.push(Box::new(format!("%{}%", escaped)));
+        }
+
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(
+            rusqlite::params_from_iter(values.iter().map(|v| v.as_ref())),
+            |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, i64>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                ))
+            }
+        )?;
+
+        for row in rows {
+            let (id, row_project_id, project_title, title, body) = row?;
+            let snippet = highlight_excerpt(&body, terms);
+            results.push(SearchResult {
+                result_type: result_type.to_string(),
+                id,
+                project_id: row_project_id,
+                project_title,
+                title,
+                snippet,
+                score: 0.0,
+            });
+        }
+    }
+
+    Ok(results)
+}
+
+// 全文搜尋專案、部落格與章節
+#[tauri::command]
+pub async fn search_content(
+    query: String,
+    project_id: Option<i64>,
+    limit: Option<usize>,
+    state: tauri::State<'_, SqliteState>
+) -> Result<Vec<SearchResult>, AppError> {
+    println!("search_content called with query: {}, project_id: {:?}", query, project_id);
+
+    let terms: Vec<String> = query.split_whitespace().map(|t| t.to_string()).collect();
+    if terms.is_empty() {
+        return Ok(Vec::new());
+    }
+    let limit = limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+
+    let mut results = state.run(move |conn| {
+        if terms.iter().all(|t| t.chars().count() >= MIN_FTS_TERM_CHARS) {
+            search_fts(conn, &build_match_query(&terms), project_id, limit)
+        } else {
+            search_like(conn, &terms, project_id, limit)
+        }
+    }).await?;
+
+    results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+    results.truncate(limit);
+
+    println!("Found {} search results", results.len());
+    Ok(results)
+}
@@ -0,0 +1,276 @@
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use chrono::Local;
+use rusqlite::{Connection, OptionalExtension, params};
+use serde::Serialize;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::SqliteState;
+use crate::error::AppError;
+
+// 加密金鑰檔：與資料庫放在同一個資料目錄，但不在資料庫內，匯出的檔案不會帶走它
+const KEY_FILE: &str = "secrets.key";
+const KEY_LEN: usize = 32;
+const NONCE_LEN: usize = 12;
+
+// 以前以明文存在 settings 表的 API 金鑰，現在改存 secrets 表
+pub const LEGACY_SECRET_SETTINGS: &[&str] = &["gemini_api_key", "openai_api_key", "anthropic_api_key"];
+
+// Settings keys holding provider configuration JSON (see llm::provider_setting_key)
+const PROVIDER_SETTING_PREFIX: &str = "llm_provider";
+const PROVIDER_SECRET_SUFFIX: &str = ":api_key";
+
+static CIPHER_KEY: OnceLock<[u8; KEY_LEN]> = OnceLock::new();
+
+// 金鑰列表項目，不含內容
+#[derive(Debug, Serialize)]
+pub struct SecretInfo {
+    pub name: String,
+    pub updated_at: String,
+}
+
+// 這些名稱只能透過 secrets API 存取，get_setting / set_setting 不會回傳明文
+pub fn is_secret_name(name: &str) -> bool {
+    LEGACY_SECRET_SETTINGS.contains(&name) || name.ends_with(PROVIDER_SECRET_SUFFIX)
+}
+
+// 供應商設定中的 API 金鑰另存為 "<設定鍵>:api_key"
+pub fn provider_secret_name(setting_key: &str) -> String {
+    format!("{}{}", setting_key, PROVIDER_SECRET_SUFFIX)
+}
+
+fn key_path() -> Result<PathBuf, String> {
+    let app_dir = tauri::api::path::app_data_dir(&tauri::Config::default())
+        .ok_or_else(|| "Failed to get app data directory".to_string())?;
+    fs::create_dir_all(&app_dir).map_err(|e| e.to_string())?;
+    Ok(app_dir.join(KEY_FILE))
+}
+
+// 只有目前的使用者可以讀取金鑰檔
+fn create_key_file(path: &Path, key: &[u8]) -> std::io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    file.write_all(key)?;
+    file.sync_all()
+}
+
+fn read_key_file(path: &Path) -> Result<[u8; KEY_LEN], String> {
+    let bytes = fs::read(path).map_err(|e| format!("Failed to read secret key file: {}", e))?;
+    bytes.try_into().map_err(|_| format!("Secret key file {:?} is corrupt", path))
+}
+
+// 讀取金鑰檔，第一次使用時建立
+fn cipher_key() -> Result<[u8; KEY_LEN], String> {
+    if let Some(key) = CIPHER_KEY.get() {
+        return Ok(*key);
+    }
+
+    let path = key_path()?;
+    let key = if path.exists() {
+        read_key_file(&path)?
+    } else {
+        let key: [u8; KEY_LEN] = Aes256Gcm::generate_key(&mut OsRng).into();
+        match create_key_file(&path, &key) {
+            Ok(()) => {
+                println!("Created secret key file at {:?}", path);
+                key
+            },
+            // Another thread created it first; use theirs
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => read_key_file(&path)?,
+            Err(e) => return Err(format!("Failed to create secret key file: {}", e)),
+        }
+    };
+
+    Ok(*CIPHER_KEY.get_or_init(|| key))
+}
+
+fn cipher() -> Result<Aes256Gcm, String> {
+    let key = cipher_key()?;
+    Ok(Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)))
+}
+
+// The secret name is bound as associated data so a ciphertext cannot be moved to another name
+fn encrypt(name: &str, value: &str) -> Result<(Vec<u8>, Vec<u8>), String> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher()?
+        .encrypt(&nonce, Payload { msg: value.as_bytes(), aad: name.as_bytes() })
+        .map_err(|_| format!("Failed to encrypt secret {}", name))?;
+    Ok((nonce.to_vec(), ciphertext))
+}
+
+fn decrypt(name: &str, nonce: &[u8], ciphertext: &[u8]) -> Result<String, String> {
+    if nonce.len() != NONCE_LEN {
+        return Err(format!("Secret {} has an invalid nonce", name));
+    }
+    let plaintext = cipher()?
+        .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad: name.as_bytes() })
+        // Usually means the key file was replaced or the row was copied from another install
+        .map_err(|_| format!("Failed to decrypt secret {}; it may have been encrypted with a different key file", name))?;
+    String::from_utf8(plaintext).map_err(|_| format!("Secret {} is not valid UTF-8", name))
+}
+
+// 加密並保存
+pub fn set_secret(conn: &Connection, name: &str, value: &str) -> Result<(), AppError> {
+    let (nonce, ciphertext) = encrypt(name, value).map_err(AppError::Internal)?;
+    let now = Local::now().to_rfc3339();
+    conn.execute(
+        "INSERT INTO secrets (name, nonce, ciphertext, created_at, updated_at)
+         VALUES (?1, ?2, ?3, ?4, ?4)
+         ON CONFLICT(name) DO UPDATE SET nonce = ?2, ciphertext = ?3, updated_at = ?4",
+        params![name, nonce, ciphertext, now],
+    )?;
+    println!("Stored secret {}", name);
+    Ok(())
+}
+
+// 讀取並解密；呼叫端不可記錄回傳值
+pub fn get_secret(conn: &Connection, name: &str) -> Result<Option<String>, AppError> {
+    let row: Option<(Vec<u8>, Vec<u8>)> = conn.query_row(
+        "SELECT nonce, ciphertext FROM secrets WHERE name = ?1",
+        [name],
+        |row| Ok((row.get(0)?, row.get(1)?))
+    ).optional()?;
+
+    match row {
+        Some((nonce, ciphertext)) => decrypt(name, &nonce, &ciphertext).map(Some).map_err(AppError::Internal),
+        None => Ok(None),
+    }
+}
+
+pub fn has_secret(conn: &Connection, name: &str) -> Result<bool, AppError> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM secrets WHERE name = ?1",
+        [name],
+        |row| row.get(0)
+    )?;
+    Ok(count > 0)
+}
+
+pub fn delete_secret(conn: &Connection, name: &str) -> Result<bool, AppError> {
+    let deleted = conn.execute("DELETE FROM secrets WHERE name = ?1", [name])?;
+    Ok(deleted > 0)
+}
+
+// 移除供應商設定 JSON 中的 api_key；沒有金鑰時回傳 None
+pub fn strip_provider_api_key(config_json: &str) -> Option<(String, String)> {
+    let mut config: serde_json::Value = serde_json::from_str(config_json).ok()?;
+    let api_key = config.get("api_key")?.as_str()?.to_string();
+    config["api_key"] = serde_json::Value::Null;
+    Some((config.to_string(), api_key))
+}
+
+// 將 settings 表中的明文金鑰搬到 secrets 表 (遷移 v9 使用)
+pub fn move_plaintext_secrets(conn: &Connection) -> Result<usize, String> {
+    // Zero freed pages so the plaintext does not linger in the database file
+    conn.pragma_update(None, "secure_delete", true).map_err(|e| e.to_string())?;
+
+    let mut moved = 0;
+    for name in LEGACY_SECRET_SETTINGS {
+        let value: Option<String> = conn.query_row(
+            "SELECT value FROM settings WHERE key = ?1",
+            [name],
+            |row| row.get(0)
+        ).optional().map_err(|e| e.to_string())?;
+        if let Some(value) = value {
+            if !value.is_empty() {
+                set_secret(conn, name, &value)?;
+                moved += 1;
+            }
+            conn.execute("DELETE FROM settings WHERE key = ?1", [name]).map_err(|e| e.to_string())?;
+        }
+    }
+
+    let provider_settings: Vec<(String, String)> = {
+        let mut stmt = conn.prepare("SELECT key, value FROM settings WHERE key LIKE ?1")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt.query_map([format!("{}%", PROVIDER_SETTING_PREFIX)], |row| Ok((row.get(0)?, row.get(1)?)))
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())?
+    };
+    for (key, value) in provider_settings {
+        if let Some((stripped, api_key)) = strip_provider_api_key(&value) {
+            if !api_key.is_empty() {
+                set_secret(conn, &provider_secret_name(&key), &api_key)?;
+                moved += 1;
+            }
+            conn.execute("UPDATE settings SET value = ?1 WHERE key = ?2", params![stripped, key])
+                .map_err(|e| e.to_string())?;
+        }
+    }
+
+    println!("Moved {} plaintext API keys into encrypted storage", moved);
+    Ok(moved)
+}
+
+// 從匯出的資料庫副本移除所有金鑰 (加密資料庫的副本使用相同密碼)
+pub fn redact_export(export_path: &Path, passphrase: Option<&str>) -> Result<(), String> {
+    let conn = crate::encryption::open_database(export_path, passphrase).map_err(|e| e.to_string())?;
+    conn.pragma_update(None, "secure_delete", true).map_err(|e| e.to_string())?;
+    conn.execute("DELETE FROM secrets", []).map_err(|e| e.to_string())?;
+    for name in LEGACY_SECRET_SETTINGS {
+        conn.execute("DELETE FROM settings WHERE key = ?1", [name]).map_err(|e| e.to_string())?;
+    }
+    // Rebuild the file so no freed page still holds key material
+    conn.execute_batch("VACUUM").map_err(|e| e.to_string())?;
+    println!("Removed secrets from export {:?}", export_path);
+    Ok(())
+}
+
+fn validate_name(name: &str) -> Result<(), AppError> {
+    if name.trim().is_empty() {
+        return Err(AppError::validation("Secret name cannot be empty"));
+    }
+    Ok(())
+}
+
+// 保存金鑰 (加密)
+#[tauri::command]
+pub async fn set_secret_value(name: String, value: String, state: tauri::State<'_, SqliteState>) -> Result<bool, AppError> {
+    validate_name(&name)?;
+    if value.is_empty() {
+        return Err(AppError::validation("Secret value cannot be empty; use delete_secret_value to remove it"));
+    }
+    state.run(move |conn| {
+        set_secret(conn, &name, &value)?;
+        Ok(true)
+    }).await
+}
+
+// 檢查金鑰是否存在，不回傳內容
+#[tauri::command]
+pub async fn has_secret_value(name: String, state: tauri::State<'_, SqliteState>) -> Result<bool, AppError> {
+    validate_name(&name)?;
+    state.run(move |conn| has_secret(conn, &name)).await
+}
+
+#[tauri::command]
+pub async fn delete_secret_value(name: String, state: tauri::State<'_, SqliteState>) -> Result<bool, AppError> {
+    validate_name(&name)?;
+    state.run(move |conn| {
+        let deleted = delete_secret(conn, &name)?;
+        println!("Deleted secret {}: {}", name, deleted);
+        Ok(deleted)
+    }).await
+}
+
+// 列出已保存的金鑰名稱
+#[tauri::command]
+pub async fn list_secrets(state: tauri::State<'_, SqliteState>) -> Result<Vec<SecretInfo>, AppError> {
+    state.run(|conn| {
+        let mut stmt = conn.prepare("SELECT name, updated_at FROM secrets ORDER BY name")?;
+        let secrets = stmt.query_map([], |row| {
+            Ok(SecretInfo { name: row.get(0)?, updated_at: row.get(1)? })
+        })?
+        .collect::<Result<Vec<_>, _>>()?;
+        Ok(secrets)
+    }).await
+}
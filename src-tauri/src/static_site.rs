@@ -0,0 +1,545 @@
+use chrono::{DateTime, FixedOffset, Local};
+use minijinja::{context, Environment};
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::SqliteState;
+use crate::db::{self, Blog, Project};
+use crate::epub_export::xml_escape;
+use crate::error::AppError;
+use crate::export_common::{default_export_path, detect_language};
+use crate::markdown_export::{excerpt, markdown_to_xhtml, parse_timestamp, slugify, split_keywords, strip_tags, strip_title_heading};
+
+// RSS / Atom 只放最新的幾篇
+const FEED_LIMIT: usize = 20;
+
+const BASE_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html lang="{{ site.language }}">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{% block title %}{{ site.title }}{% endblock %}</title>
+{% block description %}{% if site.description %}<meta name="description" content="{{ site.description }}">
+{% endif %}{% endblock %}<link rel="stylesheet" href="{{ root }}style.css">
+{% if site.has_feeds %}<link rel="alternate" type="application/rss+xml" title="{{ site.title }}" href="{{ root }}feed.xml">
+<link rel="alternate" type="application/atom+xml" title="{{ site.title }}" href="{{ root }}atom.xml">
+{% endif %}{% block head %}{% endblock %}
+</head>
+<body>
+<header class="site-header">
+<a class="site-title" href="{{ root }}index.html">{{ site.title }}</a>
+<nav><a href="{{ root }}tags/index.html">Tags</a>{% if site.has_feeds %} <a href="{{ root }}feed.xml">RSS</a>{% endif %}</nav>
+</header>
+<main>
+{% block content %}{% endblock %}
+</main>
+<footer class="site-footer">
+<p>&copy; {{ site.year }} {{ site.author }}</p>
+</footer>
+</body>
+</html>
+"#;
+
+const INDEX_TEMPLATE: &str = r#"{% extends "base.html" %}
+{% block content %}
+{% if site.description %}<p class="site-description">{{ site.description }}</p>{% endif %}
+{% for post in posts %}
+<article class="post-summary">
+<h2><a href="{{ root }}{{ post.url }}">{{ post.title }}</a></h2>
+<p class="meta"><time datetime="{{ post.date_iso }}">{{ post.date }}</time>{% for tag in post.tags %} <a class="tag" href="{{ root }}{{ tag.url }}">#{{ tag.name }}</a>{% endfor %}</p>
+{% if post.excerpt %}<p>{{ post.excerpt }}</p>{% endif %}
+</article>
+{% endfor %}
+{% endblock %}
+"#;
+
+const POST_TEMPLATE: &str = r#"{% extends "base.html" %}
+{% block title %}{{ post.title }} - {{ site.title }}{% endblock %}
+{% block description %}<meta name="description" content="{{ post.excerpt or site.description }}">
+{% endblock %}
+{% block head %}{% if post.permalink %}<link rel="canonical" href="{{ post.permalink }}">
+{% endif %}{% endblock %}
+{% block content %}
+<article class="post">
+<h1>{{ post.title }}</h1>
+<p class="meta"><time datetime="{{ post.date_iso }}">{{ post.date }}</time>{% for tag in post.tags %} <a class="tag" href="{{ root }}{{ tag.url }}">#{{ tag.name }}</a>{% endfor %}</p>
+{{ post.content|safe }}
+</article>
+<nav class="post-nav">
+{% if newer %}<a class="newer" href="{{ root }}{{ newer.url }}">&larr; {{ newer.title }}</a>{% endif %}
+{% if older %}<a class="older" href="{{ root }}{{ older.url }}">{{ older.title }} &rarr;</a>{% endif %}
+</nav>
+{% endblock %}
+"#;
+
+const TAG_TEMPLATE: &str = r#"{% extends "base.html" %}
+{% block title %}#{{ tag.name }} - {{ site.title }}{% endblock %}
+{% block content %}
+<h1>#{{ tag.name }}</h1>
+<ul class="post-list">
+{% for post in posts %}<li><time datetime="{{ post.date_iso }}">{{ post.date }}</time> <a href="{{ root }}{{ post.url }}">{{ post.title }}</a></li>
+{% endfor %}</ul>
+{% endblock %}
+"#;
+
+const TAGS_TEMPLATE: &str = r#"{% extends "base.html" %}
+{% block title %}Tags - {{ site.title }}{% endblock %}
+{% block content %}
+<h1>Tags</h1>
+<ul class="tag-list">
+{% for tag in tags %}<li><a href="{{ root }}{{ tag.url }}">#{{ tag.name }}</a> ({{ tag.count }})</li>
+{% endfor %}</ul>
+{% endblock %}
+"#;
+
+const STYLESHEET: &str = "body { font-family: Georgia, serif; line-height: 1.6; max-width: 42em; margin: 0 auto; padding: 0 1em; color: #222; }
+a { color: #1a5fb4; }
+.site-header { display: flex; justify-content: space-between; align-items: baseline; border-bottom: 1px solid #ddd; padding: 1em 0; }
+.site-header nav a { margin-left: 1em; }
+.site-title { font-size: 1.4em; font-weight: bold; text-decoration: none; color: inherit; }
+.site-footer { border-top: 1px solid #ddd; margin-top: 3em; color: #777; font-size: 0.9em; }
+.meta { color: #777; font-size: 0.9em; }
+.tag { margin-left: 0.5em; }
+.post-summary { margin: 2em 0; }
+.post-nav { display: flex; justify-content: space-between; margin-top: 3em; }
+blockquote { margin: 1em 0; padding-left: 1em; border-left: 3px solid #ddd; color: #555; }
+pre { background: #f5f5f5; padding: 1em; overflow-x: auto; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #ccc; padding: 0.25em 0.5em; }
+img { max-width: 100%; }
+";
+
+// 可自訂的範本與預設內容；style.css 原樣輸出，其他以 Jinja 語法渲染
+const TEMPLATES: [(&str, &str); 6] = [
+    ("base.html", BASE_TEMPLATE),
+    ("index.html", INDEX_TEMPLATE),
+    ("post.html", POST_TEMPLATE),
+    ("tag.html", TAG_TEMPLATE),
+    ("tags.html", TAGS_TEMPLATE),
+    ("style.css", STYLESHEET),
+];
+
+#[derive(Debug, Serialize)]
+pub struct SiteTemplate {
+    pub name: String,
+    pub content: String,
+    // false 表示使用內建預設範本
+    pub customized: bool,
+    pub updated_at: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct StaticSiteOptions {
+    // 未指定時寫到 exports 目錄
+    pub output_dir: Option<String>,
+    // 網站的公開網址；RSS、Atom 與 sitemap.xml 需要絕對網址
+    pub base_url: Option<String>,
+    pub author: Option<String>,
+    pub language: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StaticSiteResult {
+    pub output_dir: String,
+    pub files: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct TagLink {
+    name: String,
+    url: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct PostLink {
+    title: String,
+    url: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct PostView {
+    id: i64,
+    title: String,
+    url: String,
+    permalink: Option<String>,
+    date: String,
+    date_iso: String,
+    updated_iso: String,
+    excerpt: String,
+    tags: Vec<TagLink>,
+    content: String,
+}
+
+#[derive(Debug, Serialize)]
+struct TagView {
+    name: String,
+    slug: String,
+    url: String,
+    count: usize,
+    #[serde(skip)]
+    posts: Vec<usize>,
+}
+
+#[derive(Debug, Serialize)]
+struct SiteView {
+    title: String,
+    description: String,
+    author: String,
+    language: String,
+    base_url: Option<String>,
+    has_feeds: bool,
+    year: String,
+}
+
+fn default_template(name: &str) -> Option<&'static str> {
+    TEMPLATES.iter().find(|(n, _)| *n == name).map(|(_, content)| *content)
+}
+
+fn load_custom_templates(conn: &Connection, project_id: i64) -> Result<HashMap<String, (String, String)>, AppError> {
+    let mut stmt = conn.prepare("SELECT name, content, updated_at FROM site_templates WHERE project_id = ?1")?;
+    let rows = stmt.query_map([project_id], |row| {
+        Ok((row.get::<_, String>(0)?, (row.get::<_, String>(1)?, row.get::<_, String>(2)?)))
+    })?;
+    Ok(rows.collect::<Result<HashMap<_, _>, _>>()?)
+}
+
+// 自訂範本優先，其餘使用預設
+fn resolve_templates(conn: &Connection, project_id: i64) -> Result<Vec<SiteTemplate>, AppError> {
+    let mut custom = load_custom_templates(conn, project_id)?;
+    Ok(TEMPLATES.iter().map(|(name, default)| match custom.remove(*name) {
+        Some((content, updated_at)) => SiteTemplate { name: name.to_string(), content, customized: true, updated_at: Some(updated_at) },
+        None => SiteTemplate { name: name.to_string(), content: default.to_string(), customized: false, updated_at: None },
+    }).collect())
+}
+
+fn template_error(e: minijinja::Error) -> AppError {
+    AppError::validation(format!("Template error: {}", e))
+}
+
+fn build_environment(templates: &[SiteTemplate]) -> Result<Environment<'_>, AppError> {
+    let mut env = Environment::new();
+    for template in templates.iter().filter(|t| t.name.ends_with(".html")) {
+        env.add_template(&template.name, &template.content).map_err(template_error)?;
+    }
+    Ok(env)
+}
+
+fn normalize_base_url(base_url: &Option<String>) -> Result<Option<String>, AppError> {
+    let url = match base_url.as_deref().map(str::trim) {
+        Some(url) if !url.is_empty() => url,
+        _ => return Ok(None),
+    };
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        return Err(AppError::validation("Base URL must start with http:// or https://"));
+    }
+    Ok(Some(format!("{}/", url.trim_end_matches('/'))))
+}
+
+// 網址中的非 ASCII 字元 (例如中文 slug) 需要百分比編碼
+fn absolute_url(base_url: &str, path: &str) -> String {
+    let mut url = base_url.to_string();
+    for byte in path.bytes() {
+        if byte.is_ascii_graphic() {
+            url.push(byte as char);
+        } else {
+            url.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    url
+}
+
+fn default_output_dir(state: &SqliteState, project: &Project) -> Result<PathBuf, AppError> {
+    default_export_path(state, "site_", &project.title, &format!("project-{}", project.id.unwrap_or_default()), "")
+}
+
+fn write_file(output_dir: &Path, relative: &str, content: &str, files: &mut Vec<String>) -> Result<(), AppError> {
+    let path = output_dir.join(relative);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, content)?;
+    files.push(path.to_string_lossy().to_string());
+    Ok(())
+}
+
+// 文章依發佈時間由新到舊，並決定網址與標籤
+fn build_posts(blogs: &[Blog], base_url: &Option<String>) -> (Vec<PostView>, Vec<TagView>) {
+    let now = Local::now().fixed_offset();
+    let mut dated: Vec<(&Blog, DateTime<FixedOffset>, DateTime<FixedOffset>)> = blogs.iter().map(|blog| {
+        let created = parse_timestamp(&blog.created_at).unwrap_or(now);
+        let updated = parse_timestamp(&blog.updated_at).unwrap_or(created);
+        (blog, created, updated)
+    }).collect();
+    dated.sort_by(|a, b| b.1.cmp(&a.1).then(b.0.id.cmp(&a.0.id)));
+
+    // 網址依發佈先後決定，新文章不會改變舊文章的網址
+    let mut used_slugs = HashSet::new();
+    let mut slugs = HashMap::new();
+    for (blog, _, _) in dated.iter().rev() {
+        let mut slug = slugify(&blog.title);
+        if slug.is_empty() {
+            slug = format!("post-{}", blog.id);
+        }
+        // A suffixed slug can still match another post's title (e.g. "Hello 6"), so keep trying
+        let base = slug.clone();
+        let mut attempt = 1;
+        while !used_slugs.insert(slug.clone()) {
+            slug = if attempt == 1 { format!("{}-{}", base, blog.id) } else { format!("{}-{}-{}", base, blog.id, attempt) };
+            attempt += 1;
+        }
+        slugs.insert(blog.id, slug);
+    }
+
+    // 標籤以 slug 合併，名稱取第一次出現的寫法
+    let mut tags: BTreeMap<String, TagView> = BTreeMap::new();
+    let mut posts = Vec::new();
+    for (index, (blog, created, updated)) in dated.iter().enumerate() {
+        let url = format!("posts/{}.html", slugs[&blog.id]);
+
+        let mut post_tags = Vec::new();
+        for name in split_keywords(&blog.keywords) {
+            let mut tag_slug = slugify(&name);
+            if tag_slug.is_empty() {
+                continue;
+            }
+            if tag_slug == "index" {
+                tag_slug.push_str("-tag");
+            }
+            let tag = tags.entry(tag_slug.clone()).or_insert_with(|| TagView {
+                url: format!("tags/{}.html", tag_slug),
+                name: name.clone(),
+                slug: tag_slug.clone(),
+                count: 0,
+                posts: Vec::new(),
+            });
+            if tag.posts.last() != Some(&index) {
+                tag.posts.push(index);
+                tag.count += 1;
+                post_tags.push(TagLink { name: tag.name.clone(), url: tag.url.clone() });
+            }
+        }
+
+        let body = strip_title_heading(&blog.content, &blog.title);
+        posts.push(PostView {
+            id: blog.id,
+            title: blog.title.clone(),
+            permalink: base_url.as_ref().map(|base| absolute_url(base, &url)),
+            url,
+            date: created.format("%Y-%m-%d").to_string(),
+            date_iso: created.to_rfc3339(),
+            updated_iso: updated.to_rfc3339(),
+            excerpt: strip_tags(&excerpt(body)),
+            tags: post_tags,
+            content: markdown_to_xhtml(body, 1, false),
+        });
+    }
+    (posts, tags.into_values().collect())
+}
+
+fn rss_feed(site: &SiteView, base_url: &str, posts: &[PostView]) -> String {
+    let mut items = String::new();
+    for post in posts.iter().take(FEED_LIMIT) {
+        let link = absolute_url(base_url, &post.url);
+        let pub_date = DateTime::parse_from_rfc3339(&post.date_iso).map(|d| d.to_rfc2822()).unwrap_or_default();
+        items.push_str(&format!(
+            "<item>\n<title>{title}</title>\n<link>{link}</link>\n<guid isPermaLink=\"true\">{link}</guid>\n<pubDate>{date}</pubDate>\n{categories}<description>{content}</description>\n</item>\n",
+            title = xml_escape(&post.title),
+            link = xml_escape(&link),
+            date = pub_date,
+            categories = post.tags.iter().map(|t| format!("<category>{}</category>\n", xml_escape(&t.name))).collect::<String>(),
+            content = xml_escape(&post.content),
+        ));
+    }
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<rss version=\"2.0\" xmlns:atom=\"http://www.w3.org/2005/Atom\">\n<channel>\n<title>{title}</title>\n<link>{base}</link>\n<description>{description}</description>\n<language>{language}</language>\n<lastBuildDate>{built}</lastBuildDate>\n<atom:link href=\"{base}feed.xml\" rel=\"self\" type=\"application/rss+xml\"/>\n{items}</channel>\n</rss>\n",
+        title = xml_escape(&site.title),
+        base = xml_escape(base_url),
+        description = xml_escape(if site.description.is_empty() { &site.title } else { &site.description }),
+        language = xml_escape(&site.language),
+        built = Local::now().to_rfc2822(),
+        items = items,
+    )
+}
+
+fn atom_feed(site: &SiteView, base_url: &str, posts: &[PostView]) -> String {
+    let updated = posts.iter().take(FEED_LIMIT).map(|p| p.updated_iso.clone()).max().unwrap_or_else(|| Local::now().to_rfc3339());
+    let mut entries = String::new();
+    for post in posts.iter().take(FEED_LIMIT) {
+        let link = absolute_url(base_url, &post.url);
+        entries.push_str(&format!(
+            "<entry>\n<title>{title}</title>\n<link href=\"{link}\"/>\n<id>{link}</id>\n<published>{published}</published>\n<updated>{updated}</updated>\n{categories}<summary>{summary}</summary>\n<content type=\"html\">{content}</content>\n</entry>\n",
+            title = xml_escape(&post.title),
+            link = xml_escape(&link),
+            published = post.date_iso,
+            updated = post.updated_iso,
+            categories = post.tags.iter().map(|t| format!("<category term=\"{}\"/>\n", xml_escape(&t.name))).collect::<String>(),
+            summary = xml_escape(&post.excerpt),
+            content = xml_escape(&post.content),
+        ));
+    }
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<feed xmlns=\"http://www.w3.org/2005/Atom\" xml:lang=\"{language}\">\n<title>{title}</title>\n<link href=\"{base}\"/>\n<link href=\"{base}atom.xml\" rel=\"self\"/>\n<id>{base}</id>\n<updated>{updated}</updated>\n<author><name>{author}</name></author>\n{entries}</feed>\n",
+        language = xml_escape(&site.language),
+        title = xml_escape(&site.title),
+        base = xml_escape(base_url),
+        updated = updated,
+        author = xml_escape(&site.author),
+        entries = entries,
+    )
+}
+
+fn sitemap(base_url: &str, posts: &[PostView], tags: &[TagView]) -> String {
+    let url_entry = |path: &str, lastmod: Option<&str>| match lastmod {
+        Some(date) => format!("<url><loc>{}</loc><lastmod>{}</lastmod></url>\n", xml_escape(&absolute_url(base_url, path)), date),
+        None => format!("<url><loc>{}</loc></url>\n", xml_escape(&absolute_url(base_url, path))),
+    };
+    let latest = posts.iter().map(|p| p.updated_iso.clone()).max();
+    let mut urls = url_entry("", latest.as_deref());
+    for post in posts {
+        urls.push_str(&url_entry(&post.url, Some(&post.updated_iso)));
+    }
+    if !tags.is_empty() {
+        urls.push_str(&url_entry("tags/index.html", None));
+    }
+    for tag in tags {
+        urls.push_str(&url_entry(&tag.url, None));
+    }
+    format!("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n{}</urlset>\n", urls)
+}
+
+fn generate(state: &SqliteState, project: &Project, blogs: &[Blog], templates: &[SiteTemplate], options: &StaticSiteOptions) -> Result<StaticSiteResult, AppError> {
+    let base_url = normalize_base_url(&options.base_url)?;
+    let env = build_environment(templates)?;
+    let render = |name: &str, ctx: minijinja::Value| -> Result<String, AppError> {
+        env.get_template(name)
+            .and_then(|template| template.render(ctx))
+            .map_err(template_error)
+    };
+
+    let output_dir = match &options.output_dir {
+        Some(dir) if !dir.trim().is_empty() => PathBuf::from(dir),
+        _ => default_output_dir(state, project)?,
+    };
+    fs::create_dir_all(&output_dir)?;
+
+    let author = options.author.clone()
+        .filter(|a| !a.trim().is_empty())
+        .unwrap_or_else(|| project.title.clone());
+    let site = SiteView {
+        title: project.title.clone(),
+        description: project.description.clone().unwrap_or_default(),
+        author,
+        language: options.language.clone()
+            .filter(|l| !l.trim().is_empty())
+            .unwrap_or_else(|| detect_language(project)),
+        base_url: base_url.clone(),
+        has_feeds: base_url.is_some(),
+        year: Local::now().format("%Y").to_string(),
+    };
+
+    let (posts, tags) = build_posts(blogs, &base_url);
+    let mut files = Vec::new();
+    let mut warnings = Vec::new();
+
+    let html = render("index.html", context! { site => &site, root => "", posts => &posts, tags => &tags })?;
+    write_file(&output_dir, "index.html", &html, &mut files)?;
+
+    for (index, post) in posts.iter().enumerate() {
+        let link = |p: &PostView| PostLink { title: p.title.clone(), url: p.url.clone() };
+        let newer = index.checked_sub(1).map(|i| link(&posts[i]));
+        let older = posts.get(index + 1).map(link);
+        let html = render("post.html", context! { site => &site, root => "../", post => post, newer => newer, older => older })?;
+        write_file(&output_dir, &post.url, &html, &mut files)?;
+    }
+
+    for tag in &tags {
+        let tag_posts: Vec<&PostView> = tag.posts.iter().map(|&i| &posts[i]).collect();
+        let html = render("tag.html", context! { site => &site, root => "../", tag => tag, posts => tag_posts })?;
+        write_file(&output_dir, &tag.url, &html, &mut files)?;
+    }
+    let html = render("tags.html", context! { site => &site, root => "../", tags => &tags })?;
+    write_file(&output_dir, "tags/index.html", &html, &mut files)?;
+
+    let stylesheet = templates.iter().find(|t| t.name == "style.css").map(|t| t.content.as_str()).unwrap_or(STYLESHEET);
+    write_file(&output_dir, "style.css", stylesheet, &mut files)?;
+
+    match &base_url {
+        Some(base) => {
+            write_file(&output_dir, "feed.xml", &rss_feed(&site, base, &posts), &mut files)?;
+            write_file(&output_dir, "atom.xml", &atom_feed(&site, base, &posts), &mut files)?;
+            write_file(&output_dir, "sitemap.xml", &sitemap(base, &posts, &tags), &mut files)?;
+        },
+        None => warnings.push("No base URL set: RSS, Atom and sitemap.xml were not generated because they require absolute links".to_string()),
+    }
+    if tags.is_empty() {
+        warnings.push("No posts have keywords, so no tag pages were generated".to_string());
+    }
+
+    println!("Generated static site for project {} with {} posts and {} tags in {:?}", project.id.unwrap_or_default(), posts.len(), tags.len(), output_dir);
+    Ok(StaticSiteResult { output_dir: output_dir.to_string_lossy().to_string(), files, warnings })
+}
+
+// 取得專案的網站範本 (含預設)
+#[tauri::command]
+pub async fn get_site_templates(project_id: i64, state: tauri::State<'_, SqliteState>) -> Result<Vec<SiteTemplate>, AppError> {
+    state.run(move |conn| {
+        db::load_project(conn, project_id)?;
+        resolve_templates(conn, project_id)
+    }).await
+}
+
+// 儲存自訂範本；語法錯誤時不會寫入
+#[tauri::command]
+pub async fn save_site_template(project_id: i64, name: String, content: String, state: tauri::State<'_, SqliteState>) -> Result<SiteTemplate, AppError> {
+    if default_template(&name).is_none() {
+        return Err(AppError::validation(format!("Unknown site template: {}", name)));
+    }
+    if name.ends_with(".html") {
+        Environment::new().add_template(&name, &content).map_err(template_error)?;
+    }
+    state.run(move |conn| {
+        db::load_project(conn, project_id)?;
+        let now = Local::now().to_rfc3339();
+        conn.execute(
+            "INSERT OR REPLACE INTO site_templates (project_id, name, content, updated_at) VALUES (?1, ?2, ?3, ?4)",
+            params![project_id, name, content, now],
+        )?;
+        println!("Saved site template {} for project {}", name, project_id);
+        Ok(SiteTemplate { name, content, customized: true, updated_at: Some(now) })
+    }).await
+}
+
+// 還原為預設範本
+#[tauri::command]
+pub async fn reset_site_template(project_id: i64, name: String, state: tauri::State<'_, SqliteState>) -> Result<SiteTemplate, AppError> {
+    let default = default_template(&name)
+        .ok_or_else(|| AppError::validation(format!("Unknown site template: {}", name)))?;
+    state.run(move |conn| {
+        let removed = conn.execute("DELETE FROM site_templates WHERE project_id = ?1 AND name = ?2", params![project_id, name])?;
+        println!("Reset site template {} for project {} ({} rows removed)", name, project_id, removed);
+        Ok(SiteTemplate { name, content: default.to_string(), customized: false, updated_at: None })
+    }).await
+}
+
+// 將部落格專案產生為靜態網站
+#[tauri::command]
+pub async fn generate_static_site(project_id: i64, options: Option<StaticSiteOptions>, state: tauri::State<'_, SqliteState>) -> Result<StaticSiteResult, AppError> {
+    let pool = state.inner().clone();
+    let options = options.unwrap_or_default();
+    state.run(move |conn| {
+        let project = db::load_project(conn, project_id)?;
+        if project.type_ != "blog" {
+            return Err(AppError::validation("Static sites can only be generated for blog projects"));
+        }
+        let blogs = db::load_blogs(conn, project_id)?;
+        if blogs.is_empty() {
+            return Err(AppError::validation("This project has no blog posts to publish"));
+        }
+        let templates = resolve_templates(conn, project_id)?;
+        generate(&pool, &project, &blogs, &templates, &options)
+    }).await
+}
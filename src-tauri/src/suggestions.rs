@@ -0,0 +1,131 @@
+use rusqlite::{OptionalExtension, params};
+use serde::Serialize;
+use chrono::Local;
+
+use crate::SqliteState;
+use crate::ai_agent::AgentType;
+use crate::diff::{self, DiffHunk, DiffSegment, Granularity};
+use crate::error::AppError;
+
+// 單一修改區塊及其對應的 agent_suggestions 記錄
+#[derive(Debug, Serialize)]
+pub struct SuggestionHunk {
+    pub suggestion_id: i64,
+    pub status: String,
+    #[serde(flatten)]
+    pub hunk: DiffHunk,
+}
+
+// AI 建議與原文的比較結果
+#[derive(Debug, Serialize)]
+pub struct SuggestionDiff {
+    pub reasoning_id: i64,
+    pub granularity: Granularity,
+    pub segments: Vec<DiffSegment>,
+    pub hunks: Vec<SuggestionHunk>,
+}
+
+// 比較原文與 AI 建議 (inline_edit_text 或 generate_article_draft 的結果)，每個修改區塊存為一筆建議
+// Without a reasoning_id the latest reasoning of that agent for the blog is used
+#[tauri::command]
+pub async fn diff_suggestion(
+    original: String,
+    proposed: String,
+    blog_id: i64,
+    agent_type: AgentType,
+    reasoning_id: Option<i64>,
+    granularity: Option<Granularity>,
+    state: tauri::State<'_, SqliteState>
+) -> Result<SuggestionDiff, AppError> {
+    let granularity = granularity.unwrap_or_default();
+    println!("diff_suggestion called for blog {} ({:?}, {:?})", blog_id, agent_type, granularity);
+
+    state.run(move |conn| {
+        let reasoning_id = match reasoning_id {
+            Some(id) => id,
+            None => conn.query_row(
+                "SELECT id FROM agent_reasoning WHERE blog_id = ?1 AND agent_type = ?2 ORDER BY id DESC LIMIT 1",
+                params![blog_id, agent_type.to_string()],
+                |row| row.get::<_, i64>(0)
+            ).optional()?
+            .ok_or_else(|| AppError::NotFound(format!("No {} reasoning found for blog {}", agent_type.to_string(), blog_id)))?,
+        };
+
+        let hunks = diff::compute_hunks(&original, &proposed, granularity);
+        let segments = diff::diff_text(&original, &proposed, granularity);
+        let now = Local::now().to_rfc3339();
+
+        let tx = conn.unchecked_transaction()?;
+
+        // 重新比較時取代先前的區塊
+        tx.execute(
+            "DELETE FROM agent_suggestions WHERE reasoning_id = ?1 AND hunk_index IS NOT NULL",
+            [reasoning_id],
+        )?;
+
+        let mut suggestion_hunks = Vec::with_capacity(hunks.len());
+        for hunk in hunks {
+            tx.execute(
+                "INSERT INTO agent_suggestions (reasoning_id, suggestion, applied, hunk_index, original_text, start_offset, end_offset, status, created_at, updated_at)
+                 VALUES (?1, ?2, 0, ?3, ?4, ?5, ?6, 'pending', ?7, ?7)",
+                params![
+                    reasoning_id,
+                    hunk.replacement,
+                    hunk.index as i64,
+                    hunk.original,
+                    hunk.start as i64,
+                    hunk.end as i64,
+                    now
+                ],
+            )?;
+            suggestion_hunks.push(SuggestionHunk {
+                suggestion_id: tx.last_insert_rowid(),
+                status: "pending".to_string(),
+                hunk,
+            });
+        }
+
+        tx.commit()?;
+
+        println!("Stored {} suggestion hunks for reasoning {}", suggestion_hunks.len(), reasoning_id);
+        Ok(SuggestionDiff {
+            reasoning_id,
+            granularity,
+            segments,
+            hunks: suggestion_hunks,
+        })
+    }).await
+}
+
+// 將已接受的區塊套用到原文並回傳結果，由前端以 update_blog 保存
+#[tauri::command]
+pub async fn apply_suggestion_hunks(reasoning_id: i64, original: String, state: tauri::State<'_, SqliteState>) -> Result<String, AppError> {
+    println!("apply_suggestion_hunks called for reasoning {}", reasoning_id);
+
+    state.run(move |conn| {
+        let mut stmt = conn.prepare(
+            "SELECT start_offset, end_offset, COALESCE(original_text, ''), suggestion FROM agent_suggestions
+             WHERE reasoning_id = ?1 AND hunk_index IS NOT NULL AND applied = 1
+             ORDER BY start_offset ASC"
+        )?;
+
+        let rows = stmt.query_map([reasoning_id], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get::<_, String>(2)?, row.get::<_, String>(3)?))
+        })?;
+
+        let mut accepted = Vec::new();
+        for row in rows {
+            let (start, end, original_text, replacement) = row?;
+            let (start, end) = (start as usize, end as usize);
+            // The offsets only make sense against the text the diff was computed from
+            if original.get(start..end) != Some(original_text.as_str()) {
+                return Err(AppError::validation("The content has changed since this suggestion was created. Please compare again."));
+            }
+            accepted.push((start, end, replacement));
+        }
+
+        let result = diff::apply_hunks(&original, &accepted).map_err(AppError::Validation)?;
+        println!("Applied {} suggestion hunks for reasoning {}", accepted.len(), reasoning_id);
+        Ok(result)
+    }).await
+}
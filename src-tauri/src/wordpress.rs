@@ -0,0 +1,388 @@
+use async_trait::async_trait;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::SqliteState;
+use crate::db;
+use crate::error::AppError;
+use crate::publishing::{self, PlatformKind, PostStatus, PublishRequest, Publisher, RemotePost};
+use crate::secrets;
+
+pub const PLATFORM: &str = "wordpress";
+const LABEL: &str = "WordPress";
+
+#[derive(Debug, Serialize)]
+pub struct WordPressPublishResult {
+    pub post_id: i64,
+    pub url: String,
+    pub status: String,
+    // false 表示更新了先前發佈的文章
+    pub created: bool,
+}
+
+// 應用程式密碼以加密的 secret 保存 (使用者名稱一併保存)
+#[derive(Debug, Deserialize, Serialize)]
+struct Credentials {
+    username: String,
+    application_password: String,
+}
+
+pub fn credentials_secret_name(project_id: i64) -> String {
+    format!("{}:{}:credentials", PLATFORM, project_id)
+}
+
+fn load_credentials(conn: &Connection, project_id: i64) -> Result<Credentials, AppError> {
+    let value = secrets::get_secret(conn, &credentials_secret_name(project_id))?
+        .ok_or_else(|| AppError::ApiKeyMissing { provider: "WordPress".to_string() })?;
+    serde_json::from_str(&value).map_err(|e| AppError::Internal(format!("Stored WordPress credentials are invalid: {}", e)))
+}
+
+// REST API 的根網址，例如 https://example.com/wp-json/wp/v2
+fn api_base(wordpress_url: &Option<String>) -> Result<String, AppError> {
+    let url = wordpress_url.as_deref().map(str::trim).unwrap_or("");
+    if url.is_empty() {
+        return Err(AppError::validation("Set the WordPress site URL in the project settings before publishing"));
+    }
+    let site = publishing::http_url(url, "WordPress")?;
+    let site = site.trim_end_matches("/wp-json/wp/v2").trim_end_matches("/wp-json");
+    Ok(format!("{}/wp-json/wp/v2", site))
+}
+
+// WordPress 回傳的名稱經過 HTML 編碼
+fn decode_entities(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#039;", "'")
+        .replace("&#8217;", "’")
+        .replace("&amp;", "&")
+}
+
+fn http_error(status: u16, retry_after: Option<u64>, body: &str) -> AppError {
+    publishing::http_error(LABEL, status, retry_after, body)
+}
+
+struct WordPressClient {
+    base: String,
+    credentials: Credentials,
+    client: reqwest::Client,
+}
+
+impl WordPressClient {
+    fn new(base: String, credentials: Credentials) -> Result<Self, AppError> {
+        Ok(WordPressClient { base, credentials, client: publishing::http_client()? })
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        self.client.request(method, format!("{}/{}", self.base, path))
+            .basic_auth(&self.credentials.username, Some(&self.credentials.application_password))
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<(u16, Option<u64>, String), AppError> {
+        publishing::send(LABEL, request).await
+    }
+
+    async fn send_json(&self, request: reqwest::RequestBuilder) -> Result<Value, AppError> {
+        let (status, retry_after, body) = self.send(request).await?;
+        if !(200..300).contains(&status) {
+            return Err(http_error(status, retry_after, &body));
+        }
+        publishing::parse_json(LABEL, &body)
+    }
+
+    // 以名稱找出標籤或分類，不存在時建立 (taxonomy 為 "tags" 或 "categories")
+    async fn term_id(&self, taxonomy: &str, name: &str) -> Result<i64, AppError> {
+        let found = self.send_json(
+            self.request(reqwest::Method::GET, taxonomy).query(&[("search", name), ("per_page", "100")])
+        ).await?;
+        let existing = found.as_array().into_iter().flatten().find(|term| {
+            term.get("name").and_then(|n| n.as_str())
+                .map(|n| decode_entities(n).trim().to_lowercase() == name.trim().to_lowercase())
+                .unwrap_or(false)
+        });
+        if let Some(id) = existing.and_then(|term| term.get("id")).and_then(|id| id.as_i64()) {
+            return Ok(id);
+        }
+
+        let (status, retry_after, body) = self.send(
+            self.request(reqwest::Method::POST, taxonomy).json(&json!({ "name": name }))
+        ).await?;
+        let value: Value = serde_json::from_str(&body).unwrap_or(Value::Null);
+        if (200..300).contains(&status) {
+            if let Some(id) = value.get("id").and_then(|id| id.as_i64()) {
+                println!("Created WordPress {} '{}' ({})", taxonomy, name, id);
+                return Ok(id);
+            }
+        } else if value.get("code").and_then(|c| c.as_str()) == Some("term_exists") {
+            // The search did not match, e.g. because of different HTML encoding
+            if let Some(id) = value.pointer("/data/term_id").and_then(|id| id.as_i64()) {
+                return Ok(id);
+            }
+        }
+        Err(http_error(status, retry_after, &body).context(format!("Failed to create WordPress {} '{}'", taxonomy, name)))
+    }
+
+    // 建立文章，或更新已發佈的文章；遠端文章已刪除時重新建立
+    async fn save_post(&self, remote_id: Option<i64>, post: &Value) -> Result<(Value, bool), AppError> {
+        if let Some(id) = remote_id {
+            let (status, retry_after, body) = self.send(
+                self.request(reqwest::Method::POST, &format!("posts/{}", id)).json(post)
+            ).await?;
+            match status {
+                200..=299 => {
+                    return Ok((publishing::parse_json(LABEL, &body)?, false));
+                },
+                404 | 410 => println!("WordPress post {} no longer exists; creating a new post", id),
+                _ => return Err(http_error(status, retry_after, &body)),
+            }
+        }
+        let value = self.send_json(self.request(reqwest::Method::POST, "posts").json(post)).await?;
+        Ok((value, true))
+    }
+}
+
+// 取得已保存的使用者名稱 (不回傳密碼)
+#[tauri::command]
+pub async fn get_wordpress_username(project_id: i64, state: tauri::State<'_, SqliteState>) -> Result<Option<String>, AppError> {
+    state.run(move |conn| {
+        match secrets::get_secret(conn, &credentials_secret_name(project_id))? {
+            Some(value) => Ok(serde_json::from_str::<Credentials>(&value).ok().map(|c| c.username)),
+            None => Ok(None),
+        }
+    }).await
+}
+
+// 保存專案的 WordPress 使用者名稱與應用程式密碼
+#[tauri::command]
+pub async fn set_wordpress_credentials(project_id: i64, username: String, application_password: String, state: tauri::State<'_, SqliteState>) -> Result<bool, AppError> {
+    if username.trim().is_empty() || application_password.trim().is_empty() {
+        return Err(AppError::validation("WordPress username and application password are required"));
+    }
+    state.run(move |conn| {
+        db::load_project(conn, project_id)?;
+        let credentials = Credentials { username: username.trim().to_string(), application_password: application_password.trim().to_string() };
+        secrets::set_secret(conn, &credentials_secret_name(project_id), &serde_json::to_string(&credentials)?)?;
+        Ok(true)
+    }).await
+}
+
+#[tauri::command]
+pub async fn delete_wordpress_credentials(project_id: i64, state: tauri::State<'_, SqliteState>) -> Result<bool, AppError> {
+    state.run(move |conn| {
+        let deleted = secrets::delete_secret(conn, &credentials_secret_name(project_id))?;
+        println!("Deleted WordPress credentials for project {}: {}", project_id, deleted);
+        Ok(deleted)
+    }).await
+}
+
+pub struct WordPressPublisher {
+    client: WordPressClient,
+}
+
+impl WordPressPublisher {
+    pub(crate) fn new(conn: &Connection, project_id: i64, site_url: &Option<String>) -> Result<Self, AppError> {
+        let base = api_base(site_url)?;
+        let credentials = load_credentials(conn, project_id)?;
+        Ok(WordPressPublisher { client: WordPressClient::new(base, credentials)? })
+    }
+}
+
+#[async_trait]
+impl Publisher for WordPressPublisher {
+    fn platform(&self) -> PlatformKind {
+        PlatformKind::WordPress
+    }
+
+    async fn publish(&self, post: &PublishRequest, remote_id: Option<&str>) -> Result<RemotePost, AppError> {
+        let client = &self.client;
+        let mut tags = Vec::new();
+        for keyword in &post.tags {
+            tags.push(client.term_id("tags", keyword).await?);
+        }
+        let mut categories = Vec::new();
+        for category in &post.categories {
+            categories.push(client.term_id("categories", category).await?);
+        }
+
+        let mut body = json!({
+            "title": post.title,
+            "content": post.html,
+            "excerpt": post.excerpt,
+            "status": post.status.as_str(),
+            "tags": tags,
+        });
+        // 沒有分類時使用網站的預設分類
+        if !categories.is_empty() {
+            body["categories"] = json!(categories);
+        }
+        // WordPress 核心沒有 canonical 欄位，寫入常見 SEO 外掛的 meta (未註冊的 meta 會被忽略)
+        if let Some(canonical_url) = &post.canonical_url {
+            body["meta"] = json!({ "_yoast_wpseo_canonical": canonical_url, "rank_math_canonical_url": canonical_url });
+        }
+
+        let remote_id = remote_id.and_then(|id| id.parse::<i64>().ok());
+        let (response, created) = client.save_post(remote_id, &body).await?;
+        let post_id = response.get("id").and_then(|id| id.as_i64())
+            .ok_or_else(|| AppError::Parse("WordPress response has no post id".to_string()))?;
+        Ok(RemotePost {
+            id: post_id.to_string(),
+            url: response.get("link").and_then(|l| l.as_str()).unwrap_or_default().to_string(),
+            status: response.get("status").and_then(|s| s.as_str()).unwrap_or(post.status.as_str()).to_string(),
+            created,
+        })
+    }
+}
+
+// 將文章發佈到專案設定的 WordPress 網站 (草稿或公開)；再次發佈時更新同一篇文章
+#[tauri::command]
+pub async fn publish_blog_to_wordpress(blog_id: i64, status: Option<PostStatus>, state: tauri::State<'_, SqliteState>) -> Result<WordPressPublishResult, AppError> {
+    let result = publishing::publish_blog_to(&state, blog_id, Some(PlatformKind::WordPress), status).await?;
+    let post_id = result.remote_id.parse::<i64>()
+        .map_err(|_| AppError::Parse(format!("Invalid WordPress post id: {}", result.remote_id)))?;
+    Ok(WordPressPublishResult { post_id, url: result.url, status: result.status, created: result.created })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{MockResponse, MockServer};
+
+    fn client(server: &MockServer) -> WordPressClient {
+        let credentials = Credentials { username: "editor".to_string(), application_password: "abcd efgh".to_string() };
+        WordPressClient::new(api_base(&Some(server.url.clone())).unwrap(), credentials).unwrap()
+    }
+
+    #[test]
+    fn api_base_requires_https_except_for_localhost() {
+        assert_eq!(api_base(&Some("https://example.com/".to_string())).unwrap(), "https://example.com/wp-json/wp/v2");
+        assert_eq!(api_base(&Some("https://example.com/wp-json".to_string())).unwrap(), "https://example.com/wp-json/wp/v2");
+        assert_eq!(api_base(&Some("http://localhost:8080".to_string())).unwrap(), "http://localhost:8080/wp-json/wp/v2");
+        assert!(api_base(&Some("http://127.0.0.1/blog".to_string())).is_ok());
+        assert!(api_base(&Some("http://[::1]:8000".to_string())).is_ok());
+
+        for url in ["http://example.com", "http://localhost.example.com", "ftp://example.com", "example.com", ""] {
+            assert!(matches!(api_base(&Some(url.to_string())), Err(AppError::Validation(_))), "{}", url);
+        }
+        assert!(matches!(api_base(&None), Err(AppError::Validation(_))));
+    }
+
+    #[tokio::test]
+    async fn term_id_uses_an_existing_term() {
+        let server = MockServer::start(vec![
+            MockResponse::json(200, json!([{ "id": 3, "name": "Rust &amp; Tauri" }, { "id": 4, "name": "Rust" }])),
+        ]);
+        assert_eq!(client(&server).term_id("tags", "rust & tauri").await.unwrap(), 3);
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, "GET");
+        assert!(requests[0].path.starts_with("/wp-json/wp/v2/tags?search=rust"));
+        // editor:abcd efgh
+        assert_eq!(requests[0].header("authorization"), Some("Basic ZWRpdG9yOmFiY2QgZWZnaA=="));
+    }
+
+    #[tokio::test]
+    async fn term_id_creates_a_missing_term() {
+        let server = MockServer::start(vec![
+            MockResponse::json(200, json!([])),
+            MockResponse::json(201, json!({ "id": 12, "name": "Travel" })),
+        ]);
+        assert_eq!(client(&server).term_id("categories", "Travel").await.unwrap(), 12);
+
+        let requests = server.requests();
+        assert_eq!(requests[1].method, "POST");
+        assert_eq!(requests[1].path, "/wp-json/wp/v2/categories");
+        assert_eq!(requests[1].json(), json!({ "name": "Travel" }));
+    }
+
+    #[tokio::test]
+    async fn term_id_falls_back_to_term_exists() {
+        let server = MockServer::start(vec![
+            MockResponse::json(200, json!([])),
+            MockResponse::json(400, json!({ "code": "term_exists", "message": "A term with the name provided already exists.", "data": { "status": 400, "term_id": 7 } })),
+        ]);
+        assert_eq!(client(&server).term_id("tags", "Café").await.unwrap(), 7);
+    }
+
+    #[tokio::test]
+    async fn term_id_reports_other_errors() {
+        let server = MockServer::start(vec![
+            MockResponse::json(200, json!([])),
+            MockResponse::json(403, json!({ "code": "rest_cannot_create", "message": "Sorry, you are not allowed to create terms." })),
+        ]);
+        match client(&server).term_id("tags", "News").await {
+            Err(AppError::Upstream { message, status: Some(403) }) => {
+                assert!(message.starts_with("Failed to create WordPress tags 'News'"));
+                assert!(message.contains("Sorry, you are not allowed to create terms."));
+            },
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn save_post_creates_a_new_post() {
+        let server = MockServer::start(vec![MockResponse::json(201, json!({ "id": 101, "link": "https://example.com/?p=101" }))]);
+        let (value, created) = client(&server).save_post(None, &json!({ "title": "Hello" })).await.unwrap();
+        assert!(created);
+        assert_eq!(value["id"], 101);
+
+        let requests = server.requests();
+        assert_eq!(requests[0].method, "POST");
+        assert_eq!(requests[0].path, "/wp-json/wp/v2/posts");
+        assert_eq!(requests[0].json(), json!({ "title": "Hello" }));
+    }
+
+    #[tokio::test]
+    async fn save_post_updates_an_existing_post() {
+        let server = MockServer::start(vec![MockResponse::json(200, json!({ "id": 55 }))]);
+        let (value, created) = client(&server).save_post(Some(55), &json!({ "title": "Edited" })).await.unwrap();
+        assert!(!created);
+        assert_eq!(value["id"], 55);
+        assert_eq!(server.requests()[0].path, "/wp-json/wp/v2/posts/55");
+    }
+
+    #[tokio::test]
+    async fn save_post_recreates_a_deleted_post() {
+        for status in [404, 410] {
+            let server = MockServer::start(vec![
+                MockResponse::json(status, json!({ "code": "rest_post_invalid_id", "message": "Invalid post ID." })),
+                MockResponse::json(201, json!({ "id": 77 })),
+            ]);
+            let (value, created) = client(&server).save_post(Some(55), &json!({ "title": "Again" })).await.unwrap();
+            assert!(created);
+            assert_eq!(value["id"], 77);
+
+            let paths: Vec<String> = server.requests().into_iter().map(|r| r.path).collect();
+            assert_eq!(paths, vec!["/wp-json/wp/v2/posts/55", "/wp-json/wp/v2/posts"]);
+        }
+    }
+
+    #[tokio::test]
+    async fn errors_map_to_app_errors() {
+        let server = MockServer::start(vec![
+            MockResponse::json(401, json!({ "code": "rest_not_logged_in", "message": "You are not currently logged in." })),
+            MockResponse::json(429, json!({ "message": "Too many requests" })).header("Retry-After", "30"),
+            MockResponse::text(502, "text/html", "<html>Bad gateway</html>"),
+            MockResponse::text(200, "text/html", "<html>not json</html>"),
+        ]);
+        let client = client(&server);
+
+        match client.save_post(Some(1), &json!({})).await {
+            Err(AppError::Upstream { message, status: Some(401) }) => assert!(message.contains("rejected the credentials")),
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match client.save_post(None, &json!({})).await {
+            Err(AppError::RateLimited { retry_after: Some(30), quota_exhausted: false, .. }) => {},
+            other => panic!("unexpected result: {:?}", other),
+        }
+        match client.save_post(None, &json!({})).await {
+            Err(error @ AppError::Upstream { status: Some(502), .. }) => {
+                assert!(error.retryable());
+                assert!(error.to_string().contains("Bad gateway"));
+            },
+            other => panic!("unexpected result: {:?}", other),
+        }
+        assert!(matches!(client.save_post(None, &json!({})).await, Err(AppError::Parse(_))));
+    }
+}